@@ -1,3 +1,46 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+const FRENCH_WORDS: &str = "resources/french_words.txt";
+
 fn main() {
+    front_code_words();
     tauri_build::build()
 }
+
+// Writes the word list to OUT_DIR/french_words.bin, each word as the length in bytes
+// of the prefix it shares with the previous word, the length of the rest and the rest
+fn front_code_words() {
+    println!("cargo:rerun-if-changed={}", FRENCH_WORDS);
+    let text = fs::read_to_string(FRENCH_WORDS).unwrap();
+    let words = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+    let mut encoded = Vec::new();
+    let mut previous = "";
+    for word in words {
+        assert!(
+            previous < word,
+            "{} is not sorted or repeated at {}",
+            FRENCH_WORDS,
+            word
+        );
+        let prefix_len = previous
+            .char_indices()
+            .zip(word.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8());
+        let rest = &word.as_bytes()[prefix_len..];
+        encoded.push(u8::try_from(prefix_len).unwrap());
+        encoded.push(u8::try_from(rest.len()).unwrap());
+        encoded.extend_from_slice(rest);
+        previous = word;
+    }
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("french_words.bin"), encoded).unwrap();
+}
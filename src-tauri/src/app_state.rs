@@ -1,18 +1,54 @@
-use std::{fs, path::PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
+use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
 
-use crate::{debug_println, language::Language, settings::Settings};
+use crate::{
+    language::Language,
+    pause_timer::PauseTimer,
+    settings::Settings,
+    utils::move_into_dir,
+    window_engine::{
+        accents::AccentOptions, candidates::CandidatePicks, snippets::Snippet, EngineOptions,
+    },
+};
+
+pub const STATE_FILE_NAME: &str = "state.json";
+
+// Earlier versions saved the state at the config dir path itself, it is moved into
+// the dir so that the other files can be saved there
+pub fn move_legacy_state(config_dir: &Path) {
+    if !config_dir.is_file() {
+        return;
+    }
+    match move_into_dir(config_dir, STATE_FILE_NAME) {
+        Ok(()) => info!("App state moved into the config dir"),
+        Err(error) => error!(error:%; "App state not moved into the config dir"),
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct AppState {
     pub language: Language,
     pub settings: Settings,
+    // Set while the engine is paused, not saved
+    #[serde(skip)]
+    pub pause: PauseTimer,
+    // Saved in their own file
+    #[serde(skip)]
+    pub snippets: Vec<Snippet>,
+    // Counted by the engine, saved in their own file
+    #[serde(skip)]
+    pub candidate_picks: CandidatePicks,
 }
 
 impl AppState {
     pub fn load(path: PathBuf) -> Self {
         if !path.is_file() {
+            info!(path:?; "No app state yet, using the defaults");
             let app_data = Self::default();
             let app_data_json = serde_json::to_string(&app_data).unwrap();
             let _ = fs::write(&path, app_data_json);
@@ -22,8 +58,9 @@ impl AppState {
         let app_data_json = fs::read_to_string(&path).unwrap();
         let r = serde_json::from_str(&app_data_json);
 
-        if let Ok(settings) = r {
-            return settings;
+        match r {
+            Ok(settings) => return settings,
+            Err(error) => warn!(error:%; "Invalid app state, reset to the defaults"),
         }
 
         let app_data = Self::default();
@@ -32,9 +69,22 @@ impl AppState {
         app_data
     }
 
+    pub fn engine_options(&self) -> EngineOptions {
+        let options = self.settings.engine_options();
+        EngineOptions {
+            snippets: self.snippets.clone(),
+            accents: AccentOptions {
+                picks: self.candidate_picks.clone(),
+                ..options.accents
+            },
+            ..options
+        }
+    }
+
     pub fn save(&self, path: PathBuf) {
         let app_data_json = serde_json::to_string(&self).unwrap();
-        let r = fs::write(path, app_data_json);
-        debug_println!("{:?}", r);
+        if let Err(error) = fs::write(path, app_data_json) {
+            error!(error:%; "App state not saved");
+        }
     }
 }
\ No newline at end of file
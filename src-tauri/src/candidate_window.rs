@@ -0,0 +1,84 @@
+use std::sync::Mutex;
+
+use tauri::{
+    AppHandle, Emitter, Manager, PhysicalPosition, WebviewUrl, WebviewWindow, WebviewWindowBuilder,
+};
+
+use crate::osd::{caret_rect, osd_origin, work_area, OsdPosition};
+
+pub const CANDIDATES_WINDOW: &str = "candidates";
+const CANDIDATES_WIDTH: f64 = 320.0;
+const CANDIDATES_HEIGHT: f64 = 44.0;
+
+// The spellings the candidate window shows, empty while it is hidden
+#[derive(Debug, Default)]
+pub struct Candidates {
+    candidates: Vec<String>,
+}
+
+impl Candidates {
+    pub fn candidates(&self) -> Vec<String> {
+        self.candidates.clone()
+    }
+}
+
+fn candidate_window(app_handle: &AppHandle) -> tauri::Result<WebviewWindow> {
+    if let Some(window) = app_handle.get_webview_window(CANDIDATES_WINDOW) {
+        return Ok(window);
+    }
+
+    let window = WebviewWindowBuilder::new(app_handle, CANDIDATES_WINDOW, WebviewUrl::default())
+        .title("clavfrancais")
+        .inner_size(CANDIDATES_WIDTH, CANDIDATES_HEIGHT)
+        .decorations(false)
+        .transparent(true)
+        .shadow(false)
+        .resizable(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .focused(false)
+        .visible(false)
+        .build()?;
+    // The keys must keep reaching the application, it never takes the focus
+    window.set_ignore_cursor_events(true)?;
+    Ok(window)
+}
+
+// Shows `candidates` under the caret, or hides the window when there are none
+pub fn show_candidates(app_handle: &AppHandle, candidates: Vec<String>) {
+    {
+        let state = app_handle.state::<Mutex<Candidates>>();
+        let mut state = state.lock().unwrap();
+        if state.candidates.is_empty() && candidates.is_empty() {
+            return;
+        }
+        state.candidates = candidates.clone();
+    }
+
+    let handle = app_handle.clone();
+    let _ = app_handle.run_on_main_thread(move || {
+        if candidates.is_empty() {
+            if let Some(window) = handle.get_webview_window(CANDIDATES_WINDOW) {
+                let _ = window.hide();
+            }
+            return;
+        }
+
+        let Ok(window) = candidate_window(&handle) else {
+            return;
+        };
+        let Some(work_area) = work_area() else {
+            return;
+        };
+        let caret = caret_rect();
+        let size = window
+            .outer_size()
+            .map(|size| (size.width as i32, size.height as i32))
+            .unwrap_or((CANDIDATES_WIDTH as i32, CANDIDATES_HEIGHT as i32));
+
+        let (x, y) = osd_origin(OsdPosition::Caret, work_area, caret, size);
+        let _ = window.set_position(PhysicalPosition::new(x, y));
+        let _ = window.show();
+        let _ = handle.emit_to(CANDIDATES_WINDOW, "show_candidates", candidates);
+    });
+}
@@ -1,12 +1,21 @@
 use std::sync::Mutex;
+use std::time::Duration;
 
 use tauri::{AppHandle, Builder, Manager, Wry};
 
 use crate::{
     app_state::AppState,
-    controllers::{change_language, disable_run_on_startup, enable_run_on_startup, quit, toggle_language},
+    candidate_window::Candidates,
+    controllers::{
+        apply_settings, change_language, open_logs_folder, pause_engine, pause_remaining, quit,
+        remove_snippet, resume_engine, set_snippet, sync_engine, toggle_language, usage_stats,
+    },
     language::Language,
+    osd::{Osd, OsdMessage},
     settings::Settings,
+    stats::Stats,
+    tray_menu::refresh_tray,
+    window_engine::{engine_event::EngineStatus, snippets::Snippet},
 };
 
 #[tauri::command]
@@ -37,27 +46,89 @@ pub fn quit_command(app_handle: AppHandle) {
     quit(&app_handle);
 }
 
+#[tauri::command]
+pub fn get_engine_status_command(app_handle: AppHandle) -> EngineStatus {
+    let engine_status = app_handle.state::<Mutex<EngineStatus>>();
+    let engine_status = engine_status.lock().unwrap();
+    *engine_status
+}
+
+// `duration` in seconds
+#[tauri::command]
+pub fn pause_engine_command(app_handle: AppHandle, duration: u64) {
+    pause_engine(&app_handle, Duration::from_secs(duration));
+}
+
+#[tauri::command]
+pub fn resume_engine_command(app_handle: AppHandle) {
+    resume_engine(&app_handle);
+}
+
+#[tauri::command]
+pub fn get_pause_remaining_command(app_handle: AppHandle) -> Option<u64> {
+    pause_remaining(&app_handle)
+}
+
+// The message the indicator window shows when it loads
+#[tauri::command]
+pub fn get_osd_command(app_handle: AppHandle) -> Option<OsdMessage> {
+    let osd = app_handle.state::<Mutex<Osd>>();
+    let osd = osd.lock().unwrap();
+    osd.message()
+}
+
+// The spellings the candidate window shows when it loads
+#[tauri::command]
+pub fn get_candidates_command(app_handle: AppHandle) -> Vec<String> {
+    let candidates = app_handle.state::<Mutex<Candidates>>();
+    let candidates = candidates.lock().unwrap();
+    candidates.candidates()
+}
+
+#[tauri::command]
+pub fn get_stats_command(app_handle: AppHandle) -> Stats {
+    usage_stats(&app_handle)
+}
+
+#[tauri::command]
+pub fn open_logs_folder_command(app_handle: AppHandle) -> Result<(), String> {
+    open_logs_folder(&app_handle)
+}
+
 #[tauri::command]
 pub fn get_settings_command(app_handle: AppHandle) -> Settings {
     let app_state = app_handle.state::<Mutex<AppState>>();
     let app_state = app_state.lock().unwrap();
-    app_state.settings
+    app_state.settings.clone()
 }
 
 #[tauri::command]
 pub fn set_settings_command(app_handle: AppHandle, settings: Settings) {
+    {
+        let app_state = app_handle.state::<Mutex<AppState>>();
+        let mut app_state = app_state.lock().unwrap();
+        app_state.settings = settings;
+        apply_settings(&app_handle, &app_state);
+    }
+    sync_engine(&app_handle);
+    refresh_tray(&app_handle);
+}
+
+#[tauri::command]
+pub fn get_snippets_command(app_handle: AppHandle) -> Vec<Snippet> {
     let app_state = app_handle.state::<Mutex<AppState>>();
-    let mut app_state = app_state.lock().unwrap();
-    app_state.settings = settings;
+    let app_state = app_state.lock().unwrap();
+    app_state.snippets.clone()
+}
 
-    if settings.run_on_startup {
-        enable_run_on_startup(&app_handle);
-    } else {
-        disable_run_on_startup(&app_handle);
-    }
+#[tauri::command]
+pub fn set_snippet_command(app_handle: AppHandle, snippet: Snippet) -> Result<(), String> {
+    set_snippet(&app_handle, snippet)
+}
 
-    let path = app_handle.path().app_config_dir().unwrap();
-    app_state.save(path);
+#[tauri::command]
+pub fn remove_snippet_command(app_handle: AppHandle, abbreviation: String) {
+    remove_snippet(&app_handle, &abbreviation);
 }
 
 pub trait RegisterCommands {
@@ -72,7 +143,18 @@ impl RegisterCommands for Builder<Wry> {
             quit_command,
             get_settings_command,
             set_settings_command,
-            toggle_language_command
+            toggle_language_command,
+            get_engine_status_command,
+            pause_engine_command,
+            resume_engine_command,
+            get_pause_remaining_command,
+            get_osd_command,
+            get_candidates_command,
+            get_stats_command,
+            open_logs_folder_command,
+            get_snippets_command,
+            set_snippet_command,
+            remove_snippet_command
         ])
     }
 }
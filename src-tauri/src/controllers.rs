@@ -1,19 +1,43 @@
-use crate::window_engine::WindowEngine;
+use crate::window_engine::{
+    engine_event::{EngineEvent, EngineStatus},
+    EngineOptions, WindowEngine,
+};
 use clavfrancais_engine::{char_buffer::StackSizedCharBuffer, engine::setup_key_combination_map};
-use std::{sync::Mutex, thread};
-use tauri::{tray::TrayIconId, AppHandle, Emitter, Manager};
+use log::{info, warn};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::{
+    mpsc::{Receiver, Sender},
+    Mutex,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_autostart::ManagerExt;
 
 use crate::{
-    app_state::AppState,
-    debug_println,
+    app_state::{AppState, STATE_FILE_NAME},
+    candidate_window::show_candidates,
     language::Language,
-    tray_menu::{get_icon_image, TRAY_ICON_ID},
+    logging::{apply_log_options, log_engine_event},
+    osd::show_osd,
+    pause_timer::SystemClock,
+    pick_store::{save_picks, PICKS_FILE_NAME},
+    snippet_store::{save_snippets, upsert_snippet, SNIPPETS_FILE_NAME},
+    sound_cues::{Cue, SoundCues},
+    stats::{is_counted, today, Stats, StatsRecorder},
+    tray_menu::refresh_tray,
+    utils::config_file_path,
+    window_engine::snippets::Snippet,
 };
 
 pub fn change_language(app_handle: &AppHandle, language: Language) {
     let app_state = app_handle.state::<Mutex<AppState>>();
     let mut app_state = app_state.lock().unwrap();
+    info!(language:?; "Language changed");
+    // Picking a language ends a pause
+    app_state.pause.resume();
 
     if language == Language::English {
         app_state.language = Language::English;
@@ -23,15 +47,180 @@ pub fn change_language(app_handle: &AppHandle, language: Language) {
         let _ = app_handle.emit("change_language", Language::French);
     };
 
-    if let Some(tray_icon) = app_handle.tray_by_id(&TrayIconId::new(TRAY_ICON_ID)) {
-        let _ = tray_icon.set_icon(Some(get_icon_image(app_state.language)));
+    let osd_options = app_state.settings.osd_options();
+    drop(app_state);
+    sync_engine(app_handle);
+    let stats = app_handle.state::<Mutex<StatsRecorder>>();
+    stats
+        .lock()
+        .unwrap()
+        .change_language(&today(), language, Instant::now());
+    refresh_tray(app_handle);
+    show_osd(app_handle, language, osd_options);
+    play_cue(app_handle, Cue::language(language));
+}
+
+// Stops the engine until `duration` elapsed, at most a day, or the user resumes it
+pub fn pause_engine(app_handle: &AppHandle, duration: Duration) {
+    let duration = {
+        let app_state = app_handle.state::<Mutex<AppState>>();
+        let mut app_state = app_state.lock().unwrap();
+        if app_state.language != Language::French {
+            return;
+        }
+        let Some(paused) = app_state.pause.pause(&SystemClock, duration) else {
+            return;
+        };
+        info!(secs = paused.as_secs(); "Engine paused");
+        paused
+    };
+    sync_engine(app_handle);
+    let _ = app_handle.emit("pause_changed", Some(duration.as_secs()));
+    refresh_tray(app_handle);
+
+    let app_handle = app_handle.clone();
+    thread::spawn(move || {
+        thread::sleep(duration);
+        // A later pause or a resume replaced this one
+        let due = {
+            let app_state = app_handle.state::<Mutex<AppState>>();
+            let app_state = app_state.lock().unwrap();
+            app_state.pause.is_due(&SystemClock)
+        };
+        if due {
+            resume_engine(&app_handle);
+        }
+    });
+}
+
+// Seconds left before the engine resumes, None when not paused
+pub fn pause_remaining(app_handle: &AppHandle) -> Option<u64> {
+    let app_state = app_handle.state::<Mutex<AppState>>();
+    let app_state = app_state.lock().unwrap();
+    app_state
+        .pause
+        .remaining(&SystemClock)
+        .map(|remaining| remaining.as_secs())
+}
+
+pub fn resume_engine(app_handle: &AppHandle) {
+    {
+        let app_state = app_handle.state::<Mutex<AppState>>();
+        let mut app_state = app_state.lock().unwrap();
+        if !app_state.pause.resume() {
+            return;
+        }
+        info!("Engine resumed");
+    }
+    sync_engine(app_handle);
+    let _ = app_handle.emit("pause_changed", None::<u64>);
+    refresh_tray(app_handle);
+}
+
+// Adds `app` to the apps the engine leaves alone
+pub fn exclude_app(app_handle: &AppHandle, app: String) {
+    {
+        let app_state = app_handle.state::<Mutex<AppState>>();
+        let mut app_state = app_state.lock().unwrap();
+        if app_state.settings.excluded_apps.contains(&app) {
+            return;
+        }
+        info!(app = app.as_str(); "App excluded");
+        app_state.settings.excluded_apps.push(app);
+        apply_settings(app_handle, &app_state);
+    }
+    sync_engine(app_handle);
+    let _ = app_handle.emit("settings_changed", ());
+    refresh_tray(app_handle);
+}
+
+pub fn set_run_on_startup(app_handle: &AppHandle, run_on_startup: bool) {
+    {
+        let app_state = app_handle.state::<Mutex<AppState>>();
+        let mut app_state = app_state.lock().unwrap();
+        app_state.settings.run_on_startup = run_on_startup;
+        apply_settings(app_handle, &app_state);
     }
+    sync_engine(app_handle);
+    let _ = app_handle.emit("settings_changed", ());
+    refresh_tray(app_handle);
+}
+
+// Applies and saves the settings in `app_state`, which the caller keeps locked.
+// The engine is left to `sync_engine`, called once the lock is dropped
+pub fn apply_settings(app_handle: &AppHandle, app_state: &AppState) {
+    apply_log_options(app_state.settings.log_options());
+    info!("Settings applied");
 
-    if language == Language::French {
-        start_engine();
+    if app_state.settings.run_on_startup {
+        enable_run_on_startup(app_handle);
     } else {
-        stop_engine();
+        disable_run_on_startup(app_handle);
+    }
+
+    let sound_cues = app_handle.state::<Mutex<SoundCues>>();
+    sound_cues
+        .lock()
+        .unwrap()
+        .set_options(app_state.settings.sound_options());
+
+    app_state.save(state_path(app_handle));
+}
+
+// Adds `snippet` or replaces the one with the same abbreviation
+pub fn set_snippet(app_handle: &AppHandle, snippet: Snippet) -> Result<(), String> {
+    snippet.validate()?;
+    {
+        let app_state = app_handle.state::<Mutex<AppState>>();
+        let mut app_state = app_state.lock().unwrap();
+        upsert_snippet(&mut app_state.snippets, snippet);
+        save_snippets_of(app_handle, &app_state);
+    }
+    sync_engine(app_handle);
+    Ok(())
+}
+
+pub fn remove_snippet(app_handle: &AppHandle, abbreviation: &str) {
+    {
+        let app_state = app_handle.state::<Mutex<AppState>>();
+        let mut app_state = app_state.lock().unwrap();
+        app_state
+            .snippets
+            .retain(|snippet| snippet.abbreviation != abbreviation);
+        save_snippets_of(app_handle, &app_state);
     }
+    sync_engine(app_handle);
+}
+
+fn save_snippets_of(app_handle: &AppHandle, app_state: &AppState) {
+    let config_dir = app_handle.path().app_config_dir().unwrap();
+    save_snippets(
+        &config_file_path(&config_dir, SNIPPETS_FILE_NAME),
+        &app_state.snippets,
+    );
+}
+
+// The engine counts the picks in the ones the state shares with it
+fn save_picks_of(app_handle: &AppHandle) {
+    let picks = {
+        let app_state = app_handle.state::<Mutex<AppState>>();
+        let app_state = app_state.lock().unwrap();
+        app_state.candidate_picks.clone()
+    };
+    let config_dir = app_handle.path().app_config_dir().unwrap();
+    save_picks(&config_file_path(&config_dir, PICKS_FILE_NAME), &picks);
+}
+
+pub fn play_cue(app_handle: &AppHandle, cue: Cue) {
+    let sound_cues = app_handle.state::<Mutex<SoundCues>>();
+    sound_cues.lock().unwrap().play(cue);
+}
+
+// The stats with the time spent in the current language counted up to now
+pub fn usage_stats(app_handle: &AppHandle) -> Stats {
+    let stats = app_handle.state::<Mutex<StatsRecorder>>();
+    let mut stats = stats.lock().unwrap();
+    stats.stats(&today(), Instant::now())
 }
 
 pub fn toggle_language(app_handle: &AppHandle) {
@@ -47,34 +236,143 @@ pub fn toggle_language(app_handle: &AppHandle) {
     change_language(app_handle, new_language);
 }
 
-pub fn start_engine() {
-    thread::spawn(|| {
-        WindowEngine::start(
-            setup_key_combination_map(),
-            StackSizedCharBuffer::<30>::default(),
-        );
-    });
+// The running engine and the options it read when it started
+struct EngineSession {
+    engine: WindowEngine,
+    options: EngineOptions,
+}
+
+// Requests for the engine thread to bring the engine in line with the state
+pub struct EngineRequests(pub Sender<()>);
+
+// The options of the engine the state calls for, None when it must be stopped
+fn wanted_engine_options(app_state: &AppState) -> Option<EngineOptions> {
+    (app_state.language == Language::French && !app_state.pause.is_paused())
+        .then(|| app_state.engine_options())
+}
+
+// Has the engine started, stopped or restarted to match the state, without
+// waiting for it. The caller must not hold the AppState lock
+pub fn sync_engine(app_handle: &AppHandle) {
+    let _ = app_handle.state::<EngineRequests>().0.send(());
+}
+
+// Answers the requests of `sync_engine` one at a time, on a thread of its own
+// since stopping the engine joins its threads which takes a while. Nothing is
+// done when the engine already runs with the options the state calls for
+pub fn run_engine_requests(app_handle: &AppHandle, requests: Receiver<()>) {
+    let mut session: Option<EngineSession> = None;
+    while requests.recv().is_ok() {
+        // Requests made meanwhile are answered by this one
+        requests.try_iter().for_each(drop);
+
+        let wanted = {
+            let app_state = app_handle.state::<Mutex<AppState>>();
+            let app_state = app_state.lock().unwrap();
+            wanted_engine_options(&app_state)
+        };
+        let up_to_date = match (session.as_ref(), wanted.as_ref()) {
+            (Some(session), Some(options)) => {
+                session.options == *options && session.engine.is_running()
+            }
+            (None, None) => true,
+            _ => false,
+        };
+        if up_to_date {
+            continue;
+        }
+
+        if let Some(mut previous) = session.take() {
+            if !previous.engine.stop() {
+                warn!("Engine threads did not exit in time");
+            }
+        }
+        if let Some(options) = wanted {
+            let events = app_handle.state::<Sender<EngineEvent>>().inner().clone();
+            let engine = WindowEngine::start(
+                setup_key_combination_map(),
+                StackSizedCharBuffer::<30>::default(),
+                options.clone(),
+                events,
+            );
+            session = Some(EngineSession { engine, options });
+        }
+    }
 }
 
-pub fn stop_engine() {
-    WindowEngine::stop();
+pub fn forward_engine_events(app_handle: &AppHandle, receiver: Receiver<EngineEvent>) {
+    for event in receiver {
+        log_engine_event(&event);
+        if let Some(status) = event.status() {
+            let engine_status = app_handle.state::<Mutex<EngineStatus>>();
+            *engine_status.lock().unwrap() = status;
+            refresh_tray(app_handle);
+            if status != EngineStatus::Running {
+                // Nothing answers the candidates once the engine is gone
+                show_candidates(app_handle, vec![]);
+            }
+        }
+        if let EngineEvent::Candidates { candidates } = &event {
+            show_candidates(app_handle, candidates.clone());
+        }
+        if event == EngineEvent::CandidatePicked {
+            save_picks_of(app_handle);
+        }
+        if let Some(cue) = Cue::from_event(&event) {
+            play_cue(app_handle, cue);
+        }
+        if is_counted(&event) {
+            let stats = app_handle.state::<Mutex<StatsRecorder>>();
+            stats
+                .lock()
+                .unwrap()
+                .record_event(&today(), &event, Instant::now());
+        }
+        let _ = app_handle.emit("engine_event", event);
+    }
+}
+
+fn state_path(app_handle: &AppHandle) -> PathBuf {
+    let config_dir = app_handle.path().app_config_dir().unwrap();
+    config_file_path(&config_dir, STATE_FILE_NAME)
 }
 
 pub fn quit(app_handle: &AppHandle) {
-    let path = app_handle.path().app_config_dir().unwrap();
     let app_state = app_handle.state::<Mutex<AppState>>();
     let app_state = app_state.lock().unwrap();
-    app_state.save(path);
+    app_state.save(state_path(app_handle));
+    let stats = app_handle.state::<Mutex<StatsRecorder>>();
+    stats.lock().unwrap().save(&today(), Instant::now());
+    info!("Quitting");
+    log::logger().flush();
     std::process::exit(0);
 }
 
+// Opens the folder of the log files in the file explorer
+pub fn open_logs_folder(app_handle: &AppHandle) -> Result<(), String> {
+    let dir = app_handle
+        .path()
+        .app_log_dir()
+        .map_err(|error| error.to_string())?;
+    fs::create_dir_all(&dir).map_err(|error| error.to_string())?;
+    Command::new("explorer")
+        .arg(&dir)
+        .spawn()
+        .map_err(|error| {
+            warn!(error:%; "Logs folder not opened");
+            error.to_string()
+        })?;
+    Ok(())
+}
+
 pub fn enable_run_on_startup(app_handle: &AppHandle) {
     let auto_start_manager = app_handle.autolaunch();
     if auto_start_manager.is_enabled().unwrap() {
         return;
     }
-    let r = auto_start_manager.enable();
-    debug_println!("{:?}", r);
+    if let Err(error) = auto_start_manager.enable() {
+        warn!(error:%; "Run on startup not enabled");
+    }
 }
 
 pub fn disable_run_on_startup(app_handle: &AppHandle) {
@@ -82,6 +380,7 @@ pub fn disable_run_on_startup(app_handle: &AppHandle) {
     if !auto_start_manager.is_enabled().unwrap() {
         return;
     }
-    let r = auto_start_manager.disable();
-    debug_println!("{:?}", r);
+    if let Err(error) = auto_start_manager.disable() {
+        warn!(error:%; "Run on startup not disabled");
+    }
 }
@@ -1,11 +1,20 @@
 mod app_state;
+mod candidate_window;
 mod commands;
 mod controllers;
 mod language;
+mod logging;
+mod osd;
+mod pause_timer;
+mod pick_store;
 mod plugins;
 mod settings;
 mod setup;
+mod snippet_store;
+mod sound_cues;
+mod stats;
 mod toggle_shortcut;
+mod tray_icon;
 mod tray_menu;
 mod utils;
 mod window_engine;
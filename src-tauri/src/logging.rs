@@ -0,0 +1,290 @@
+use std::fmt::Write as _;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use log::kv::{self, VisitSource};
+use log::{debug, error, info, trace, warn, LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+
+use crate::{utils::local_time, window_engine::engine_event::EngineEvent};
+
+const LOG_FILE_STEM: &str = "clavfrancais";
+// The current file is rotated once it reaches this size
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+// The current file included, older ones are removed
+const MAX_FILES: usize = 5;
+// Records of other crates, such as tauri, are only kept from this level
+const DEPENDENCY_LEVEL: LevelFilter = LevelFilter::Warn;
+
+// Set from the settings, read wherever typed text could be logged
+static TYPED_TEXT: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Default)]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub fn filter(&self) -> LevelFilter {
+        match self {
+            LogLevel::Off => LevelFilter::Off,
+            LogLevel::Error => LevelFilter::Error,
+            LogLevel::Warn => LevelFilter::Warn,
+            LogLevel::Info => LevelFilter::Info,
+            LogLevel::Debug => LevelFilter::Debug,
+            LogLevel::Trace => LevelFilter::Trace,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct LogOptions {
+    pub level: LogLevel,
+    // Typed characters and the text of the edits are left out otherwise
+    pub typed_text: bool,
+}
+
+pub fn apply_log_options(options: LogOptions) {
+    log::set_max_level(options.level.filter());
+    TYPED_TEXT.store(options.typed_text, Ordering::Relaxed);
+}
+
+// `text` typed or edited by the engine, only when the settings allow logging it
+pub fn typed(text: &str) -> Option<&str> {
+    TYPED_TEXT.load(Ordering::Relaxed).then_some(text)
+}
+
+pub fn log_file_path(dir: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        dir.join(format!("{}.log", LOG_FILE_STEM))
+    } else {
+        dir.join(format!("{}.{}.log", LOG_FILE_STEM, index))
+    }
+}
+
+// Shifts each file to the next index, the oldest of `max_files` is dropped
+// and the current one is free to be created again
+pub fn rotate(dir: &Path, max_files: usize) -> io::Result<()> {
+    let oldest = log_file_path(dir, max_files - 1);
+    if oldest.exists() {
+        fs::remove_file(oldest)?;
+    }
+    for index in (0..max_files - 1).rev() {
+        let path = log_file_path(dir, index);
+        if path.exists() {
+            fs::rename(path, log_file_path(dir, index + 1))?;
+        }
+    }
+    Ok(())
+}
+
+struct KeyValues(String);
+
+impl<'kvs> VisitSource<'kvs> for KeyValues {
+    fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        let _ = write!(self.0, " {}={}", key, value);
+        Ok(())
+    }
+}
+
+// One line per record: time, level, module, message then its key values
+pub fn format_record(timestamp: &str, record: &Record) -> String {
+    let mut key_values = KeyValues(String::new());
+    let _ = record.key_values().visit(&mut key_values);
+    format!(
+        "{} {:<5} {}: {}{}",
+        timestamp,
+        record.level(),
+        record.target(),
+        record.args(),
+        key_values.0
+    )
+}
+
+// The local time, such as `2024-05-31 14:03:07.250`
+fn timestamp() -> String {
+    let time = local_time();
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:03}",
+        time.year, time.month, time.day, time.hour, time.minute, time.second, time.millis
+    )
+}
+
+struct LogFile {
+    dir: PathBuf,
+    // Opened on the first record, and again after each rotation
+    file: Option<File>,
+    size: u64,
+}
+
+impl LogFile {
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if self.file.is_some() && self.size >= MAX_FILE_SIZE {
+            self.file = None;
+            rotate(&self.dir, MAX_FILES)?;
+        }
+
+        if self.file.is_none() {
+            fs::create_dir_all(&self.dir)?;
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(log_file_path(&self.dir, 0))?;
+            self.size = file.metadata()?.len();
+            self.file = Some(file);
+        }
+        if let Some(file) = &mut self.file {
+            writeln!(file, "{}", line)?;
+            self.size += line.len() as u64 + 1;
+        }
+        Ok(())
+    }
+}
+
+struct FileLogger {
+    file: Mutex<LogFile>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let own = metadata
+            .target()
+            .starts_with(module_path!().split("::").next().unwrap());
+        own || metadata.level() <= DEPENDENCY_LEVEL
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format_record(&timestamp(), record);
+        // The release build has no console
+        #[cfg(debug_assertions)]
+        println!("{}", line);
+        let _ = self.file.lock().unwrap().write_line(&line);
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &mut self.file.lock().unwrap().file {
+            let _ = file.flush();
+        }
+    }
+}
+
+// Writes the records to rotating files in `dir`, the app log dir
+pub fn init_logging(dir: PathBuf, options: LogOptions) {
+    let logger = FileLogger {
+        file: Mutex::new(LogFile {
+            dir,
+            file: None,
+            size: 0,
+        }),
+    };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        apply_log_options(options);
+    }
+}
+
+pub fn log_engine_event(event: &EngineEvent) {
+    match event {
+        EngineEvent::Started => info!("Engine started"),
+        EngineEvent::Stopped => info!("Engine stopped"),
+        EngineEvent::Crashed { message } => error!(message = message.as_str(); "Engine crashed"),
+        EngineEvent::HookInstalled => info!("Hook installed"),
+        EngineEvent::HookFailed { message } => error!(message = message.as_str(); "Hook failed"),
+        EngineEvent::HookLost => warn!("Hook lost"),
+        EngineEvent::Restarting { delay_ms } => warn!(delay_ms; "Engine restarting"),
+        EngineEvent::Buffer { content } => {
+            if let Some(content) = typed(content) {
+                trace!(content; "Buffer")
+            }
+        }
+        EngineEvent::Combination { kind, output } => {
+            debug!(kind:?, output = typed(output); "Combination")
+        }
+        EngineEvent::Undo { output } => debug!(output = typed(output); "Undo"),
+        EngineEvent::Snippet { output } => debug!(output = typed(output); "Snippet expanded"),
+        EngineEvent::AccentCompletion { output } => {
+            debug!(output = typed(output); "Accent completed")
+        }
+        EngineEvent::CandidatePicked => debug!("Candidate picked"),
+        EngineEvent::Candidates { candidates } => {
+            debug!(count = candidates.len(); "Candidates")
+        }
+        EngineEvent::SecureInput { active } => info!(active; "Secure input"),
+        EngineEvent::Latency {
+            samples,
+            p50_micros,
+            p95_micros,
+            max_micros,
+        } => debug!(samples, p50_micros, p95_micros, max_micros; "Latency"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use log::{Level, LevelFilter, Record};
+
+    use super::{format_record, log_file_path, rotate, LogLevel};
+
+    #[test]
+    fn test_format_record() {
+        let key_values = [("language", "French")];
+        let line = format_record(
+            "2024-05-31 14:03:07.250",
+            &Record::builder()
+                .args(format_args!("Language changed"))
+                .level(Level::Info)
+                .target("clavfrancais_lib::controllers")
+                .key_values(&key_values)
+                .build(),
+        );
+        assert_eq!(
+            line,
+            "2024-05-31 14:03:07.250 INFO  clavfrancais_lib::controllers: Language changed language=French"
+        );
+    }
+
+    #[test]
+    fn test_level_filter() {
+        assert_eq!(LogLevel::Off.filter(), LevelFilter::Off);
+        assert_eq!(LogLevel::default().filter(), LevelFilter::Info);
+        assert_eq!(LogLevel::Trace.filter(), LevelFilter::Trace);
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("clavfrancais-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_rotate() {
+        let dir = temp_dir("rotate");
+        for generation in 0..4 {
+            fs::write(log_file_path(&dir, 0), generation.to_string()).unwrap();
+            rotate(&dir, 3).unwrap();
+        }
+
+        assert!(!log_file_path(&dir, 0).exists());
+        assert_eq!(fs::read_to_string(log_file_path(&dir, 1)).unwrap(), "3");
+        assert_eq!(fs::read_to_string(log_file_path(&dir, 2)).unwrap(), "2");
+        assert!(!log_file_path(&dir, 3).exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
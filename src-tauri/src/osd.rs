@@ -0,0 +1,346 @@
+#[cfg(windows)]
+use std::mem::size_of;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{
+    AppHandle, Emitter, Manager, PhysicalPosition, WebviewUrl, WebviewWindow, WebviewWindowBuilder,
+};
+#[cfg(windows)]
+use windows::Win32::Foundation::{POINT, RECT};
+#[cfg(windows)]
+use windows::Win32::Graphics::Gdi::{
+    ClientToScreen, GetMonitorInfoW, MonitorFromWindow, MONITORINFO, MONITOR_DEFAULTTONEAREST,
+};
+#[cfg(windows)]
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetForegroundWindow, GetGUIThreadInfo, GetWindowThreadProcessId, GUITHREADINFO,
+};
+
+use crate::language::Language;
+
+pub const OSD_WINDOW: &str = "osd";
+const OSD_WIDTH: f64 = 180.0;
+const OSD_HEIGHT: f64 = 64.0;
+// Space between the indicator and the screen edges or the caret
+const MARGIN: i32 = 24;
+const CARET_GAP: i32 = 8;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Default)]
+pub enum OsdPosition {
+    #[default]
+    Off,
+    // Under the text caret, in the bottom right corner when the application has none
+    Caret,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct OsdOptions {
+    pub position: OsdPosition,
+    pub duration: Duration,
+    // Percent
+    pub opacity: u8,
+}
+
+// What the indicator window shows
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OsdMessage {
+    pub text: String,
+    pub opacity: u8,
+}
+
+#[derive(Debug, Default)]
+pub struct Osd {
+    message: Option<OsdMessage>,
+    // Counts the messages shown so an older timer does not hide a newer one
+    generation: u64,
+}
+
+impl Osd {
+    pub fn message(&self) -> Option<OsdMessage> {
+        self.message.clone()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Rect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+#[cfg(windows)]
+impl From<RECT> for Rect {
+    fn from(rect: RECT) -> Self {
+        Self {
+            left: rect.left,
+            top: rect.top,
+            right: rect.right,
+            bottom: rect.bottom,
+        }
+    }
+}
+
+// The top left corner of an indicator of `size` inside `work_area`
+pub fn osd_origin(
+    position: OsdPosition,
+    work_area: Rect,
+    caret: Option<Rect>,
+    size: (i32, i32),
+) -> (i32, i32) {
+    let (width, height) = size;
+    let left = work_area.left + MARGIN;
+    let right = work_area.right - MARGIN - width;
+    let top = work_area.top + MARGIN;
+    let bottom = work_area.bottom - MARGIN - height;
+
+    match (position, caret) {
+        (OsdPosition::Caret, Some(caret)) => {
+            // Above the caret when there is no room below
+            let y = if caret.bottom + CARET_GAP + height <= work_area.bottom {
+                caret.bottom + CARET_GAP
+            } else {
+                caret.top - CARET_GAP - height
+            };
+            let x = caret.left.min(work_area.right - width).max(work_area.left);
+            (x, y.max(work_area.top))
+        }
+        (OsdPosition::TopLeft, _) => (left, top),
+        (OsdPosition::TopRight, _) => (right, top),
+        (OsdPosition::BottomLeft, _) => (left, bottom),
+        _ => (right, bottom),
+    }
+}
+
+// The caret of the focused application in screen coordinates
+#[cfg(windows)]
+pub fn caret_rect() -> Option<Rect> {
+    unsafe { windows_caret_rect() }
+}
+
+// The work area of the monitor showing the focused application
+#[cfg(windows)]
+pub fn work_area() -> Option<Rect> {
+    unsafe { windows_work_area() }
+}
+
+// Other applications' windows are only looked into on Windows, the indicator
+// and the candidates are not shown elsewhere
+#[cfg(not(windows))]
+pub fn caret_rect() -> Option<Rect> {
+    None
+}
+
+#[cfg(not(windows))]
+pub fn work_area() -> Option<Rect> {
+    None
+}
+
+#[cfg(windows)]
+unsafe fn windows_caret_rect() -> Option<Rect> {
+    let thread_id = GetWindowThreadProcessId(GetForegroundWindow(), None);
+    let mut info = GUITHREADINFO {
+        cbSize: size_of::<GUITHREADINFO>() as u32,
+        ..Default::default()
+    };
+    if GetGUIThreadInfo(thread_id, &mut info).is_err() || info.hwndCaret.is_invalid() {
+        return None;
+    }
+
+    let mut top_left = POINT {
+        x: info.rcCaret.left,
+        y: info.rcCaret.top,
+    };
+    let mut bottom_right = POINT {
+        x: info.rcCaret.right,
+        y: info.rcCaret.bottom,
+    };
+    if !ClientToScreen(info.hwndCaret, &mut top_left).as_bool()
+        || !ClientToScreen(info.hwndCaret, &mut bottom_right).as_bool()
+    {
+        return None;
+    }
+
+    Some(Rect {
+        left: top_left.x,
+        top: top_left.y,
+        right: bottom_right.x,
+        bottom: bottom_right.y,
+    })
+}
+
+#[cfg(windows)]
+unsafe fn windows_work_area() -> Option<Rect> {
+    let monitor = MonitorFromWindow(GetForegroundWindow(), MONITOR_DEFAULTTONEAREST);
+    let mut info = MONITORINFO {
+        cbSize: size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    if !GetMonitorInfoW(monitor, &mut info).as_bool() {
+        return None;
+    }
+    Some(info.rcWork.into())
+}
+
+fn osd_window(app_handle: &AppHandle) -> tauri::Result<WebviewWindow> {
+    if let Some(window) = app_handle.get_webview_window(OSD_WINDOW) {
+        return Ok(window);
+    }
+
+    let window = WebviewWindowBuilder::new(app_handle, OSD_WINDOW, WebviewUrl::default())
+        .title("clavfrancais")
+        .inner_size(OSD_WIDTH, OSD_HEIGHT)
+        .decorations(false)
+        .transparent(true)
+        .shadow(false)
+        .resizable(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .focused(false)
+        .visible(false)
+        .build()?;
+    // Clicks reach the application under the indicator
+    window.set_ignore_cursor_events(true)?;
+    Ok(window)
+}
+
+// Shows `language` in the indicator window for the configured duration
+pub fn show_osd(app_handle: &AppHandle, language: Language, options: OsdOptions) {
+    if options.position == OsdPosition::Off {
+        return;
+    }
+
+    let message = OsdMessage {
+        text: language.name().to_string(),
+        opacity: options.opacity.min(100),
+    };
+    let generation = {
+        let osd = app_handle.state::<Mutex<Osd>>();
+        let mut osd = osd.lock().unwrap();
+        osd.message = Some(message.clone());
+        osd.generation += 1;
+        osd.generation
+    };
+
+    // Windows are created and moved on the main thread, commands and the
+    // toggle shortcut run on others
+    let handle = app_handle.clone();
+    let _ = app_handle.run_on_main_thread(move || {
+        let Ok(window) = osd_window(&handle) else {
+            return;
+        };
+        let Some(work_area) = work_area() else {
+            return;
+        };
+        let caret = if options.position == OsdPosition::Caret {
+            caret_rect()
+        } else {
+            None
+        };
+        let size = window
+            .outer_size()
+            .map(|size| (size.width as i32, size.height as i32))
+            .unwrap_or((OSD_WIDTH as i32, OSD_HEIGHT as i32));
+
+        let (x, y) = osd_origin(options.position, work_area, caret, size);
+        let _ = window.set_position(PhysicalPosition::new(x, y));
+        let _ = window.show();
+        let _ = handle.emit_to(OSD_WINDOW, "show_osd", message);
+    });
+
+    let app_handle = app_handle.clone();
+    thread::spawn(move || {
+        thread::sleep(options.duration);
+        {
+            let osd = app_handle.state::<Mutex<Osd>>();
+            let mut osd = osd.lock().unwrap();
+            if osd.generation != generation {
+                return;
+            }
+            osd.message = None;
+        }
+
+        let handle = app_handle.clone();
+        let _ = app_handle.run_on_main_thread(move || {
+            if let Some(window) = handle.get_webview_window(OSD_WINDOW) {
+                let _ = window.hide();
+            }
+        });
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::{osd_origin, OsdPosition, Rect, CARET_GAP, MARGIN};
+
+    const SCREEN: Rect = Rect {
+        left: 0,
+        top: 0,
+        right: 1920,
+        bottom: 1040,
+    };
+    const SIZE: (i32, i32) = (180, 64);
+
+    fn caret(left: i32, top: i32) -> Option<Rect> {
+        Some(Rect {
+            left,
+            top,
+            right: left + 1,
+            bottom: top + 20,
+        })
+    }
+
+    #[test]
+    fn test_corners() {
+        let table = [
+            (OsdPosition::TopLeft, (MARGIN, MARGIN)),
+            (OsdPosition::TopRight, (1920 - MARGIN - 180, MARGIN)),
+            (OsdPosition::BottomLeft, (MARGIN, 1040 - MARGIN - 64)),
+            (
+                OsdPosition::BottomRight,
+                (1920 - MARGIN - 180, 1040 - MARGIN - 64),
+            ),
+        ];
+        for (position, expected) in table {
+            assert_eq!(osd_origin(position, SCREEN, None, SIZE), expected);
+        }
+    }
+
+    #[test]
+    fn test_under_the_caret() {
+        assert_eq!(
+            osd_origin(OsdPosition::Caret, SCREEN, caret(300, 200), SIZE),
+            (300, 220 + CARET_GAP)
+        );
+    }
+
+    #[test]
+    fn test_caret_near_the_edges() {
+        // No room below, shown above
+        assert_eq!(
+            osd_origin(OsdPosition::Caret, SCREEN, caret(300, 1000), SIZE),
+            (300, 1000 - CARET_GAP - 64)
+        );
+        // Kept inside the screen on the right
+        assert_eq!(
+            osd_origin(OsdPosition::Caret, SCREEN, caret(1900, 200), SIZE).0,
+            1920 - 180
+        );
+    }
+
+    #[test]
+    fn test_no_caret_falls_back_to_the_corner() {
+        assert_eq!(
+            osd_origin(OsdPosition::Caret, SCREEN, None, SIZE),
+            osd_origin(OsdPosition::BottomRight, SCREEN, None, SIZE)
+        );
+    }
+}
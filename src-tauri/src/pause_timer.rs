@@ -0,0 +1,146 @@
+use std::time::{Duration, Instant};
+
+// Longer pauses are shortened to this, the duration comes from the frontend
+pub const MAX_PAUSE: Duration = Duration::from_secs(24 * 60 * 60);
+
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+// When the paused engine resumes on its own
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PauseTimer {
+    until: Option<Instant>,
+}
+
+impl PauseTimer {
+    // A new pause replaces the running one. Returns how long the engine is paused
+    // for, None when the end of the pause cannot be represented
+    pub fn pause(&mut self, clock: &impl Clock, duration: Duration) -> Option<Duration> {
+        let duration = duration.min(MAX_PAUSE);
+        self.until = Some(clock.now().checked_add(duration)?);
+        Some(duration)
+    }
+
+    // Whether the engine was paused
+    pub fn resume(&mut self) -> bool {
+        self.until.take().is_some()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.until.is_some()
+    }
+
+    // None when not paused
+    pub fn remaining(&self, clock: &impl Clock) -> Option<Duration> {
+        self.until
+            .map(|until| until.saturating_duration_since(clock.now()))
+    }
+
+    pub fn is_due(&self, clock: &impl Clock) -> bool {
+        self.remaining(clock)
+            .is_some_and(|remaining| remaining.is_zero())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+    use std::time::{Duration, Instant};
+
+    use super::{Clock, PauseTimer, MAX_PAUSE};
+
+    struct MockClock(Cell<Instant>);
+
+    impl MockClock {
+        fn new() -> Self {
+            Self(Cell::new(Instant::now()))
+        }
+
+        fn advance(&self, duration: Duration) {
+            self.0.set(self.0.get() + duration);
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Instant {
+            self.0.get()
+        }
+    }
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn test_not_paused() {
+        let clock = MockClock::new();
+        let mut timer = PauseTimer::default();
+        assert!(!timer.is_paused());
+        assert!(!timer.is_due(&clock));
+        assert_eq!(timer.remaining(&clock), None);
+        assert!(!timer.resume());
+    }
+
+    #[test]
+    fn test_pause_runs_out() {
+        let clock = MockClock::new();
+        let mut timer = PauseTimer::default();
+        timer.pause(&clock, 5 * MINUTE);
+
+        clock.advance(2 * MINUTE);
+        assert!(timer.is_paused());
+        assert!(!timer.is_due(&clock));
+        assert_eq!(timer.remaining(&clock), Some(3 * MINUTE));
+
+        clock.advance(3 * MINUTE);
+        assert!(timer.is_due(&clock));
+        clock.advance(MINUTE);
+        assert!(timer.is_due(&clock));
+        assert_eq!(timer.remaining(&clock), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_long_pause_is_capped() {
+        let clock = MockClock::new();
+        let mut timer = PauseTimer::default();
+        assert_eq!(
+            timer.pause(&clock, Duration::from_secs(u64::MAX)),
+            Some(MAX_PAUSE)
+        );
+        assert_eq!(timer.remaining(&clock), Some(MAX_PAUSE));
+        assert_eq!(timer.pause(&clock, 5 * MINUTE), Some(5 * MINUTE));
+    }
+
+    #[test]
+    fn test_resume_early() {
+        let clock = MockClock::new();
+        let mut timer = PauseTimer::default();
+        timer.pause(&clock, 5 * MINUTE);
+
+        assert!(timer.resume());
+        clock.advance(5 * MINUTE);
+        assert!(!timer.is_paused());
+        assert!(!timer.is_due(&clock));
+    }
+
+    #[test]
+    fn test_new_pause_replaces_the_running_one() {
+        let clock = MockClock::new();
+        let mut timer = PauseTimer::default();
+        timer.pause(&clock, 5 * MINUTE);
+        clock.advance(4 * MINUTE);
+        timer.pause(&clock, 30 * MINUTE);
+
+        clock.advance(MINUTE);
+        assert!(!timer.is_due(&clock));
+        assert_eq!(timer.remaining(&clock), Some(29 * MINUTE));
+    }
+}
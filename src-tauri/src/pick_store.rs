@@ -0,0 +1,47 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use log::error;
+
+use crate::window_engine::candidates::CandidatePicks;
+
+pub const PICKS_FILE_NAME: &str = "candidate_picks.json";
+
+pub fn load_picks(path: &Path) -> CandidatePicks {
+    let Ok(picks_json) = fs::read_to_string(path) else {
+        return CandidatePicks::default();
+    };
+    CandidatePicks::new(serde_json::from_str(&picks_json).unwrap_or_default())
+}
+
+pub fn save_picks(path: &Path, picks: &CandidatePicks) {
+    // Sorted so that the file only changes where the counts did
+    let counts: BTreeMap<String, u32> = picks.counts().into_iter().collect();
+    let picks_json = serde_json::to_string_pretty(&counts).unwrap();
+    if let Err(error) = fs::write(path, picks_json) {
+        error!(error:%; "Candidate picks not saved");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::{load_picks, save_picks};
+    use crate::window_engine::candidates::CandidatePicks;
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!(
+            "clavfrancais-candidate-picks-{}.json",
+            std::process::id()
+        ));
+        let picks = CandidatePicks::default();
+        picks.record("où");
+        picks.record("Où");
+        save_picks(&path, &picks);
+        assert_eq!(load_picks(&path).counts(), picks.counts());
+        fs::remove_file(&path).unwrap();
+
+        assert!(load_picks(&path).counts().is_empty());
+    }
+}
@@ -1,11 +1,139 @@
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 
-use crate::toggle_shortcut::ToggleShortcut;
+use crate::{
+    logging::{LogLevel, LogOptions},
+    osd::{OsdOptions, OsdPosition},
+    sound_cues::{SoundOptions, SoundTheme},
+    toggle_shortcut::ToggleShortcut,
+    window_engine::{
+        accents::AccentOptions,
+        bypass::{BypassKey, BypassMode, BypassOptions},
+        candidates::CandidatePicks,
+        replacement::ReplacementMode,
+        trigger_rules::{PhysicalLayout, TriggerRule, TriggerRules},
+        undo::UndoKey,
+        word_boundary::{WordBoundary, DEFAULT_BOUNDARY_CHARS},
+        EngineOptions,
+    },
+};
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Default)]
-#[serde(rename_all = "camelCase")]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct Settings {
     pub run_on_startup: bool,
     pub hide_to_tray: bool,
     pub toggle_shortcut: ToggleShortcut,
+    pub word_boundaries: String,
+    pub apostrophe_elision: bool,
+    pub replacement_mode: ReplacementMode,
+    pub physical_layout: PhysicalLayout,
+    // Applied before the ones of the physical layout
+    pub trigger_rules: Vec<TriggerRule>,
+    pub bypass_key: BypassKey,
+    pub bypass_mode: BypassMode,
+    pub undo_key: UndoKey,
+    pub excluded_apps: Vec<String>,
+    pub accent_completion: bool,
+    pub accent_suggestions: bool,
+    // Accented words picked for unaccented spellings that have several
+    pub accent_preferences: Vec<String>,
+    pub osd_position: OsdPosition,
+    pub osd_duration_ms: u64,
+    // Percent
+    pub osd_opacity: u8,
+    pub language_sound: bool,
+    pub combination_sound: bool,
+    pub sound_theme: SoundTheme,
+    // Percent
+    pub sound_volume: u8,
+    pub log_level: LogLevel,
+    // Typed characters are only logged when turned on, and never in secure input
+    pub log_typed_text: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            run_on_startup: false,
+            hide_to_tray: false,
+            toggle_shortcut: ToggleShortcut::default(),
+            word_boundaries: DEFAULT_BOUNDARY_CHARS.to_string(),
+            apostrophe_elision: true,
+            replacement_mode: ReplacementMode::default(),
+            physical_layout: PhysicalLayout::default(),
+            trigger_rules: vec![],
+            bypass_key: BypassKey::default(),
+            bypass_mode: BypassMode::default(),
+            undo_key: UndoKey::default(),
+            excluded_apps: vec![],
+            accent_completion: false,
+            accent_suggestions: false,
+            accent_preferences: vec![],
+            osd_position: OsdPosition::default(),
+            osd_duration_ms: 1200,
+            osd_opacity: 90,
+            language_sound: false,
+            combination_sound: false,
+            sound_theme: SoundTheme::default(),
+            sound_volume: 60,
+            log_level: LogLevel::default(),
+            log_typed_text: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn word_boundary(&self) -> WordBoundary {
+        WordBoundary::new(&self.word_boundaries, self.apostrophe_elision)
+    }
+
+    pub fn engine_options(&self) -> EngineOptions {
+        EngineOptions {
+            word_boundary: self.word_boundary(),
+            replacement_mode: self.replacement_mode,
+            trigger_rules: TriggerRules::for_layout(self.physical_layout)
+                .with_custom_rules(&self.trigger_rules),
+            bypass: BypassOptions {
+                key: self.bypass_key,
+                mode: self.bypass_mode,
+            },
+            undo_key: self.undo_key,
+            excluded_apps: self.excluded_apps.clone(),
+            // Saved apart from the settings, see AppState::engine_options
+            snippets: vec![],
+            accents: AccentOptions {
+                enabled: self.accent_completion,
+                suggestions: self.accent_suggestions,
+                preferences: self.accent_preferences.clone(),
+                // Saved apart from the settings, see AppState::engine_options
+                picks: CandidatePicks::default(),
+            },
+        }
+    }
+
+    pub fn osd_options(&self) -> OsdOptions {
+        OsdOptions {
+            position: self.osd_position,
+            duration: Duration::from_millis(self.osd_duration_ms),
+            opacity: self.osd_opacity,
+        }
+    }
+
+    pub fn sound_options(&self) -> SoundOptions {
+        SoundOptions {
+            language: self.language_sound,
+            combination: self.combination_sound,
+            theme: self.sound_theme,
+            volume: self.sound_volume,
+        }
+    }
+
+    pub fn log_options(&self) -> LogOptions {
+        LogOptions {
+            level: self.log_level,
+            typed_text: self.log_typed_text,
+        }
+    }
 }
@@ -1,16 +1,32 @@
 use std::error::Error;
 
 use crate::{
-    app_state::AppState,
-    controllers::{start_engine, toggle_language},
-    language::Language,
-    tray_menu::register_tray_menu,
+    app_state::{move_legacy_state, AppState, STATE_FILE_NAME},
+    candidate_window::Candidates,
+    controllers::{
+        forward_engine_events, run_engine_requests, sync_engine, toggle_language, EngineRequests,
+    },
+    logging::{apply_log_options, init_logging, LogOptions},
+    osd::Osd,
+    pick_store::{load_picks, PICKS_FILE_NAME},
+    snippet_store::{load_snippets, SNIPPETS_FILE_NAME},
+    sound_cues::SoundCues,
+    stats::{load_stats, StatsRecorder, STATS_FILE_NAME},
+    tray_menu::{register_tray_menu, TrayMenuModel},
+    utils::config_file_path,
+    window_engine::engine_event::{EngineEvent, EngineStatus},
 };
 
+#[cfg(not(windows))]
+use crate::sound_cues::UnsupportedAudio as SystemAudio;
+#[cfg(windows)]
+use crate::sound_cues::WindowsAudio as SystemAudio;
 use crate::window_engine::WindowEngine;
 
+use log::{error, info};
 use std::sync::{mpsc, Mutex};
 use std::thread;
+use std::time::Instant;
 use tauri::Manager;
 use tauri::{App, Builder, Emitter, Wry};
 
@@ -25,20 +41,63 @@ impl AppSetup for Builder<Wry> {
 }
 
 fn setup(app: &mut App) -> Result<(), Box<dyn Error>> {
-    let app_data_path = app.path().app_config_dir().unwrap();
-    let app_state = AppState::load(app_data_path);
+    // Started with the default options so that loading the state is logged
+    init_logging(app.path().app_log_dir().unwrap(), LogOptions::default());
+    info!(version:% = app.package_info().version; "Starting");
 
-    let settings = app_state.settings;
+    let config_dir = app.path().app_config_dir().unwrap();
+    move_legacy_state(&config_dir);
+    let mut app_state = AppState::load(config_file_path(&config_dir, STATE_FILE_NAME));
+    app_state.snippets = load_snippets(&config_file_path(&config_dir, SNIPPETS_FILE_NAME));
+    app_state.candidate_picks = load_picks(&config_file_path(&config_dir, PICKS_FILE_NAME));
+
+    let settings = &app_state.settings;
+    apply_log_options(settings.log_options());
 
     if !settings.hide_to_tray {
         let _ = app.get_webview_window("main").unwrap().show();
     }
 
+    let (engine_event_sender, engine_event_receiver) = mpsc::channel::<EngineEvent>();
+    app.manage(engine_event_sender);
+    app.manage(Mutex::new(EngineStatus::default()));
+    app.manage(Mutex::new(Osd::default()));
+    app.manage(Mutex::new(Candidates::default()));
+    app.manage(Mutex::new(SoundCues::new(
+        settings.sound_options(),
+        Box::new(SystemAudio::default()),
+    )));
+    let stats_path = config_file_path(&config_dir, STATS_FILE_NAME);
+    app.manage(Mutex::new(StatsRecorder::new(
+        load_stats(&stats_path),
+        stats_path,
+        app_state.language,
+        Instant::now(),
+    )));
+
+    let app_handle = app.app_handle().clone();
+    thread::spawn(move || {
+        forward_engine_events(&app_handle, engine_event_receiver);
+    });
+
+    let (engine_request_sender, engine_request_receiver) = mpsc::channel::<()>();
+    app.manage(EngineRequests(engine_request_sender));
+    let app_handle = app.app_handle().clone();
+    thread::spawn(move || {
+        run_engine_requests(&app_handle, engine_request_receiver);
+    });
+
     let app_handle = app.app_handle().clone();
 
     thread::spawn(move || {
         let (sender, receiver) = mpsc::channel::<()>();
-        WindowEngine::set_toggle_channel(sender);
+        let _shortcut_listener = match WindowEngine::listen_toggle_shortcut(sender) {
+            Ok(listener) => listener,
+            Err(message) => {
+                error!(message = message.as_str(); "Toggle shortcut not listened to");
+                return;
+            }
+        };
 
         loop {
             let r = receiver.recv();
@@ -49,14 +108,18 @@ fn setup(app: &mut App) -> Result<(), Box<dyn Error>> {
         }
     });
 
-    if app_state.language == Language::French {
-        start_engine();
-    };
-
     let _ = app.emit("change_language", app_state.language);
 
-    register_tray_menu(app, app_state.language)?;
+    let tray_menu_model = TrayMenuModel {
+        language: app_state.language,
+        status: EngineStatus::default(),
+        paused: false,
+        run_on_startup: settings.run_on_startup,
+        target_app: None,
+    };
+    register_tray_menu(app, &tray_menu_model)?;
 
     app.manage(Mutex::new(app_state));
+    sync_engine(app.app_handle());
     Ok(())
 }
@@ -0,0 +1,59 @@
+use std::{fs, path::Path};
+
+use log::error;
+
+use crate::window_engine::snippets::Snippet;
+
+pub const SNIPPETS_FILE_NAME: &str = "snippets.json";
+
+pub fn load_snippets(path: &Path) -> Vec<Snippet> {
+    let Ok(snippets_json) = fs::read_to_string(path) else {
+        return vec![];
+    };
+    serde_json::from_str(&snippets_json).unwrap_or_default()
+}
+
+pub fn save_snippets(path: &Path, snippets: &[Snippet]) {
+    let snippets_json = serde_json::to_string_pretty(snippets).unwrap();
+    if let Err(error) = fs::write(path, snippets_json) {
+        error!(error:%; "Snippets not saved");
+    }
+}
+
+// Adds `snippet` or replaces the one with the same abbreviation
+pub fn upsert_snippet(snippets: &mut Vec<Snippet>, snippet: Snippet) {
+    match snippets
+        .iter_mut()
+        .find(|s| s.abbreviation == snippet.abbreviation)
+    {
+        Some(existing) => *existing = snippet,
+        None => snippets.push(snippet),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::upsert_snippet;
+    use crate::window_engine::snippets::Snippet;
+
+    fn snippet(abbreviation: &str, expansion: &str) -> Snippet {
+        Snippet {
+            abbreviation: abbreviation.to_string(),
+            expansion: expansion.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_upsert_snippet() {
+        let mut snippets = vec![snippet(";svp", "s'il vous plait")];
+        upsert_snippet(&mut snippets, snippet(";adr", "12 rue de la Paix"));
+        upsert_snippet(&mut snippets, snippet(";svp", "s'il vous plaît"));
+        assert_eq!(
+            snippets,
+            [
+                snippet(";svp", "s'il vous plaît"),
+                snippet(";adr", "12 rue de la Paix")
+            ]
+        );
+    }
+}
@@ -0,0 +1,317 @@
+#[cfg(windows)]
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use serde::{Deserialize, Serialize};
+#[cfg(windows)]
+use windows::core::PCWSTR;
+#[cfg(windows)]
+use windows::Win32::Foundation::HMODULE;
+#[cfg(windows)]
+use windows::Win32::Media::Audio::{PlaySoundW, SND_ASYNC, SND_MEMORY, SND_NODEFAULT};
+
+use crate::{
+    language::Language,
+    window_engine::engine_event::{CombinationKind, EngineEvent},
+};
+
+const SAMPLE_RATE: u32 = 22050;
+// Samples faded in and out so the tones do not pop
+const FADE_SAMPLES: usize = 64;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Cue {
+    French,
+    English,
+    CombinationApplied,
+    CombinationReverted,
+}
+
+impl Cue {
+    pub fn language(language: Language) -> Self {
+        match language {
+            Language::English => Cue::English,
+            Language::French => Cue::French,
+        }
+    }
+
+    pub fn from_event(event: &EngineEvent) -> Option<Self> {
+        match event {
+            EngineEvent::Combination {
+                kind: CombinationKind::Revert,
+                ..
+            }
+            | EngineEvent::Undo { .. } => Some(Cue::CombinationReverted),
+            EngineEvent::Combination { .. } => Some(Cue::CombinationApplied),
+            _ => None,
+        }
+    }
+
+    fn is_language(&self) -> bool {
+        matches!(self, Cue::French | Cue::English)
+    }
+
+    // Frequency in hertz and duration in milliseconds of each tone, rising
+    // when French turns on and falling when it turns off
+    fn tones(&self) -> &'static [(f32, u32)] {
+        match self {
+            Cue::French => &[(660.0, 70), (880.0, 90)],
+            Cue::English => &[(880.0, 70), (660.0, 90)],
+            Cue::CombinationApplied => &[(1320.0, 35)],
+            Cue::CombinationReverted => &[(440.0, 45)],
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize, Default)]
+pub enum SoundTheme {
+    #[default]
+    Chime,
+    // Shorter and sharper
+    Click,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct SoundOptions {
+    pub language: bool,
+    pub combination: bool,
+    pub theme: SoundTheme,
+    // Percent
+    pub volume: u8,
+}
+
+pub trait AudioOutput {
+    fn play(&mut self, cue: Cue, theme: SoundTheme, volume: u8);
+}
+
+// The cue as a 16 bit mono wav file
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn wav(cue: Cue, theme: SoundTheme, volume: u8) -> Vec<u8> {
+    let amplitude = i16::MAX as f32 * volume.min(100) as f32 / 100.0;
+    let mut samples: Vec<i16> = vec![];
+    for &(frequency, millis) in cue.tones() {
+        let millis = match theme {
+            SoundTheme::Chime => millis,
+            SoundTheme::Click => millis / 3,
+        };
+        let len = (SAMPLE_RATE * millis / 1000) as usize;
+        for i in 0..len {
+            let phase = 2.0 * PI * frequency * i as f32 / SAMPLE_RATE as f32;
+            let wave = match theme {
+                SoundTheme::Chime => phase.sin(),
+                SoundTheme::Click => phase.sin().signum(),
+            };
+            let fade = (i.min(len - 1 - i) as f32 / FADE_SAMPLES as f32).min(1.0);
+            samples.push((wave * fade * amplitude) as i16);
+        }
+    }
+
+    let data_len = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16_u32.to_le_bytes());
+    // PCM, mono
+    wav.extend_from_slice(&1_u16.to_le_bytes());
+    wav.extend_from_slice(&1_u16.to_le_bytes());
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2_u16.to_le_bytes());
+    wav.extend_from_slice(&16_u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+// Only Windows plays the cues
+#[cfg(not(windows))]
+#[derive(Debug, Default)]
+pub struct UnsupportedAudio;
+
+#[cfg(not(windows))]
+impl AudioOutput for UnsupportedAudio {
+    fn play(&mut self, _cue: Cue, _theme: SoundTheme, _volume: u8) {}
+}
+
+#[cfg(windows)]
+#[derive(Debug, Default)]
+pub struct WindowsAudio {
+    // Played asynchronously from memory, the buffers must outlive the playback
+    sounds: HashMap<(Cue, SoundTheme, u8), Vec<u8>>,
+}
+
+#[cfg(windows)]
+impl AudioOutput for WindowsAudio {
+    fn play(&mut self, cue: Cue, theme: SoundTheme, volume: u8) {
+        let sound = self
+            .sounds
+            .entry((cue, theme, volume))
+            .or_insert_with(|| wav(cue, theme, volume));
+        // Stops the sound still playing
+        unsafe {
+            let _ = PlaySoundW(
+                PCWSTR(sound.as_ptr() as *const u16),
+                HMODULE::default(),
+                SND_MEMORY | SND_ASYNC | SND_NODEFAULT,
+            );
+        }
+    }
+}
+
+// Plays the cues the settings turned on
+pub struct SoundCues {
+    options: SoundOptions,
+    output: Box<dyn AudioOutput + Send>,
+}
+
+impl SoundCues {
+    pub fn new(options: SoundOptions, output: Box<dyn AudioOutput + Send>) -> Self {
+        Self { options, output }
+    }
+
+    pub fn set_options(&mut self, options: SoundOptions) {
+        self.options = options;
+    }
+
+    pub fn play(&mut self, cue: Cue) {
+        let enabled = if cue.is_language() {
+            self.options.language
+        } else {
+            self.options.combination
+        };
+        if !enabled || self.options.volume == 0 {
+            return;
+        }
+        self.output
+            .play(cue, self.options.theme, self.options.volume);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use super::{wav, AudioOutput, Cue, SoundCues, SoundOptions, SoundTheme};
+    use crate::window_engine::engine_event::{CombinationKind, EngineEvent};
+
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<(Cue, SoundTheme, u8)>>>);
+
+    impl AudioOutput for Recorder {
+        fn play(&mut self, cue: Cue, theme: SoundTheme, volume: u8) {
+            self.0.lock().unwrap().push((cue, theme, volume));
+        }
+    }
+
+    fn cues(options: SoundOptions) -> (SoundCues, Recorder) {
+        let recorder = Recorder::default();
+        (
+            SoundCues::new(options, Box::new(recorder.clone())),
+            recorder,
+        )
+    }
+
+    fn played(recorder: &Recorder) -> Vec<Cue> {
+        recorder.0.lock().unwrap().iter().map(|p| p.0).collect()
+    }
+
+    #[test]
+    fn test_language_cues_only() {
+        let (mut cues, recorder) = cues(SoundOptions {
+            language: true,
+            volume: 60,
+            ..Default::default()
+        });
+        cues.play(Cue::French);
+        cues.play(Cue::CombinationApplied);
+        cues.play(Cue::English);
+        assert_eq!(played(&recorder), [Cue::French, Cue::English]);
+    }
+
+    #[test]
+    fn test_combination_cues_only() {
+        let (mut cues, recorder) = cues(SoundOptions {
+            combination: true,
+            theme: SoundTheme::Click,
+            volume: 40,
+            ..Default::default()
+        });
+        cues.play(Cue::French);
+        cues.play(Cue::CombinationReverted);
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            [(Cue::CombinationReverted, SoundTheme::Click, 40)]
+        );
+    }
+
+    #[test]
+    fn test_muted() {
+        let (mut cues, recorder) = cues(SoundOptions {
+            language: true,
+            combination: true,
+            volume: 0,
+            ..Default::default()
+        });
+        cues.play(Cue::French);
+        cues.play(Cue::CombinationApplied);
+        assert!(played(&recorder).is_empty());
+    }
+
+    #[test]
+    fn test_cue_from_event() {
+        let table = [
+            (
+                EngineEvent::Combination {
+                    kind: CombinationKind::Combine,
+                    output: "é".to_string(),
+                },
+                Some(Cue::CombinationApplied),
+            ),
+            (
+                EngineEvent::Combination {
+                    kind: CombinationKind::Revert,
+                    output: "ee".to_string(),
+                },
+                Some(Cue::CombinationReverted),
+            ),
+            (
+                EngineEvent::Undo {
+                    output: "ew".to_string(),
+                },
+                Some(Cue::CombinationReverted),
+            ),
+            (EngineEvent::Started, None),
+        ];
+        for (event, expected) in table {
+            assert_eq!(Cue::from_event(&event), expected);
+        }
+    }
+
+    fn peak(wav: &[u8]) -> i16 {
+        wav[44..]
+            .chunks_exact(2)
+            .map(|s| i16::from_le_bytes([s[0], s[1]]).saturating_abs())
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_wav() {
+        let loud = wav(Cue::French, SoundTheme::Chime, 100);
+        assert_eq!(&loud[..4], b"RIFF");
+        assert_eq!(&loud[8..16], b"WAVEfmt ");
+        let data_len = u32::from_le_bytes([loud[40], loud[41], loud[42], loud[43]]);
+        assert_eq!(data_len as usize, loud.len() - 44);
+
+        let quiet = wav(Cue::French, SoundTheme::Chime, 50);
+        assert!(peak(&quiet) <= peak(&loud) / 2 + 1);
+        assert!(peak(&quiet) > peak(&loud) / 3);
+
+        let click = wav(Cue::French, SoundTheme::Click, 100);
+        assert!(click.len() < loud.len());
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    language::Language,
+    utils::local_time,
+    window_engine::engine_event::{CombinationKind, EngineEvent},
+};
+
+// Days kept in the daily totals
+const MAX_DAYS: usize = 366;
+// Counts are written at most this often, and when the app quits
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UsageCounts {
+    // By the character the combination typed, such as `é`
+    pub combinations: BTreeMap<String, u64>,
+    pub reverts: u64,
+    pub undos: u64,
+    pub snippets: u64,
+    pub accent_completions: u64,
+    pub toggles: u64,
+    pub french_secs: u64,
+    pub english_secs: u64,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Stats {
+    pub total: UsageCounts,
+    // Keyed by the local date, such as `2024-05-31`
+    pub days: BTreeMap<String, UsageCounts>,
+}
+
+impl Stats {
+    fn update(&mut self, day: &str, update: impl Fn(&mut UsageCounts)) {
+        update(&mut self.total);
+        update(self.days.entry(day.to_string()).or_default());
+        while self.days.len() > MAX_DAYS {
+            self.days.pop_first();
+        }
+    }
+
+    // Only the kind of edit is counted, never the text it was made in. Whether
+    // the event was counted
+    pub fn record_event(&mut self, day: &str, event: &EngineEvent) -> bool {
+        match event {
+            EngineEvent::Combination {
+                kind: CombinationKind::Revert,
+                ..
+            } => self.update(day, |counts| counts.reverts += 1),
+            EngineEvent::Combination { output, .. } => self.update(day, |counts| {
+                *counts.combinations.entry(output.clone()).or_default() += 1
+            }),
+            EngineEvent::Undo { .. } => self.update(day, |counts| counts.undos += 1),
+            EngineEvent::Snippet { .. } => self.update(day, |counts| counts.snippets += 1),
+            EngineEvent::AccentCompletion { .. } => {
+                self.update(day, |counts| counts.accent_completions += 1)
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn record_toggle(&mut self, day: &str) {
+        self.update(day, |counts| counts.toggles += 1);
+    }
+
+    pub fn record_time(&mut self, day: &str, language: Language, secs: u64) {
+        match language {
+            Language::French => self.update(day, |counts| counts.french_secs += secs),
+            Language::English => self.update(day, |counts| counts.english_secs += secs),
+        }
+    }
+}
+
+// The events record_event counts, the others are not worth locking the recorder for
+pub fn is_counted(event: &EngineEvent) -> bool {
+    matches!(
+        event,
+        EngineEvent::Combination { .. }
+            | EngineEvent::Undo { .. }
+            | EngineEvent::Snippet { .. }
+            | EngineEvent::AccentCompletion { .. }
+    )
+}
+
+pub const STATS_FILE_NAME: &str = "stats.json";
+
+pub fn load_stats(path: &Path) -> Stats {
+    let Ok(stats_json) = fs::read_to_string(path) else {
+        return Stats::default();
+    };
+    serde_json::from_str(&stats_json).unwrap_or_default()
+}
+
+// The local date, such as `2024-05-31`
+pub fn today() -> String {
+    let time = local_time();
+    format!("{:04}-{:02}-{:02}", time.year, time.month, time.day)
+}
+
+// Collects the stats while the app runs
+#[derive(Debug)]
+pub struct StatsRecorder {
+    stats: Stats,
+    path: PathBuf,
+    // In use since `since`, its time is counted when it changes or the stats are read
+    language: Language,
+    since: Instant,
+    saved_at: Instant,
+    unsaved: bool,
+}
+
+impl StatsRecorder {
+    pub fn new(stats: Stats, path: PathBuf, language: Language, now: Instant) -> Self {
+        Self {
+            stats,
+            path,
+            language,
+            since: now,
+            saved_at: now,
+            unsaved: false,
+        }
+    }
+
+    fn count_time(&mut self, day: &str, now: Instant) {
+        // Whole seconds, the rest is counted with the next ones
+        let secs = now.saturating_duration_since(self.since).as_secs();
+        if secs == 0 {
+            return;
+        }
+        self.stats.record_time(day, self.language, secs);
+        self.since += Duration::from_secs(secs);
+        self.unsaved = true;
+    }
+
+    pub fn record_event(&mut self, day: &str, event: &EngineEvent, now: Instant) {
+        if self.stats.record_event(day, event) {
+            self.unsaved = true;
+            self.save_if_due(day, now);
+        }
+    }
+
+    pub fn change_language(&mut self, day: &str, language: Language, now: Instant) {
+        self.count_time(day, now);
+        if language != self.language {
+            self.language = language;
+            self.stats.record_toggle(day);
+            self.unsaved = true;
+        }
+        self.save_if_due(day, now);
+    }
+
+    pub fn stats(&mut self, day: &str, now: Instant) -> Stats {
+        self.count_time(day, now);
+        self.stats.clone()
+    }
+
+    fn save_if_due(&mut self, day: &str, now: Instant) {
+        if self.unsaved && now.saturating_duration_since(self.saved_at) >= SAVE_INTERVAL {
+            self.save(day, now);
+        }
+    }
+
+    pub fn save(&mut self, day: &str, now: Instant) {
+        self.count_time(day, now);
+        let stats_json = serde_json::to_string(&self.stats).unwrap();
+        if let Err(error) = fs::write(&self.path, stats_json) {
+            error!(error:%; "Stats not saved");
+        }
+        self.saved_at = now;
+        self.unsaved = false;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+    use std::time::{Duration, Instant};
+
+    use super::{is_counted, Stats, StatsRecorder, MAX_DAYS};
+    use crate::language::Language;
+    use crate::window_engine::engine_event::{CombinationKind, EngineEvent};
+
+    fn combination(kind: CombinationKind, output: &str) -> EngineEvent {
+        EngineEvent::Combination {
+            kind,
+            output: output.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_record_events() {
+        let mut stats = Stats::default();
+        stats.record_event("2024-05-30", &combination(CombinationKind::Combine, "é"));
+        stats.record_event("2024-05-31", &combination(CombinationKind::Combine, "é"));
+        stats.record_event("2024-05-31", &combination(CombinationKind::Replace, "€"));
+        stats.record_event("2024-05-31", &combination(CombinationKind::Revert, "ee"));
+        stats.record_event(
+            "2024-05-31",
+            &EngineEvent::Undo {
+                output: "ew".to_string(),
+            },
+        );
+        // What the engine shows of the current word is not counted
+        let buffer = EngineEvent::Buffer {
+            content: "merci".to_string(),
+        };
+        assert!(!stats.record_event("2024-05-31", &buffer));
+        assert!(!is_counted(&buffer));
+        assert!(is_counted(&combination(CombinationKind::Revert, "ee")));
+
+        assert_eq!(stats.total.combinations["é"], 2);
+        assert_eq!(stats.total.combinations["€"], 1);
+        assert_eq!(stats.total.reverts, 1);
+        assert_eq!(stats.total.undos, 1);
+        assert_eq!(stats.days["2024-05-30"].combinations["é"], 1);
+        assert_eq!(stats.days["2024-05-31"].combinations["é"], 1);
+        assert_eq!(stats.days["2024-05-31"].reverts, 1);
+    }
+
+    // The expansion and the word typed are never kept
+    #[test]
+    fn test_no_text_is_kept() {
+        let mut stats = Stats::default();
+        stats.record_event(
+            "2024-05-31",
+            &EngineEvent::Snippet {
+                output: "12 rue de la Paix ".to_string(),
+            },
+        );
+        stats.record_event(
+            "2024-05-31",
+            &EngineEvent::AccentCompletion {
+                output: "déjà ".to_string(),
+            },
+        );
+        stats.record_event("2024-05-31", &combination(CombinationKind::Revert, "ee"));
+
+        let stats_json = serde_json::to_string(&stats).unwrap();
+        assert!(!stats_json.contains("Paix"));
+        assert!(!stats_json.contains("déjà"));
+        assert!(!stats_json.contains("ee"));
+        assert_eq!(stats.total.snippets, 1);
+        assert_eq!(stats.total.accent_completions, 1);
+    }
+
+    #[test]
+    fn test_old_days_are_dropped() {
+        let mut stats = Stats::default();
+        for day in 0..MAX_DAYS + 2 {
+            stats.record_toggle(&format!("day-{:04}", day));
+        }
+        assert_eq!(stats.days.len(), MAX_DAYS);
+        assert!(!stats.days.contains_key("day-0000"));
+        assert_eq!(stats.total.toggles, MAX_DAYS as u64 + 2);
+    }
+
+    #[test]
+    fn test_time_in_each_language() {
+        let start = Instant::now();
+        let mut recorder =
+            StatsRecorder::new(Stats::default(), PathBuf::new(), Language::English, start);
+
+        let day = "2024-05-31";
+        recorder.change_language(day, Language::French, start + Duration::from_millis(2500));
+        // Picking the language in use is not a toggle
+        recorder.change_language(day, Language::French, start + Duration::from_secs(5));
+        recorder.change_language(day, Language::English, start + Duration::from_secs(12));
+        let stats = recorder.stats(day, start + Duration::from_secs(15));
+
+        assert_eq!(stats.total.toggles, 2);
+        assert_eq!(stats.total.english_secs, 2 + 3);
+        assert_eq!(stats.total.french_secs, 10);
+        assert_eq!(stats.days[day].french_secs, 10);
+    }
+}
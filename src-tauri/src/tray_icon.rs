@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+#[cfg(windows)]
+use std::ffi::c_void;
+#[cfg(windows)]
+use std::mem::size_of;
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+use tauri::image::Image;
+#[cfg(windows)]
+use windows::core::w;
+#[cfg(windows)]
+use windows::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD};
+
+use crate::{language::Language, window_engine::engine_event::EngineStatus};
+
+// Embedded so the icons load whatever directory the app is started from
+const UK_PNG: &[u8] = include_bytes!("../resources/uk.png");
+const FRANCE_PNG: &[u8] = include_bytes!("../resources/france.png");
+
+// Badge colors, RGB
+const PAUSED_COLOR: [u8; 3] = [0x9E, 0x9E, 0x9E];
+const ERROR_COLOR: [u8; 3] = [0xE5, 0x39, 0x35];
+const RECOVERING_COLOR: [u8; 3] = [0xFB, 0x8C, 0x00];
+const LIGHT_OUTLINE: [u8; 3] = [0xFF, 0xFF, 0xFF];
+const DARK_OUTLINE: [u8; 3] = [0x21, 0x21, 0x21];
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum IconBadge {
+    None,
+    Paused,
+    Error,
+    Recovering,
+}
+
+impl IconBadge {
+    pub fn from_status(status: EngineStatus, paused: bool) -> Self {
+        if paused {
+            return IconBadge::Paused;
+        }
+        match status {
+            EngineStatus::Crashed | EngineStatus::HookFailed => IconBadge::Error,
+            EngineStatus::Recovering => IconBadge::Recovering,
+            EngineStatus::Stopped | EngineStatus::Running => IconBadge::None,
+        }
+    }
+
+    fn color(&self) -> Option<[u8; 3]> {
+        match self {
+            IconBadge::None => None,
+            IconBadge::Paused => Some(PAUSED_COLOR),
+            IconBadge::Error => Some(ERROR_COLOR),
+            IconBadge::Recovering => Some(RECOVERING_COLOR),
+        }
+    }
+}
+
+// The taskbar theme, the badge outline contrasts with it
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
+impl Theme {
+    #[cfg(windows)]
+    pub fn taskbar() -> Self {
+        let mut light: u32 = 0;
+        let mut len = size_of::<u32>() as u32;
+        let read = unsafe {
+            RegGetValueW(
+                HKEY_CURRENT_USER,
+                w!(r"Software\Microsoft\Windows\CurrentVersion\Themes\Personalize"),
+                w!("SystemUsesLightTheme"),
+                RRF_RT_REG_DWORD,
+                None,
+                Some(&mut light as *mut u32 as *mut c_void),
+                Some(&mut len),
+            )
+        };
+
+        // Windows defaults to a dark taskbar
+        if read.is_ok() && light != 0 {
+            Theme::Light
+        } else {
+            Theme::Dark
+        }
+    }
+
+    #[cfg(not(windows))]
+    pub fn taskbar() -> Self {
+        Theme::Dark
+    }
+
+    fn outline(&self) -> [u8; 3] {
+        match self {
+            Theme::Light => DARK_OUTLINE,
+            Theme::Dark => LIGHT_OUTLINE,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct IconVariant {
+    pub language: Language,
+    pub badge: IconBadge,
+    pub theme: Theme,
+}
+
+// Dims a paused icon and draws the status badge in the bottom right corner
+fn render(rgba: &mut [u8], width: u32, height: u32, variant: &IconVariant) {
+    if variant.badge == IconBadge::Paused {
+        for pixel in rgba.chunks_exact_mut(4) {
+            let grey = ((pixel[0] as u32 * 3 + pixel[1] as u32 * 6 + pixel[2] as u32) / 10) as u8;
+            pixel[..3].fill(grey);
+        }
+    }
+
+    let Some(color) = variant.badge.color() else {
+        return;
+    };
+    let size = width.min(height) as f32;
+    let radius = size * 0.22;
+    let outline = (size * 0.04).max(1.0);
+    let center_x = width as f32 - radius - outline;
+    let center_y = height as f32 - radius - outline;
+
+    for y in 0..height {
+        for x in 0..width {
+            let dx = x as f32 + 0.5 - center_x;
+            let dy = y as f32 + 0.5 - center_y;
+            let distance = (dx * dx + dy * dy).sqrt();
+            let fill = if distance <= radius {
+                color
+            } else if distance <= radius + outline {
+                variant.theme.outline()
+            } else {
+                continue;
+            };
+
+            let i = ((y * width + x) * 4) as usize;
+            rgba[i..i + 3].copy_from_slice(&fill);
+            rgba[i + 3] = 0xFF;
+        }
+    }
+}
+
+fn base_png(language: Language) -> &'static [u8] {
+    match language {
+        Language::English => UK_PNG,
+        Language::French => FRANCE_PNG,
+    }
+}
+
+fn generate(variant: &IconVariant) -> Image<'static> {
+    let base = Image::from_bytes(base_png(variant.language)).expect("Embedded icon is a valid png");
+    let (width, height) = (base.width(), base.height());
+    let mut rgba = base.rgba().to_vec();
+    render(&mut rgba, width, height, variant);
+    Image::new_owned(rgba, width, height)
+}
+
+// Variants are generated once and reused
+pub fn icon_image(variant: IconVariant) -> Image<'static> {
+    static ICONS: OnceLock<Mutex<HashMap<IconVariant, Image<'static>>>> = OnceLock::new();
+
+    let mut icons = ICONS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    icons
+        .entry(variant)
+        .or_insert_with(|| generate(&variant))
+        .clone()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{generate, render, IconBadge, IconVariant, Theme, ERROR_COLOR, LIGHT_OUTLINE};
+    use crate::{language::Language, window_engine::engine_event::EngineStatus};
+
+    const SIZE: u32 = 32;
+
+    fn variant(badge: IconBadge, theme: Theme) -> IconVariant {
+        IconVariant {
+            language: Language::French,
+            badge,
+            theme,
+        }
+    }
+
+    // A blue square
+    fn rendered(variant: &IconVariant) -> Vec<u8> {
+        let mut rgba = [0x00, 0x55, 0xA4, 0xFF].repeat((SIZE * SIZE) as usize);
+        render(&mut rgba, SIZE, SIZE, variant);
+        rgba
+    }
+
+    fn pixel(rgba: &[u8], x: u32, y: u32) -> &[u8] {
+        let i = ((y * SIZE + x) * 4) as usize;
+        &rgba[i..i + 4]
+    }
+
+    #[test]
+    fn test_no_badge_keeps_the_icon() {
+        let rgba = rendered(&variant(IconBadge::None, Theme::Dark));
+        assert!(rgba
+            .chunks_exact(4)
+            .all(|pixel| pixel == [0x00, 0x55, 0xA4, 0xFF]));
+    }
+
+    #[test]
+    fn test_error_badge() {
+        let rgba = rendered(&variant(IconBadge::Error, Theme::Dark));
+        assert_eq!(pixel(&rgba, 0, 0), [0x00, 0x55, 0xA4, 0xFF]);
+        assert_eq!(&pixel(&rgba, SIZE - 9, SIZE - 9)[..3], ERROR_COLOR);
+        // The outline contrasts with a dark taskbar
+        assert!(rgba
+            .chunks_exact(4)
+            .any(|pixel| pixel[..3] == LIGHT_OUTLINE));
+    }
+
+    #[test]
+    fn test_paused_icon_is_grey() {
+        let rgba = rendered(&variant(IconBadge::Paused, Theme::Light));
+        let top_left = pixel(&rgba, 0, 0);
+        assert_eq!(top_left[0], top_left[1]);
+        assert_eq!(top_left[1], top_left[2]);
+    }
+
+    #[test]
+    fn test_badge_from_status() {
+        assert_eq!(
+            IconBadge::from_status(EngineStatus::Running, true),
+            IconBadge::Paused
+        );
+        assert_eq!(
+            IconBadge::from_status(EngineStatus::HookFailed, false),
+            IconBadge::Error
+        );
+        assert_eq!(
+            IconBadge::from_status(EngineStatus::Running, false),
+            IconBadge::None
+        );
+    }
+
+    #[test]
+    fn test_embedded_icons_decode() {
+        for language in Language::ALL {
+            let image = generate(&IconVariant {
+                language,
+                badge: IconBadge::Error,
+                theme: Theme::Dark,
+            });
+            assert_eq!(
+                image.rgba().len(),
+                (image.width() * image.height() * 4) as usize
+            );
+        }
+    }
+}
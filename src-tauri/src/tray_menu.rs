@@ -1,83 +1,292 @@
 use std::error::Error;
+use std::sync::Mutex;
+use std::time::Duration;
 
 use tauri::{
-    image::Image,
-    menu::{MenuBuilder, MenuItemBuilder},
-    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    App, Manager,
+    menu::{CheckMenuItemBuilder, Menu, MenuBuilder, MenuItemBuilder, SubmenuBuilder},
+    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent, TrayIconId},
+    App, AppHandle, Manager, Runtime,
 };
 
 use crate::{
-    controllers::{quit, toggle_language},
+    app_state::AppState,
+    controllers::{
+        change_language, exclude_app, pause_engine, quit, resume_engine, set_run_on_startup,
+        toggle_language,
+    },
     language::Language,
+    tray_icon::{icon_image, IconBadge, IconVariant, Theme},
+    window_engine::{engine_event::EngineStatus, secure_input::foreground_app},
 };
 
 pub const TRAY_ICON_ID: &str = "10";
 
-pub fn register_tray_menu(app: &App, start_language: Language) -> Result<(), Box<dyn Error>> {
-    let quit_item = MenuItemBuilder::new("Quit").id("quit").build(app)?;
-    let show_item = MenuItemBuilder::new("Show").id("show").build(app)?;
-    let toggle_item = MenuItemBuilder::new("Toggle").id("toggle").build(app)?;
-    let menu = MenuBuilder::new(app)
-        .items(&[&quit_item, &show_item, &toggle_item])
-        .build()?;
+const PAUSE_DURATIONS_MINUTES: [u64; 2] = [5, 30];
+
+// Everything the tray menu and tooltip show, rebuilt from the app state
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TrayMenuModel {
+    pub language: Language,
+    pub status: EngineStatus,
+    pub paused: bool,
+    pub run_on_startup: bool,
+    // The application focused when the pointer reached the tray icon
+    pub target_app: Option<String>,
+}
+
+impl TrayMenuModel {
+    pub fn tooltip(&self) -> String {
+        if self.language == Language::English {
+            return format!("Clavfrançais - {}", self.language.name());
+        }
+        if self.paused {
+            return format!("Clavfrançais - {} - Paused", self.language.name());
+        }
+        format!(
+            "Clavfrançais - {} - {:?}",
+            self.language.name(),
+            self.status
+        )
+    }
+
+    pub fn icon(&self, theme: Theme) -> IconVariant {
+        IconVariant {
+            language: self.language,
+            badge: IconBadge::from_status(self.status, self.paused),
+            theme,
+        }
+    }
+}
+
+// The application "Disable for this app" applies to, remembered when the pointer
+// enters the tray icon because clicking the tray focuses the taskbar
+#[derive(Debug, Default)]
+pub struct TrayTarget {
+    pub app: Option<String>,
+}
+
+// The model the tray shows, it is only rebuilt when the model changes
+#[derive(Debug)]
+struct ShownTray {
+    model: TrayMenuModel,
+}
+
+fn build_menu<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    model: &TrayMenuModel,
+) -> tauri::Result<Menu<R>> {
+    let mut menu = MenuBuilder::new(manager);
+    for language in Language::ALL {
+        let item =
+            CheckMenuItemBuilder::with_id(format!("language:{:?}", language), language.name())
+                .checked(model.language == language)
+                .build(manager)?;
+        menu = menu.item(&item);
+    }
+    menu = menu.separator();
+
+    if model.paused {
+        let resume_item = MenuItemBuilder::with_id("resume", "Resume").build(manager)?;
+        menu = menu.item(&resume_item);
+    } else {
+        let mut pause_menu = SubmenuBuilder::new(manager, "Pause");
+        for minutes in PAUSE_DURATIONS_MINUTES {
+            let item = MenuItemBuilder::with_id(
+                format!("pause:{}", minutes),
+                format!("For {} minutes", minutes),
+            )
+            .enabled(model.language == Language::French)
+            .build(manager)?;
+            pause_menu = pause_menu.item(&item);
+        }
+        menu = menu.item(&pause_menu.build()?);
+    }
+
+    let disable_text = match &model.target_app {
+        Some(app) => format!("Disable for {}", app),
+        None => "Disable for this app".to_string(),
+    };
+    let disable_item = MenuItemBuilder::with_id("disable_app", disable_text)
+        .enabled(model.target_app.is_some())
+        .build(manager)?;
+    let run_on_startup_item = CheckMenuItemBuilder::with_id("run_on_startup", "Run on startup")
+        .checked(model.run_on_startup)
+        .build(manager)?;
+    let show_item = MenuItemBuilder::with_id("show", "Show").build(manager)?;
+    let quit_item = MenuItemBuilder::with_id("quit", "Quit").build(manager)?;
+
+    menu.item(&disable_item)
+        .item(&run_on_startup_item)
+        .separator()
+        .item(&show_item)
+        .item(&quit_item)
+        .build()
+}
+
+fn on_menu_event(app_handle: &AppHandle, id: &str) {
+    if let Some(language) = id.strip_prefix("language:") {
+        if let Some(language) = Language::ALL
+            .into_iter()
+            .find(|l| format!("{:?}", l) == language)
+        {
+            change_language(app_handle, language);
+        }
+        return;
+    }
+    if let Some(minutes) = id.strip_prefix("pause:") {
+        if let Ok(minutes) = minutes.parse::<u64>() {
+            pause_engine(app_handle, Duration::from_secs(minutes * 60));
+        }
+        return;
+    }
+
+    match id {
+        "resume" => resume_engine(app_handle),
+        "disable_app" => {
+            let app = app_handle
+                .state::<Mutex<TrayTarget>>()
+                .lock()
+                .unwrap()
+                .app
+                .clone();
+            if let Some(app) = app {
+                exclude_app(app_handle, app);
+            }
+        }
+        "run_on_startup" => {
+            let run_on_startup = {
+                let app_state = app_handle.state::<Mutex<AppState>>();
+                let app_state = app_state.lock().unwrap();
+                app_state.settings.run_on_startup
+            };
+            set_run_on_startup(app_handle, !run_on_startup);
+        }
+        "show" => {
+            if let Some(window) = app_handle.get_webview_window("main") {
+                let _ = window.show();
+            }
+        }
+        "quit" => quit(app_handle),
+        _ => {}
+    }
+}
+
+pub fn register_tray_menu(app: &App, model: &TrayMenuModel) -> Result<(), Box<dyn Error>> {
+    app.manage(Mutex::new(TrayTarget::default()));
+    app.manage(Mutex::new(ShownTray {
+        model: model.clone(),
+    }));
+    let menu = build_menu(app, model)?;
 
     TrayIconBuilder::with_id(TRAY_ICON_ID)
-        .icon(get_icon_image(start_language))
+        .icon(icon_image(model.icon(Theme::taskbar())))
+        .tooltip(model.tooltip())
         .menu(&menu)
         .show_menu_on_left_click(false)
-        .on_menu_event(|app_handle, menu_event| match menu_event.id().as_ref() {
-            "quit" => {
-                quit(app_handle);
-            }
-            "show" => {
-                if let Some(window) = app_handle.get_webview_window("main") {
-                    let _ = window.show();
-                }
+        .on_menu_event(|app_handle, menu_event| on_menu_event(app_handle, menu_event.id().as_ref()))
+        .on_tray_icon_event(|tray_icon, event| match event {
+            TrayIconEvent::Enter { .. } => {
+                let app_handle = tray_icon.app_handle();
+                app_handle.state::<Mutex<TrayTarget>>().lock().unwrap().app = foreground_app();
+                refresh_tray(app_handle);
             }
-            "toggle" => {
-                toggle_language(app_handle);
-            }
-            _ => {}
-        })
-        .on_tray_icon_event(|tray_icon, event| {
-            if let TrayIconEvent::Click {
+            TrayIconEvent::Click {
                 button,
                 button_state,
                 ..
-            } = event
-            {
+            } => {
                 if (button != MouseButton::Left) | (button_state != MouseButtonState::Up) {
                     return;
                 }
                 toggle_language(tray_icon.app_handle());
             }
+            _ => {}
         })
         .build(app)?;
 
     Ok(())
 }
 
-static mut UK_IMAGE: Option<Image<'static>> = None;
-static mut FRANCE_IMAGE: Option<Image<'static>> = None;
+// Rebuilds the menu, tooltip and icon from the app state when what they show
+// changed. Must not be called while the app state or the engine status is locked
+pub fn refresh_tray(app_handle: &AppHandle) {
+    let Some(tray_icon) = app_handle.tray_by_id(&TrayIconId::new(TRAY_ICON_ID)) else {
+        return;
+    };
+    let (Some(app_state), Some(status), Some(target), Some(shown)) = (
+        app_handle.try_state::<Mutex<AppState>>(),
+        app_handle.try_state::<Mutex<EngineStatus>>(),
+        app_handle.try_state::<Mutex<TrayTarget>>(),
+        app_handle.try_state::<Mutex<ShownTray>>(),
+    ) else {
+        return;
+    };
 
-pub fn get_icon_image(language: Language) -> Image<'static> {
-    if language == Language::English {
-        unsafe {
-            if UK_IMAGE.is_none() {
-                UK_IMAGE = Some(Image::from_path("resources/uk.png").expect("Missing uk icon"));
-            }
+    let model = {
+        let app_state = app_state.lock().unwrap();
+        TrayMenuModel {
+            language: app_state.language,
+            status: *status.lock().unwrap(),
+            paused: app_state.pause.is_paused(),
+            run_on_startup: app_state.settings.run_on_startup,
+            target_app: target.lock().unwrap().app.clone(),
+        }
+    };
 
-            UK_IMAGE.clone().unwrap()
+    // Not kept locked while the tray is updated, that happens on the main thread
+    {
+        let mut shown = shown.lock().unwrap();
+        if shown.model == model {
+            return;
         }
-    } else {
-        unsafe {
-            if FRANCE_IMAGE.is_none() {
-                FRANCE_IMAGE =
-                    Some(Image::from_path("resources/france.png").expect("Missing france icon"));
-            }
+        shown.model = model.clone();
+    }
+
+    if let Ok(menu) = build_menu(app_handle, &model) {
+        let _ = tray_icon.set_menu(Some(menu));
+    }
+    let _ = tray_icon.set_tooltip(Some(model.tooltip()));
+    // Read along with the model, a theme change shows with the next one
+    let _ = tray_icon.set_icon(Some(icon_image(model.icon(Theme::taskbar()))));
+}
+
+#[cfg(test)]
+mod test {
+    use super::TrayMenuModel;
+    use crate::{language::Language, window_engine::engine_event::EngineStatus};
+
+    fn model(language: Language, status: EngineStatus, paused: bool) -> TrayMenuModel {
+        TrayMenuModel {
+            language,
+            status,
+            paused,
+            run_on_startup: false,
+            target_app: None,
+        }
+    }
 
-            FRANCE_IMAGE.clone().unwrap()
+    #[test]
+    fn test_tooltip() {
+        let table = [
+            (
+                model(Language::English, EngineStatus::Stopped, false),
+                "Clavfrançais - English",
+            ),
+            (
+                model(Language::French, EngineStatus::Running, false),
+                "Clavfrançais - Français - Running",
+            ),
+            (
+                model(Language::French, EngineStatus::HookFailed, false),
+                "Clavfrançais - Français - HookFailed",
+            ),
+            (
+                model(Language::French, EngineStatus::Stopped, true),
+                "Clavfrançais - Français - Paused",
+            ),
+        ];
+        for (model, expected) in table {
+            assert_eq!(model.tooltip(), expected);
         }
     }
 }
@@ -1,6 +1,209 @@
-#[macro_export] macro_rules! debug_println {
-    ($($arg:tt)*) => {
-        #[cfg(debug_assertions)]
-        println!($($arg)*)
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+#[cfg(not(windows))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// A thread that signals its exit, so that join_timeout can wait on it
+pub struct TimedJoinHandle<T> {
+    handle: JoinHandle<T>,
+    // Disconnected once the thread returned or panicked, nothing is ever sent
+    exited: Receiver<()>,
+}
+
+impl<T> TimedJoinHandle<T> {
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+}
+
+pub fn spawn_timed<F, T>(f: F) -> TimedJoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (exit_sender, exited) = mpsc::channel::<()>();
+    let handle = thread::spawn(move || {
+        // Dropped when `f` returns or unwinds
+        let _exit_sender = exit_sender;
+        f()
+    });
+    TimedJoinHandle { handle, exited }
+}
+
+// Waits up to `timeout` for the thread to exit. Returns None when it panicked or
+// did not exit in time, in which case it is left running detached
+pub fn join_timeout<T>(thread: TimedJoinHandle<T>, timeout: Duration) -> Option<T> {
+    match thread.exited.recv_timeout(timeout) {
+        Err(RecvTimeoutError::Timeout) => None,
+        _ => thread.handle.join().ok(),
+    }
+}
+
+// The path of a file the app saves in its config dir, the dir is created when missing
+pub fn config_file_path(config_dir: &Path, file_name: &str) -> PathBuf {
+    let _ = fs::create_dir_all(config_dir);
+    config_dir.join(file_name)
+}
+
+// Replaces the file at `path` with a dir holding it as `file_name`
+pub fn move_into_dir(path: &Path, file_name: &str) -> io::Result<()> {
+    let mut moving = OsString::from(path);
+    moving.push(".moving");
+    fs::rename(path, &moving)?;
+    fs::create_dir_all(path)?;
+    fs::rename(&moving, path.join(file_name))
+}
+
+// A wall clock time, such as the one logs and stats are dated with
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LocalTime {
+    pub year: u32,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub millis: u32,
+}
+
+#[cfg(windows)]
+pub fn local_time() -> LocalTime {
+    let time = unsafe { windows::Win32::System::SystemInformation::GetLocalTime() };
+    LocalTime {
+        year: time.wYear.into(),
+        month: time.wMonth.into(),
+        day: time.wDay.into(),
+        hour: time.wHour.into(),
+        minute: time.wMinute.into(),
+        second: time.wSecond.into(),
+        millis: time.wMilliseconds.into(),
+    }
+}
+
+// Only Windows is asked for its time zone, elsewhere the time is UTC
+#[cfg(not(windows))]
+pub fn local_time() -> LocalTime {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    utc_time(since_epoch)
+}
+
+// The UTC time `since_epoch` after 1970-01-01, from the days to civil algorithm
+#[cfg(any(not(windows), test))]
+fn utc_time(since_epoch: Duration) -> LocalTime {
+    let secs = since_epoch.as_secs();
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Months counted from March so that the leap day ends the year
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
     };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    let secs_of_day = secs % 86_400;
+    LocalTime {
+        year: year as u32,
+        month: month as u32,
+        day: day as u32,
+        hour: (secs_of_day / 3600) as u32,
+        minute: (secs_of_day / 60 % 60) as u32,
+        second: (secs_of_day % 60) as u32,
+        millis: since_epoch.subsec_millis(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    use super::{config_file_path, join_timeout, move_into_dir, spawn_timed, utc_time, LocalTime};
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("clavfrancais-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_config_file_path() {
+        let dir = temp_path("config");
+        let path = config_file_path(&dir, "stats.json");
+        assert_eq!(path, dir.join("stats.json"));
+        assert!(dir.is_dir());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_move_into_dir() {
+        let path = temp_path("legacy-state");
+        fs::write(&path, "{}").unwrap();
+        move_into_dir(&path, "state.json").unwrap();
+        assert_eq!(fs::read_to_string(path.join("state.json")).unwrap(), "{}");
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_join_timeout() {
+        assert_eq!(
+            join_timeout(spawn_timed(|| 42), Duration::from_secs(1)),
+            Some(42)
+        );
+        assert_eq!(
+            join_timeout(spawn_timed(|| panic!("expected")), Duration::from_secs(1)),
+            None::<()>
+        );
+
+        // Left running detached until the sender is dropped
+        let (sender, receiver) = mpsc::channel::<()>();
+        let thread = spawn_timed(move || receiver.recv());
+        let started = Instant::now();
+        assert!(join_timeout(thread, Duration::from_millis(50)).is_none());
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        drop(sender);
+    }
+
+    #[test]
+    fn test_utc_time() {
+        let table = [
+            (0, (1970, 1, 1, 0, 0, 0)),
+            (951_782_400, (2000, 2, 29, 0, 0, 0)),
+            (1_709_210_096, (2024, 2, 29, 12, 34, 56)),
+            (1_735_689_599, (2024, 12, 31, 23, 59, 59)),
+        ];
+        for (secs, (year, month, day, hour, minute, second)) in table {
+            assert_eq!(
+                utc_time(Duration::from_millis(secs * 1000 + 250)),
+                LocalTime {
+                    year,
+                    month,
+                    day,
+                    hour,
+                    minute,
+                    second,
+                    millis: 250,
+                },
+                "{}",
+                secs
+            );
+        }
+    }
 }
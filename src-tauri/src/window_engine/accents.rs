@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use super::candidates::{rank, CandidatePicks, Suggestion};
+use super::replacement::Expansion;
+
+// The sorted French word list, front-coded by build.rs
+const FRENCH_WORDS: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/french_words.bin"));
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct AccentOptions {
+    pub enabled: bool,
+    // Offers the spellings of the words with several to pick from
+    pub suggestions: bool,
+    // Accented words picked when the unaccented spelling has several, such as `où`
+    pub preferences: Vec<String>,
+    // Saved by the app, suggested first
+    pub picks: CandidatePicks,
+}
+
+// The word lowercase with its accents removed
+pub fn strip_accents(word: &str) -> String {
+    let mut stripped = String::with_capacity(word.len());
+    for c in word.chars().flat_map(char::to_lowercase) {
+        match c {
+            'à' | 'â' | 'ä' => stripped.push('a'),
+            'é' | 'è' | 'ê' | 'ë' => stripped.push('e'),
+            'î' | 'ï' => stripped.push('i'),
+            'ô' | 'ö' => stripped.push('o'),
+            'ù' | 'û' | 'ü' => stripped.push('u'),
+            'ÿ' => stripped.push('y'),
+            'ç' => stripped.push('c'),
+            'œ' => stripped.push_str("oe"),
+            'æ' => stripped.push_str("ae"),
+            c => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+#[derive(Debug, Default)]
+pub struct AccentList {
+    // Keyed by the unaccented spelling
+    words: HashMap<String, Vec<String>>,
+}
+
+impl AccentList {
+    pub fn new(words: impl IntoIterator<Item = String>) -> Self {
+        let mut list = Self::default();
+        for word in words {
+            list.words
+                .entry(strip_accents(&word))
+                .or_default()
+                .push(word);
+        }
+        list
+    }
+
+    pub fn french() -> &'static Self {
+        static LIST: OnceLock<AccentList> = OnceLock::new();
+        LIST.get_or_init(|| Self::new(decode_words(FRENCH_WORDS)))
+    }
+
+    // Every spelling of a lowercase unaccented word
+    pub fn words(&self, word: &str) -> Option<&[String]> {
+        self.words.get(word).map(Vec::as_slice)
+    }
+
+    // The accented spelling of a lowercase unaccented word, None when the word
+    // has none, or several and none of them is preferred
+    pub fn lookup(&self, word: &str, preferences: &[String]) -> Option<&str> {
+        let words = self.words.get(word)?;
+        let accented = match words.as_slice() {
+            [only] => only,
+            _ => words.iter().find(|w| preferences.contains(w))?,
+        };
+        (accented != word).then_some(accented.as_str())
+    }
+}
+
+// The words front-coded as each one's prefix shared with the previous word, the
+// rest and their lengths in bytes
+fn decode_words(mut encoded: &[u8]) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    while let [prefix_len, rest_len, rest @ ..] = encoded {
+        let (rest, next) = rest.split_at(usize::from(*rest_len));
+        word.truncate(usize::from(*prefix_len));
+        word.push_str(std::str::from_utf8(rest).unwrap());
+        words.push(word.clone());
+        encoded = next;
+    }
+    words
+}
+
+// `accented` written with the case of `typed`, None for mixed case such as `eCole`
+fn match_case(typed: &str, accented: &str) -> Option<String> {
+    if !typed.chars().any(char::is_uppercase) {
+        return Some(accented.to_string());
+    }
+
+    let mut typed_chars = typed.chars();
+    let first_upper = typed_chars.next().is_some_and(char::is_uppercase);
+    if first_upper && !typed_chars.any(char::is_uppercase) {
+        let mut accented_chars = accented.chars();
+        let first = accented_chars.next()?;
+        return Some(first.to_uppercase().chain(accented_chars).collect());
+    }
+    if !typed.chars().any(char::is_lowercase) {
+        return Some(accented.to_uppercase());
+    }
+    None
+}
+
+#[derive(Debug, Default)]
+pub struct AccentCompleter {
+    options: AccentOptions,
+}
+
+impl AccentCompleter {
+    pub fn new(options: AccentOptions) -> Self {
+        let preferences = options
+            .preferences
+            .iter()
+            .map(|word| word.trim().to_lowercase())
+            .collect();
+        Self {
+            options: AccentOptions {
+                preferences,
+                ..options
+            },
+        }
+    }
+
+    // The rewrite of `word` once `boundary` ended it, when the word list knows how
+    // to accent it
+    pub fn complete(&self, word: &str, boundary: char) -> Option<Expansion> {
+        if !self.options.enabled {
+            return None;
+        }
+
+        let lowercase = unaccented(word)?;
+        let accented = AccentList::french().lookup(&lowercase, &self.options.preferences)?;
+        let text = match_case(word, accented)?;
+        Some(Expansion {
+            replaced_len: word.chars().count(),
+            text: format!("{}{}", text, boundary),
+        })
+    }
+
+    // The spellings to pick from when `word` has several, the typed one included
+    pub fn suggest(&self, word: &str, boundary: char) -> Option<Suggestion> {
+        if !self.options.suggestions {
+            return None;
+        }
+
+        let lowercase = unaccented(word)?;
+        let words = AccentList::french().words(&lowercase)?;
+        if words.len() < 2 {
+            return None;
+        }
+
+        let words = rank(words, &self.options.preferences, &self.options.picks);
+        let candidates = words
+            .iter()
+            .map(|accented| match_case(word, accented))
+            .collect::<Option<_>>()?;
+        Some(Suggestion {
+            words,
+            candidates,
+            word_len: word.chars().count(),
+            boundary,
+        })
+    }
+
+    // Suggested first from now on, and the next times the engine starts once the
+    // app saved the picks
+    pub fn pick(&mut self, word: &str) {
+        self.options.picks.record(word);
+    }
+}
+
+// `word` lowercase, None when it is not a word typed without accents
+fn unaccented(word: &str) -> Option<String> {
+    if word.is_empty() || !word.chars().all(char::is_alphabetic) {
+        return None;
+    }
+    let lowercase = word.to_lowercase();
+    // Words typed with some of their accents are left as they are
+    (strip_accents(&lowercase) == lowercase).then_some(lowercase)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        decode_words, match_case, strip_accents, AccentCompleter, AccentList, AccentOptions,
+        FRENCH_WORDS,
+    };
+    use crate::window_engine::candidates::CandidatePicks;
+
+    fn new_completer(preferences: &[&str]) -> AccentCompleter {
+        AccentCompleter::new(AccentOptions {
+            enabled: true,
+            suggestions: true,
+            preferences: preferences.iter().map(|word| word.to_string()).collect(),
+            ..Default::default()
+        })
+    }
+
+    fn complete(completer: &AccentCompleter, word: &str) -> Option<String> {
+        completer
+            .complete(word, ' ')
+            .map(|expansion| expansion.text.trim_end().to_string())
+    }
+
+    #[test]
+    fn test_strip_accents() {
+        assert_eq!(strip_accents("Déjà"), "deja");
+        assert_eq!(strip_accents("cœur"), "coeur");
+        assert_eq!(strip_accents("garçon"), "garcon");
+    }
+
+    #[test]
+    fn test_unique_words() {
+        let completer = new_completer(&[]);
+        assert_eq!(complete(&completer, "ecole").as_deref(), Some("école"));
+        assert_eq!(complete(&completer, "deja").as_deref(), Some("déjà"));
+        assert_eq!(complete(&completer, "tres").as_deref(), Some("très"));
+        assert_eq!(complete(&completer, "coeur").as_deref(), Some("cœur"));
+        assert_eq!(complete(&completer, "maison"), None);
+    }
+
+    #[test]
+    fn test_ambiguous_words() {
+        let completer = new_completer(&[]);
+        assert_eq!(complete(&completer, "ou"), None);
+        assert_eq!(complete(&completer, "a"), None);
+        assert_eq!(complete(&completer, "passe"), None);
+
+        // Preferences are matched whatever their case
+        let completer = new_completer(&["Où", "événement"]);
+        assert_eq!(complete(&completer, "ou").as_deref(), Some("où"));
+        assert_eq!(
+            complete(&completer, "evenement").as_deref(),
+            Some("événement")
+        );
+        assert_eq!(complete(&completer, "a"), None);
+    }
+
+    #[test]
+    fn test_preferring_the_unaccented_word() {
+        let completer = new_completer(&["ou"]);
+        assert_eq!(complete(&completer, "ou"), None);
+    }
+
+    #[test]
+    fn test_case() {
+        let completer = new_completer(&[]);
+        assert_eq!(complete(&completer, "Ecole").as_deref(), Some("École"));
+        assert_eq!(complete(&completer, "ECOLE").as_deref(), Some("ÉCOLE"));
+        assert_eq!(complete(&completer, "eCole"), None);
+        assert_eq!(match_case("Ca", "ça").as_deref(), Some("Ça"));
+    }
+
+    #[test]
+    fn test_partly_accented_words_are_kept() {
+        let completer = new_completer(&[]);
+        assert_eq!(complete(&completer, "élève"), None);
+        assert_eq!(complete(&completer, "ecolé"), None);
+    }
+
+    #[test]
+    fn test_disabled() {
+        let completer = AccentCompleter::new(AccentOptions::default());
+        assert_eq!(complete(&completer, "ecole"), None);
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let mut completer = new_completer(&[]);
+        let suggestion = completer.suggest("Ou", ',').unwrap();
+        assert_eq!(suggestion.candidates, ["Ou", "Où"]);
+        assert_eq!(suggestion.boundary, ',');
+
+        completer.pick("où");
+        assert_eq!(
+            completer.suggest("ou", ' ').unwrap().candidates,
+            ["où", "ou"]
+        );
+
+        // Nothing to pick from
+        assert_eq!(completer.suggest("ecole", ' '), None);
+        assert_eq!(completer.suggest("maison", ' '), None);
+        assert_eq!(completer.suggest("où", ' '), None);
+
+        let completer = AccentCompleter::new(AccentOptions {
+            enabled: true,
+            ..Default::default()
+        });
+        assert_eq!(completer.suggest("ou", ' '), None);
+    }
+
+    #[test]
+    fn test_saved_picks() {
+        let completer = AccentCompleter::new(AccentOptions {
+            suggestions: true,
+            picks: CandidatePicks::new([("où".to_string(), 1)].into()),
+            ..Default::default()
+        });
+        assert_eq!(
+            completer.suggest("ou", ' ').unwrap().candidates,
+            ["où", "ou"]
+        );
+    }
+
+    #[test]
+    fn test_expansion() {
+        let expansion = new_completer(&[]).complete("deja", '.').unwrap();
+        assert_eq!(expansion.replaced_len, 4);
+        assert_eq!(expansion.text, "déjà.");
+    }
+
+    #[test]
+    fn test_decode_words() {
+        let encoded = b"\x00\x02ou\x01\x02\xc3\xb9\x01\x04utre";
+        assert_eq!(decode_words(encoded), ["ou", "où", "outre"]);
+    }
+
+    // Every spelling of a word is listed, such as ou and où
+    #[test]
+    fn test_french_words() {
+        let words = decode_words(FRENCH_WORDS);
+        assert!(words.windows(2).all(|pair| pair[0] < pair[1]));
+        let list = AccentList::french();
+        assert_eq!(
+            list.words.values().map(Vec::len).sum::<usize>(),
+            words.len()
+        );
+        assert_eq!(
+            list.words("ou"),
+            Some(&["ou".to_string(), "où".to_string()][..])
+        );
+        assert_eq!(
+            list.words("a"),
+            Some(&["a".to_string(), "à".to_string()][..])
+        );
+        assert!(list.words("passe").is_some_and(|words| words.len() == 2));
+    }
+}
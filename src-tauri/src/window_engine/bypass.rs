@@ -0,0 +1,177 @@
+use clavfrancais_engine::keys::Key;
+use serde::{Deserialize, Serialize};
+
+use super::word_boundary::BoundaryKind;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Default)]
+pub enum BypassKey {
+    #[default]
+    Escape,
+    ScrollLock,
+    Pause,
+}
+
+impl BypassKey {
+    fn key(&self) -> Key {
+        match self {
+            BypassKey::Escape => Key::Escape,
+            BypassKey::ScrollLock => Key::ScrollLock,
+            BypassKey::Pause => Key::Pause,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Default)]
+pub enum BypassMode {
+    #[default]
+    Off,
+    // The character typed after the bypass key is never combined
+    NextKey,
+    // Nothing is combined until the word typed after the bypass key ends
+    NextWord,
+    // Nothing is combined while the bypass key is held
+    Hold,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct BypassOptions {
+    pub key: BypassKey,
+    pub mode: BypassMode,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum BypassState {
+    Idle,
+    // The bypass key was pressed, nothing typed since
+    Armed,
+    // Inside the word typed after the bypass key
+    InWord,
+    Held,
+}
+
+// Lets the user type a literal sequence such as `ee` without toggling the language
+#[derive(Debug)]
+pub struct Bypass {
+    options: BypassOptions,
+    state: BypassState,
+}
+
+impl Bypass {
+    pub fn new(options: BypassOptions) -> Self {
+        Self {
+            options,
+            state: BypassState::Idle,
+        }
+    }
+
+    fn is_bypass_key(&self, key: &Key) -> bool {
+        self.options.mode != BypassMode::Off && *key == self.options.key.key()
+    }
+
+    pub fn key_down(&mut self, key: &Key) {
+        if !self.is_bypass_key(key) {
+            return;
+        }
+
+        self.state = match self.options.mode {
+            BypassMode::Hold => BypassState::Held,
+            _ => BypassState::Armed,
+        };
+    }
+
+    pub fn key_up(&mut self, key: &Key) {
+        if self.is_bypass_key(key) && self.state == BypassState::Held {
+            self.state = BypassState::Idle;
+        }
+    }
+
+    // Whether the character must reach the application as typed
+    pub fn skips(&mut self, boundary: BoundaryKind) -> bool {
+        let in_word = boundary == BoundaryKind::None;
+        match (self.options.mode, self.state) {
+            (_, BypassState::Idle) => false,
+            (_, BypassState::Held) => true,
+            (BypassMode::NextKey, _) => {
+                self.state = BypassState::Idle;
+                true
+            }
+            // Spaces or punctuation typed before the word keep the bypass armed
+            (BypassMode::NextWord, BypassState::Armed) => {
+                if in_word {
+                    self.state = BypassState::InWord;
+                }
+                in_word
+            }
+            (BypassMode::NextWord, BypassState::InWord) => {
+                if !in_word {
+                    self.state = BypassState::Idle;
+                }
+                in_word
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use clavfrancais_engine::keys::Key;
+
+    use super::{Bypass, BypassKey, BypassMode, BypassOptions};
+    use crate::window_engine::word_boundary::BoundaryKind;
+
+    fn bypass(mode: BypassMode) -> Bypass {
+        Bypass::new(BypassOptions {
+            key: BypassKey::ScrollLock,
+            mode,
+        })
+    }
+
+    #[test]
+    fn test_off() {
+        let mut bypass = bypass(BypassMode::Off);
+        bypass.key_down(&Key::ScrollLock);
+        assert!(!bypass.skips(BoundaryKind::None));
+    }
+
+    #[test]
+    fn test_other_keys_do_not_arm() {
+        let mut bypass = bypass(BypassMode::NextKey);
+        bypass.key_down(&Key::Escape);
+        assert!(!bypass.skips(BoundaryKind::None));
+    }
+
+    #[test]
+    fn test_next_key() {
+        let mut bypass = bypass(BypassMode::NextKey);
+        assert!(!bypass.skips(BoundaryKind::None));
+        bypass.key_down(&Key::ScrollLock);
+        assert!(bypass.skips(BoundaryKind::None));
+        assert!(!bypass.skips(BoundaryKind::None));
+    }
+
+    #[test]
+    fn test_next_word() {
+        let mut bypass = bypass(BypassMode::NextWord);
+        bypass.key_down(&Key::ScrollLock);
+        // A space before the word
+        assert!(!bypass.skips(BoundaryKind::Boundary));
+        assert!(bypass.skips(BoundaryKind::None));
+        assert!(bypass.skips(BoundaryKind::None));
+        assert!(!bypass.skips(BoundaryKind::Boundary));
+        assert!(!bypass.skips(BoundaryKind::None));
+    }
+
+    #[test]
+    fn test_hold() {
+        let mut bypass = bypass(BypassMode::Hold);
+        bypass.key_down(&Key::ScrollLock);
+        assert!(bypass.skips(BoundaryKind::None));
+        assert!(bypass.skips(BoundaryKind::Boundary));
+        // Auto repeat while the key is held
+        bypass.key_down(&Key::ScrollLock);
+        assert!(bypass.skips(BoundaryKind::None));
+        bypass.key_up(&Key::ScrollLock);
+        assert!(!bypass.skips(BoundaryKind::None));
+    }
+}
@@ -0,0 +1,179 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use super::replacement::Expansion;
+
+// Selected with Alt and the number keys 1 to 9
+pub const MAX_CANDIDATES: usize = 9;
+
+// How often each spelling was picked. The engine counts them and the app saves
+// them, both through clones of the same counts
+#[derive(Debug, Clone, Default)]
+pub struct CandidatePicks {
+    counts: Arc<Mutex<HashMap<String, u32>>>,
+}
+
+impl CandidatePicks {
+    pub fn new(counts: HashMap<String, u32>) -> Self {
+        Self {
+            counts: Arc::new(Mutex::new(counts)),
+        }
+    }
+
+    pub fn record(&self, word: &str) {
+        *self
+            .counts
+            .lock()
+            .unwrap()
+            .entry(word.to_lowercase())
+            .or_default() += 1;
+    }
+
+    fn count(&self, word: &str) -> u32 {
+        let counts = self.counts.lock().unwrap();
+        counts.get(word).copied().unwrap_or_default()
+    }
+
+    pub fn counts(&self) -> HashMap<String, u32> {
+        self.counts.lock().unwrap().clone()
+    }
+}
+
+// Clones are the same counts whatever they hold, picking a spelling is no reason to
+// restart the engine
+impl PartialEq for CandidatePicks {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.counts, &other.counts)
+    }
+}
+
+impl Eq for CandidatePicks {}
+
+// `words` as they are suggested, the preferred spellings first, then the ones
+// picked most often, then alphabetical order
+pub fn rank(words: &[String], preferences: &[String], picks: &CandidatePicks) -> Vec<String> {
+    let mut ranked = words.to_vec();
+    ranked.sort_by_key(|word| (!preferences.contains(word), Reverse(picks.count(word))));
+    ranked.truncate(MAX_CANDIDATES);
+    ranked
+}
+
+// The index of the candidate the number key `c` selects
+pub fn selection(c: char, len: usize) -> Option<usize> {
+    let index = c.to_digit(10)?.checked_sub(1)? as usize;
+    (index < len).then_some(index)
+}
+
+// The spellings offered for the word a boundary just ended
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Suggestion {
+    // Lowercase as in the word list
+    pub words: Vec<String>,
+    // Written with the case of the typed word
+    pub candidates: Vec<String>,
+    // Characters of the word on screen
+    pub word_len: usize,
+    pub boundary: char,
+}
+
+impl Suggestion {
+    // Replaces the word and its boundary, the number key picking it types nothing
+    pub fn select(&self, c: char) -> Option<(&str, Expansion)> {
+        let index = selection(c, self.candidates.len())?;
+        let expansion = Expansion {
+            replaced_len: self.word_len + 1,
+            text: format!("{}{}", self.candidates[index], self.boundary),
+        };
+        Some((&self.words[index], expansion))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{rank, selection, CandidatePicks, Suggestion, MAX_CANDIDATES};
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn test_list_order() {
+        let picks = CandidatePicks::default();
+        assert_eq!(rank(&words(&["ou", "où"]), &[], &picks), ["ou", "où"]);
+    }
+
+    #[test]
+    fn test_preferences_first() {
+        let picks = CandidatePicks::default();
+        assert_eq!(
+            rank(&words(&["ou", "où"]), &words(&["où"]), &picks),
+            ["où", "ou"]
+        );
+    }
+
+    #[test]
+    fn test_picked_words_first() {
+        let picks = CandidatePicks::default();
+        picks.record("Côte");
+        picks.record("côte");
+        picks.record("coté");
+        assert_eq!(
+            rank(&words(&["cote", "coté", "côte", "côté"]), &[], &picks),
+            ["côte", "coté", "cote", "côté"]
+        );
+        // A preference still comes first
+        assert_eq!(
+            rank(
+                &words(&["cote", "coté", "côte", "côté"]),
+                &words(&["côté"]),
+                &picks
+            ),
+            ["côté", "côte", "coté", "cote"]
+        );
+    }
+
+    #[test]
+    fn test_clones_share_counts() {
+        let picks = CandidatePicks::new([("où".to_string(), 2)].into());
+        let engine_picks = picks.clone();
+        engine_picks.record("là");
+        assert_eq!(picks, engine_picks);
+        assert_eq!(
+            picks.counts(),
+            [("où".to_string(), 2), ("là".to_string(), 1)].into()
+        );
+        assert_ne!(picks, CandidatePicks::new(picks.counts()));
+    }
+
+    #[test]
+    fn test_at_most_nine() {
+        let many: Vec<String> = (0..12).map(|i| i.to_string()).collect();
+        let picks = CandidatePicks::default();
+        assert_eq!(rank(&many, &[], &picks).len(), MAX_CANDIDATES);
+    }
+
+    #[test]
+    fn test_selection() {
+        assert_eq!(selection('1', 2), Some(0));
+        assert_eq!(selection('2', 2), Some(1));
+        assert_eq!(selection('3', 2), None);
+        assert_eq!(selection('0', 2), None);
+        assert_eq!(selection('a', 2), None);
+    }
+
+    #[test]
+    fn test_select() {
+        let suggestion = Suggestion {
+            words: words(&["la", "là"]),
+            candidates: words(&["La", "Là"]),
+            word_len: 2,
+            boundary: ' ',
+        };
+        let (word, expansion) = suggestion.select('2').unwrap();
+        assert_eq!(word, "là");
+        assert_eq!(expansion.replaced_len, 3);
+        assert_eq!(expansion.text, "Là ");
+        assert!(suggestion.select('3').is_none());
+    }
+}
@@ -0,0 +1,188 @@
+use clavfrancais_engine::engine::CombinationTarget;
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum CombinationKind {
+    Replace,
+    Combine,
+    Revert,
+}
+
+// Emitted to the frontend as `engine_event`, mirrored in src/types/engine-event.ts
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum EngineEvent {
+    Started,
+    Stopped,
+    Crashed {
+        message: String,
+    },
+    HookInstalled,
+    HookFailed {
+        message: String,
+    },
+    // Windows removed the hooks or the listener thread exited, the supervisor restarts it
+    HookLost,
+    Restarting {
+        delay_ms: u64,
+    },
+    Buffer {
+        content: String,
+    },
+    Combination {
+        kind: CombinationKind,
+        output: String,
+    },
+    // The last combination was undone, `output` is what was typed back
+    Undo {
+        output: String,
+    },
+    // An abbreviation was replaced, `output` is the expansion and the boundary typed after it
+    Snippet {
+        output: String,
+    },
+    // A word was accented from the word list, `output` is the accented word and its boundary
+    AccentCompletion {
+        output: String,
+    },
+    // A spelling was picked, the pick counts shared with the engine are worth saving
+    CandidatePicked,
+    // Spellings of the word just ended to pick from with Alt and the number keys,
+    // empty once they are no longer offered
+    Candidates {
+        candidates: Vec<String>,
+    },
+    // Keys typed in a password field, on the secure desktop or in an excluded app
+    // are neither processed nor reported until the input leaves it
+    SecureInput {
+        active: bool,
+    },
+    // Time the engine took to handle the input events of the last report interval,
+    // measured from the hook callback
+    Latency {
+        samples: u32,
+        p50_micros: u64,
+        p95_micros: u64,
+        max_micros: u64,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Default)]
+pub enum EngineStatus {
+    #[default]
+    Stopped,
+    Running,
+    Crashed,
+    HookFailed,
+    Recovering,
+}
+
+impl EngineEvent {
+    pub fn combination(target: &CombinationTarget) -> Self {
+        let (kind, output) = match target {
+            CombinationTarget::Replace(c) => (CombinationKind::Replace, c.to_string()),
+            CombinationTarget::Combine(a) => (CombinationKind::Combine, a.to_string()),
+            CombinationTarget::Revert(a, b) => (CombinationKind::Revert, format!("{}{}", a, b)),
+        };
+        Self::Combination { kind, output }
+    }
+
+    // The status the engine is in after this event, None when it does not change it
+    pub fn status(&self) -> Option<EngineStatus> {
+        match self {
+            Self::Started | Self::HookInstalled => Some(EngineStatus::Running),
+            Self::Stopped => Some(EngineStatus::Stopped),
+            Self::Crashed { .. } => Some(EngineStatus::Crashed),
+            Self::HookFailed { .. } => Some(EngineStatus::HookFailed),
+            Self::HookLost => Some(EngineStatus::Recovering),
+            Self::Restarting { .. }
+            | Self::Buffer { .. }
+            | Self::Combination { .. }
+            | Self::Undo { .. }
+            | Self::Snippet { .. }
+            | Self::AccentCompletion { .. }
+            | Self::CandidatePicked
+            | Self::Candidates { .. }
+            | Self::SecureInput { .. }
+            | Self::Latency { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use clavfrancais_engine::engine::CombinationTarget;
+
+    use super::{CombinationKind, EngineEvent};
+
+    #[test]
+    fn test_serialize() {
+        let table = [
+            (EngineEvent::Started, r#"{"type":"started"}"#),
+            (
+                EngineEvent::CandidatePicked,
+                r#"{"type":"candidatePicked"}"#,
+            ),
+            (
+                EngineEvent::HookFailed {
+                    message: "access denied".to_string(),
+                },
+                r#"{"type":"hookFailed","message":"access denied"}"#,
+            ),
+            (
+                EngineEvent::Buffer {
+                    content: "ecol".to_string(),
+                },
+                r#"{"type":"buffer","content":"ecol"}"#,
+            ),
+            (
+                EngineEvent::Combination {
+                    kind: CombinationKind::Combine,
+                    output: "é".to_string(),
+                },
+                r#"{"type":"combination","kind":"Combine","output":"é"}"#,
+            ),
+            (
+                EngineEvent::Restarting { delay_ms: 500 },
+                r#"{"type":"restarting","delayMs":500}"#,
+            ),
+            (
+                EngineEvent::Undo {
+                    output: "ew".to_string(),
+                },
+                r#"{"type":"undo","output":"ew"}"#,
+            ),
+            (
+                EngineEvent::SecureInput { active: true },
+                r#"{"type":"secureInput","active":true}"#,
+            ),
+            (
+                EngineEvent::Latency {
+                    samples: 12,
+                    p50_micros: 250,
+                    p95_micros: 900,
+                    max_micros: 1200,
+                },
+                r#"{"type":"latency","samples":12,"p50Micros":250,"p95Micros":900,"maxMicros":1200}"#,
+            ),
+        ];
+        for (event, expected) in table {
+            assert_eq!(serde_json::to_string(&event).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_combination() {
+        assert_eq!(
+            EngineEvent::combination(&CombinationTarget::Revert('e', 'w')),
+            EngineEvent::Combination {
+                kind: CombinationKind::Revert,
+                output: "ew".to_string(),
+            }
+        );
+    }
+}
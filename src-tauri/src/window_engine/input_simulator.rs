@@ -1,17 +1,31 @@
+#[cfg(windows)]
 use core::mem::size_of;
 
+#[cfg(windows)]
 use windows::Win32::UI::Input::KeyboardAndMouse::{
     SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
     KEYEVENTF_UNICODE, VIRTUAL_KEY, VK_BACK,
 };
 
-pub struct WindowsInputSimulator;
-
 pub trait InputSimulator {
     fn character(c: char);
     fn backspace();
 }
 
+// Without the keyboard hooks nothing is typed that would need replacing
+#[cfg(not(windows))]
+pub struct UnsupportedSimulator;
+
+#[cfg(not(windows))]
+impl InputSimulator for UnsupportedSimulator {
+    fn character(_c: char) {}
+    fn backspace() {}
+}
+
+#[cfg(windows)]
+pub struct WindowsInputSimulator;
+
+#[cfg(windows)]
 impl InputSimulator for WindowsInputSimulator {
     fn character(c: char) {
         unsafe {
@@ -0,0 +1,398 @@
+use clavfrancais_engine::keys::Key;
+
+use super::keys_converter::changes_focus;
+use super::modifiers::Modifiers;
+
+// Control characters produced by ToUnicodeEx while Ctrl is held
+const CTRL_A: char = '\u{1}';
+const CTRL_V: char = '\u{16}';
+const CTRL_X: char = '\u{18}';
+const CTRL_Y: char = '\u{19}';
+const CTRL_Z: char = '\u{1a}';
+const CTRL_BACKSPACE: char = '\u{7f}';
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum KeyEdit {
+    // A character typed at the caret
+    Insert(char),
+    // Removes the character before the caret
+    Backspace,
+    // Removes text after the caret or a selection. Every way of selecting text
+    // already invalidates the buffer so the buffer is still in sync
+    DeleteForward,
+    // Moves the caret or edits the document in a way the buffer cannot follow
+    Invalidate,
+    // Leaves the document untouched
+    Ignore,
+}
+
+pub fn classify_key(key: &Key, unicode_char: Option<char>, modifiers: Modifiers) -> KeyEdit {
+    match key {
+        Key::Backspace => {
+            if unicode_char == Some(CTRL_BACKSPACE) {
+                return KeyEdit::Invalidate;
+            }
+            return KeyEdit::Backspace;
+        }
+        Key::Delete => return KeyEdit::DeleteForward,
+        Key::Alt
+        | Key::AltGr
+        | Key::CapsLock
+        | Key::ControlLeft
+        | Key::ControlRight
+        | Key::ShiftLeft
+        | Key::ShiftRight
+        | Key::NumLock
+        | Key::ScrollLock
+        | Key::PrintScreen
+        | Key::Pause => return KeyEdit::Ignore,
+        Key::LeftArrow
+        | Key::RightArrow
+        | Key::UpArrow
+        | Key::DownArrow
+        | Key::Home
+        | Key::End
+        | Key::PageUp
+        | Key::PageDown
+        | Key::Return
+        | Key::Tab
+        | Key::Escape
+        | Key::Insert
+        | Key::MetaLeft
+        | Key::MetaRight
+        | Key::F1
+        | Key::F2
+        | Key::F3
+        | Key::F4
+        | Key::F5
+        | Key::F6
+        | Key::F7
+        | Key::F8
+        | Key::F9
+        | Key::F10
+        | Key::F11
+        | Key::F12 => return KeyEdit::Invalidate,
+        _ => {}
+    }
+
+    let Some(unicode_char) = unicode_char else {
+        if changes_focus(key) {
+            return KeyEdit::Invalidate;
+        }
+        return KeyEdit::Ignore;
+    };
+
+    match unicode_char {
+        // Select all, paste, cut, redo and undo
+        CTRL_A | CTRL_V | CTRL_X | CTRL_Y | CTRL_Z => KeyEdit::Invalidate,
+        c if c.is_control() => KeyEdit::Ignore,
+        // Menu accelerators and shortcuts such as Alt+E or Win+V may move the focus
+        _ if modifiers.is_chord() => KeyEdit::Invalidate,
+        c => KeyEdit::Insert(c),
+    }
+}
+
+// The number of the candidate Alt and a number key of the top row pick, by key
+// so that it works on layouts where those keys type symbols. Plain number keys
+// are typed, and Alt with the keypad keys types Alt codes
+pub fn candidate_number(key: &Key, modifiers: Modifiers) -> Option<char> {
+    let only_alt = modifiers.alt && !modifiers.alt_gr && !modifiers.ctrl && !modifiers.meta;
+    if !only_alt {
+        return None;
+    }
+    match key {
+        Key::Key1 => Some('1'),
+        Key::Key2 => Some('2'),
+        Key::Key3 => Some('3'),
+        Key::Key4 => Some('4'),
+        Key::Key5 => Some('5'),
+        Key::Key6 => Some('6'),
+        Key::Key7 => Some('7'),
+        Key::Key8 => Some('8'),
+        Key::Key9 => Some('9'),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use clavfrancais_engine::keys::Key;
+
+    use super::{candidate_number, classify_key, KeyEdit};
+    use crate::window_engine::keys_converter::{UNNAMED_KEYS, VIRTUAL_KEYS};
+    use crate::window_engine::modifiers::Modifiers;
+
+    fn key_table() -> Vec<(Key, Option<char>, KeyEdit)> {
+        vec![
+            (Key::Alt, None, KeyEdit::Ignore),
+            (Key::AltGr, None, KeyEdit::Ignore),
+            (Key::Backspace, Some('\u{8}'), KeyEdit::Backspace),
+            (Key::CapsLock, None, KeyEdit::Ignore),
+            (Key::ControlLeft, None, KeyEdit::Ignore),
+            (Key::ControlRight, None, KeyEdit::Ignore),
+            (Key::Delete, None, KeyEdit::DeleteForward),
+            (Key::DownArrow, None, KeyEdit::Invalidate),
+            (Key::End, None, KeyEdit::Invalidate),
+            (Key::Escape, Some('\u{1b}'), KeyEdit::Invalidate),
+            (Key::F1, None, KeyEdit::Invalidate),
+            (Key::F10, None, KeyEdit::Invalidate),
+            (Key::F11, None, KeyEdit::Invalidate),
+            (Key::F12, None, KeyEdit::Invalidate),
+            (Key::F2, None, KeyEdit::Invalidate),
+            (Key::F3, None, KeyEdit::Invalidate),
+            (Key::F4, None, KeyEdit::Invalidate),
+            (Key::F5, None, KeyEdit::Invalidate),
+            (Key::F6, None, KeyEdit::Invalidate),
+            (Key::F7, None, KeyEdit::Invalidate),
+            (Key::F8, None, KeyEdit::Invalidate),
+            (Key::F9, None, KeyEdit::Invalidate),
+            (Key::Home, None, KeyEdit::Invalidate),
+            (Key::LeftArrow, None, KeyEdit::Invalidate),
+            (Key::MetaLeft, None, KeyEdit::Invalidate),
+            (Key::MetaRight, None, KeyEdit::Invalidate),
+            (Key::PageDown, None, KeyEdit::Invalidate),
+            (Key::PageUp, None, KeyEdit::Invalidate),
+            (Key::Return, Some('\r'), KeyEdit::Invalidate),
+            (Key::RightArrow, None, KeyEdit::Invalidate),
+            (Key::ShiftLeft, None, KeyEdit::Ignore),
+            (Key::ShiftRight, None, KeyEdit::Ignore),
+            (Key::Space, Some(' '), KeyEdit::Insert(' ')),
+            (Key::Tab, Some('\t'), KeyEdit::Invalidate),
+            (Key::UpArrow, None, KeyEdit::Invalidate),
+            (Key::PrintScreen, None, KeyEdit::Ignore),
+            (Key::ScrollLock, None, KeyEdit::Ignore),
+            (Key::Pause, None, KeyEdit::Ignore),
+            (Key::NumLock, None, KeyEdit::Ignore),
+            (Key::BackQuote, Some('`'), KeyEdit::Insert('`')),
+            (Key::Key1, Some('1'), KeyEdit::Insert('1')),
+            (Key::Key2, Some('2'), KeyEdit::Insert('2')),
+            (Key::Key3, Some('3'), KeyEdit::Insert('3')),
+            (Key::Key4, Some('4'), KeyEdit::Insert('4')),
+            (Key::Key5, Some('5'), KeyEdit::Insert('5')),
+            (Key::Key6, Some('6'), KeyEdit::Insert('6')),
+            (Key::Key7, Some('7'), KeyEdit::Insert('7')),
+            (Key::Key8, Some('8'), KeyEdit::Insert('8')),
+            (Key::Key9, Some('9'), KeyEdit::Insert('9')),
+            (Key::Key0, Some('0'), KeyEdit::Insert('0')),
+            (Key::Minus, Some('-'), KeyEdit::Insert('-')),
+            (Key::Equal, Some('='), KeyEdit::Insert('=')),
+            (Key::Q, Some('q'), KeyEdit::Insert('q')),
+            (Key::W, Some('w'), KeyEdit::Insert('w')),
+            (Key::E, Some('e'), KeyEdit::Insert('e')),
+            (Key::R, Some('r'), KeyEdit::Insert('r')),
+            (Key::T, Some('t'), KeyEdit::Insert('t')),
+            (Key::Y, Some('y'), KeyEdit::Insert('y')),
+            (Key::U, Some('u'), KeyEdit::Insert('u')),
+            (Key::I, Some('i'), KeyEdit::Insert('i')),
+            (Key::O, Some('o'), KeyEdit::Insert('o')),
+            (Key::P, Some('p'), KeyEdit::Insert('p')),
+            (Key::LeftBracket, Some('['), KeyEdit::Insert('[')),
+            (Key::RightBracket, Some(']'), KeyEdit::Insert(']')),
+            (Key::A, Some('a'), KeyEdit::Insert('a')),
+            (Key::S, Some('s'), KeyEdit::Insert('s')),
+            (Key::D, Some('d'), KeyEdit::Insert('d')),
+            (Key::F, Some('f'), KeyEdit::Insert('f')),
+            (Key::G, Some('g'), KeyEdit::Insert('g')),
+            (Key::H, Some('h'), KeyEdit::Insert('h')),
+            (Key::J, Some('j'), KeyEdit::Insert('j')),
+            (Key::K, Some('k'), KeyEdit::Insert('k')),
+            (Key::L, Some('l'), KeyEdit::Insert('l')),
+            (Key::SemiColon, Some(';'), KeyEdit::Insert(';')),
+            (Key::Quote, Some('\''), KeyEdit::Insert('\'')),
+            (Key::BackSlash, Some('\\'), KeyEdit::Insert('\\')),
+            (Key::IntlBackslash, Some('<'), KeyEdit::Insert('<')),
+            (Key::Z, Some('z'), KeyEdit::Insert('z')),
+            (Key::X, Some('x'), KeyEdit::Insert('x')),
+            (Key::C, Some('c'), KeyEdit::Insert('c')),
+            (Key::V, Some('v'), KeyEdit::Insert('v')),
+            (Key::B, Some('b'), KeyEdit::Insert('b')),
+            (Key::N, Some('n'), KeyEdit::Insert('n')),
+            (Key::M, Some('m'), KeyEdit::Insert('m')),
+            (Key::Comma, Some(','), KeyEdit::Insert(',')),
+            (Key::Dot, Some('.'), KeyEdit::Insert('.')),
+            (Key::Slash, Some('/'), KeyEdit::Insert('/')),
+            (Key::Insert, None, KeyEdit::Invalidate),
+            (Key::NumpadMinus, Some('-'), KeyEdit::Insert('-')),
+            (Key::NumpadPlus, Some('+'), KeyEdit::Insert('+')),
+            (Key::NumpadMultiply, Some('*'), KeyEdit::Insert('*')),
+            (Key::NumpadDivide, Some('/'), KeyEdit::Insert('/')),
+            (Key::Numpad0, Some('0'), KeyEdit::Insert('0')),
+            (Key::Numpad1, Some('1'), KeyEdit::Insert('1')),
+            (Key::Numpad2, Some('2'), KeyEdit::Insert('2')),
+            (Key::Numpad3, Some('3'), KeyEdit::Insert('3')),
+            (Key::Numpad4, Some('4'), KeyEdit::Insert('4')),
+            (Key::Numpad5, Some('5'), KeyEdit::Insert('5')),
+            (Key::Numpad6, Some('6'), KeyEdit::Insert('6')),
+            (Key::Numpad7, Some('7'), KeyEdit::Insert('7')),
+            (Key::Numpad8, Some('8'), KeyEdit::Insert('8')),
+            (Key::Numpad9, Some('9'), KeyEdit::Insert('9')),
+            (Key::NumpadDecimal, Some('.'), KeyEdit::Insert('.')),
+        ]
+    }
+
+    #[test]
+    fn test_classify_every_key() {
+        for (key, unicode_char, expected) in key_table() {
+            assert_eq!(
+                classify_key(&key, unicode_char, Modifiers::default()),
+                expected,
+                "{:?} {:?}",
+                key,
+                unicode_char
+            );
+        }
+    }
+
+    #[test]
+    fn test_table_covers_every_key() {
+        let table = key_table();
+        for (key, _) in VIRTUAL_KEYS {
+            assert!(
+                table.iter().any(|(k, _, _)| k == key),
+                "{:?} is missing from the table",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn test_classify_unnamed_keys() {
+        for key in UNNAMED_KEYS {
+            let expected = if key.changes_focus {
+                KeyEdit::Invalidate
+            } else {
+                KeyEdit::Ignore
+            };
+            assert_eq!(
+                classify_key(
+                    &Key::Unknown(key.virtual_key_code),
+                    None,
+                    Modifiers::default()
+                ),
+                expected,
+                "{}",
+                key.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_classify_ctrl_shortcuts() {
+        let table = [
+            (Key::Backspace, Some('\u{7f}'), KeyEdit::Invalidate),
+            (Key::A, Some('\u{1}'), KeyEdit::Invalidate),
+            (Key::C, Some('\u{3}'), KeyEdit::Ignore),
+            (Key::S, Some('\u{13}'), KeyEdit::Ignore),
+            (Key::V, Some('\u{16}'), KeyEdit::Invalidate),
+            (Key::X, Some('\u{18}'), KeyEdit::Invalidate),
+            (Key::Y, Some('\u{19}'), KeyEdit::Invalidate),
+            (Key::Z, Some('\u{1a}'), KeyEdit::Invalidate),
+            (Key::Delete, Some('\u{7f}'), KeyEdit::DeleteForward),
+        ];
+        for (key, unicode_char, expected) in table {
+            assert_eq!(
+                classify_key(&key, unicode_char, Modifiers::default()),
+                expected,
+                "{:?}",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn test_classify_without_char() {
+        assert_eq!(
+            classify_key(&Key::A, None, Modifiers::default()),
+            KeyEdit::Ignore
+        );
+        assert_eq!(
+            classify_key(&Key::Key1, None, Modifiers::default()),
+            KeyEdit::Ignore
+        );
+    }
+
+    #[test]
+    fn test_classify_chords() {
+        let ctrl = Modifiers {
+            ctrl: true,
+            ..Default::default()
+        };
+        let alt = Modifiers {
+            alt: true,
+            ..Default::default()
+        };
+        let meta = Modifiers {
+            meta: true,
+            ..Default::default()
+        };
+        let alt_gr = Modifiers {
+            ctrl: true,
+            alt: true,
+            alt_gr: true,
+            ..Default::default()
+        };
+        let shift = Modifiers {
+            shift: true,
+            ..Default::default()
+        };
+
+        let table = [
+            (Key::E, Some('e'), alt, KeyEdit::Invalidate),
+            (Key::V, Some('v'), meta, KeyEdit::Invalidate),
+            (Key::C, Some('\u{3}'), ctrl, KeyEdit::Ignore),
+            (Key::Z, Some('\u{1a}'), ctrl, KeyEdit::Invalidate),
+            (Key::Backspace, Some('\u{7f}'), ctrl, KeyEdit::Invalidate),
+            (Key::E, Some('€'), alt_gr, KeyEdit::Insert('€')),
+            (Key::Key2, Some('@'), alt_gr, KeyEdit::Insert('@')),
+            (Key::E, Some('E'), shift, KeyEdit::Insert('E')),
+        ];
+        for (key, unicode_char, modifiers, expected) in table {
+            assert_eq!(
+                classify_key(&key, unicode_char, modifiers),
+                expected,
+                "{:?} {:?}",
+                key,
+                modifiers
+            );
+        }
+    }
+
+    #[test]
+    fn test_candidate_number() {
+        let alt = Modifiers {
+            alt: true,
+            ..Default::default()
+        };
+        let alt_gr = Modifiers {
+            ctrl: true,
+            alt: true,
+            alt_gr: true,
+            ..Default::default()
+        };
+        let ctrl_alt = Modifiers {
+            ctrl: true,
+            alt: true,
+            ..Default::default()
+        };
+
+        let table = [
+            (Key::Key2, alt, Some('2')),
+            (Key::Key9, alt, Some('9')),
+            (Key::Key0, alt, None),
+            (Key::Key2, Modifiers::default(), None),
+            (Key::Key2, alt_gr, None),
+            (Key::Key2, ctrl_alt, None),
+            (Key::Numpad2, alt, None),
+            (Key::E, alt, None),
+        ];
+        for (key, modifiers, expected) in table {
+            assert_eq!(
+                candidate_number(&key, modifiers),
+                expected,
+                "{:?} {:?}",
+                key,
+                modifiers
+            );
+        }
+    }
+}
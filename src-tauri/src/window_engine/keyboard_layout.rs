@@ -0,0 +1,435 @@
+use super::layout_tables::{CANADIAN_FRENCH, UK, US, US_INTERNATIONAL};
+use super::modifiers::Modifiers;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum KeyOutput {
+    None,
+    Char(char),
+    // A dead key, the character is what it types when followed by a space
+    Dead(char),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LayoutKey {
+    pub virtual_key: u8,
+    // Normal, Shift, AltGr and Shift+AltGr
+    pub outputs: [KeyOutput; 4],
+}
+
+// Letters a layout does not list type their lowercase and uppercase forms
+#[derive(Debug)]
+pub struct KeyboardLayout {
+    pub name: &'static str,
+    pub keys: &'static [LayoutKey],
+    // Compositions that differ from the usual ones, such as ' then c -> ç on US-International
+    pub compositions: &'static [(char, char, char)],
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LayoutId {
+    Us,
+    Uk,
+    UsInternational,
+    CanadianFrench,
+}
+
+impl LayoutId {
+    // The KLID names the layout in the registry, such as 00020409 for US-International
+    pub fn from_klid(klid: u32) -> Option<Self> {
+        match klid {
+            0x0000_0409 => Some(Self::Us),
+            0x0000_0809 => Some(Self::Uk),
+            0x0002_0409 => Some(Self::UsInternational),
+            0x0000_1009 => Some(Self::CanadianFrench),
+            _ => None,
+        }
+    }
+
+    pub fn layout(&self) -> &'static KeyboardLayout {
+        match self {
+            Self::Us => &US,
+            Self::Uk => &UK,
+            Self::UsInternational => &US_INTERNATIONAL,
+            Self::CanadianFrench => &CANADIAN_FRENCH,
+        }
+    }
+}
+
+// The low word of a HKL is the input language and the high word the layout. It is
+// the KLID of the base layouts, variants such as US-International or Dvorak get
+// a 0xFxxx handle instead, assigned per system. Its low bits are the "Layout Id"
+// of the variant in the registry, `variant_klid` looks it up
+pub fn klid_from_hkl(hkl: usize, variant_klid: impl FnOnce(u16) -> Option<u32>) -> Option<u32> {
+    let layout = ((hkl >> 16) & 0xFFFF) as u16;
+    if layout & 0xF000 == 0xF000 {
+        variant_klid(layout & 0x0FFF)
+    } else {
+        Some(layout as u32)
+    }
+}
+
+const VK_BACK: u32 = 0x08;
+const VK_TAB: u32 = 0x09;
+const VK_RETURN: u32 = 0x0D;
+const VK_ESCAPE: u32 = 0x1B;
+const VK_SPACE: u32 = 0x20;
+const VK_NUMPAD0: u32 = 0x60;
+const VK_NUMPAD9: u32 = 0x69;
+
+// Keys that type the same character on every layout, as ToUnicodeEx reports them
+fn common_char(virtual_key: u32) -> Option<char> {
+    match virtual_key {
+        VK_BACK => Some('\u{8}'),
+        VK_TAB => Some('\t'),
+        VK_RETURN => Some('\r'),
+        VK_ESCAPE => Some('\u{1b}'),
+        VK_SPACE => Some(' '),
+        VK_NUMPAD0..=VK_NUMPAD9 => char::from_digit(virtual_key - VK_NUMPAD0, 10),
+        0x6A => Some('*'),
+        0x6B => Some('+'),
+        0x6D => Some('-'),
+        0x6E => Some('.'),
+        0x6F => Some('/'),
+        _ => None,
+    }
+}
+
+// Ctrl without AltGr types control characters instead of text
+fn control_char(virtual_key: u32) -> Option<char> {
+    match virtual_key {
+        0x41..=0x5A => char::from_u32(virtual_key - 0x40),
+        VK_BACK => Some('\u{7f}'),
+        VK_RETURN => Some('\n'),
+        VK_SPACE => Some(' '),
+        _ => None,
+    }
+}
+
+impl KeyboardLayout {
+    pub fn output(&self, virtual_key: u32, modifiers: Modifiers, caps_lock: bool) -> KeyOutput {
+        // Windows treats Ctrl+Alt as AltGr
+        let alt_gr = modifiers.alt_gr || (modifiers.ctrl && modifiers.alt);
+        if modifiers.ctrl && !alt_gr {
+            return control_char(virtual_key).map_or(KeyOutput::None, KeyOutput::Char);
+        }
+
+        let outputs = match self.key(virtual_key) {
+            Some(outputs) => outputs,
+            None => match common_char(virtual_key) {
+                Some(c) if !alt_gr => return KeyOutput::Char(c),
+                _ => return KeyOutput::None,
+            },
+        };
+
+        // Caps lock only shifts the keys typing letters
+        let is_letter = matches!(outputs[0], KeyOutput::Char(c) if c.is_alphabetic());
+        let shift = modifiers.shift ^ (caps_lock && is_letter);
+        outputs[shift as usize + 2 * alt_gr as usize]
+    }
+
+    fn key(&self, virtual_key: u32) -> Option<[KeyOutput; 4]> {
+        if let Some(key) = self
+            .keys
+            .iter()
+            .find(|key| key.virtual_key as u32 == virtual_key)
+        {
+            return Some(key.outputs);
+        }
+
+        let letter = char::from_u32(virtual_key).filter(|c| c.is_ascii_uppercase())?;
+        Some([
+            KeyOutput::Char(letter.to_ascii_lowercase()),
+            KeyOutput::Char(letter),
+            KeyOutput::None,
+            KeyOutput::None,
+        ])
+    }
+}
+
+// Accented letters a dead key makes with each base letter, keyed by what the
+// dead key types alone
+fn compose(dead: char, base: char, compositions: &[(char, char, char)]) -> Option<char> {
+    if let Some((_, _, composed)) = compositions
+        .iter()
+        .find(|(accent, letter, _)| *accent == dead && *letter == base)
+    {
+        return Some(*composed);
+    }
+
+    let (bases, composed) = match dead {
+        '`' => ("aeiouAEIOU", "àèìòùÀÈÌÒÙ"),
+        '\'' | '´' => ("aeiouyAEIOUY", "áéíóúýÁÉÍÓÚÝ"),
+        '^' => ("aeiouAEIOU", "âêîôûÂÊÎÔÛ"),
+        '~' => ("anoANO", "ãñõÃÑÕ"),
+        '"' | '¨' => ("aeiouyAEIOUY", "äëïöüÿÄËÏÖÜŸ"),
+        '¸' => ("cC", "çÇ"),
+        _ => return None,
+    };
+    let index = bases.chars().position(|c| c == base)?;
+    composed.chars().nth(index)
+}
+
+// Combines dead keys with the key typed after them the way Windows does, without
+// touching the dead key state Windows keeps for the focused application
+#[derive(Debug, Default)]
+pub struct DeadKeyComposer {
+    pending: Option<char>,
+}
+
+impl DeadKeyComposer {
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    // Returns the characters typed, none while a dead key waits for the next key
+    pub fn feed(&mut self, output: KeyOutput, compositions: &[(char, char, char)]) -> Vec<char> {
+        let Some(dead) = self.pending else {
+            return match output {
+                KeyOutput::None => vec![],
+                KeyOutput::Char(c) => vec![c],
+                KeyOutput::Dead(dead) => {
+                    self.pending = Some(dead);
+                    vec![]
+                }
+            };
+        };
+
+        match output {
+            // Modifiers and keys typing nothing keep the dead key waiting
+            KeyOutput::None => vec![],
+            KeyOutput::Char(' ') => {
+                self.pending = None;
+                vec![dead]
+            }
+            // Backspace, Escape and other control characters cancel the dead key
+            KeyOutput::Char(c) if c.is_control() => {
+                self.pending = None;
+                vec![c]
+            }
+            KeyOutput::Char(c) => {
+                self.pending = None;
+                match compose(dead, c, compositions) {
+                    Some(composed) => vec![composed],
+                    None => vec![dead, c],
+                }
+            }
+            KeyOutput::Dead(next) => {
+                self.pending = None;
+                vec![dead, next]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{klid_from_hkl, DeadKeyComposer, KeyOutput, KeyboardLayout, LayoutId};
+    use crate::window_engine::modifiers::Modifiers;
+
+    const OEM_2: u32 = 0xBF;
+    const OEM_3: u32 = 0xC0;
+    const OEM_4: u32 = 0xDB;
+    const OEM_6: u32 = 0xDD;
+    const OEM_7: u32 = 0xDE;
+    const SHIFT: u32 = 0xA0;
+
+    #[derive(Clone, Copy)]
+    enum Press {
+        Key(u32),
+        Shift(u32),
+        AltGr(u32),
+        CtrlAlt(u32),
+        Ctrl(u32),
+        CapsLock(u32),
+    }
+
+    use Press::{AltGr, CapsLock, Ctrl, CtrlAlt, Key, Shift};
+
+    fn letter(c: char) -> u32 {
+        c.to_ascii_uppercase() as u32
+    }
+
+    fn word(word: &str) -> Vec<Press> {
+        word.chars().map(|c| Key(letter(c))).collect()
+    }
+
+    fn type_keys(layout: &KeyboardLayout, presses: &[Press]) -> String {
+        let mut composer = DeadKeyComposer::default();
+        let mut typed = String::new();
+        for press in presses {
+            let mut modifiers = Modifiers::default();
+            let mut caps_lock = false;
+            let virtual_key = match *press {
+                Key(virtual_key) => virtual_key,
+                Shift(virtual_key) => {
+                    modifiers.shift = true;
+                    virtual_key
+                }
+                AltGr(virtual_key) => {
+                    modifiers.alt_gr = true;
+                    modifiers.ctrl = true;
+                    modifiers.alt = true;
+                    virtual_key
+                }
+                CtrlAlt(virtual_key) => {
+                    modifiers.ctrl = true;
+                    modifiers.alt = true;
+                    virtual_key
+                }
+                Ctrl(virtual_key) => {
+                    modifiers.ctrl = true;
+                    virtual_key
+                }
+                CapsLock(virtual_key) => {
+                    caps_lock = true;
+                    virtual_key
+                }
+            };
+            let output = layout.output(virtual_key, modifiers, caps_lock);
+            typed.extend(composer.feed(output, layout.compositions));
+        }
+        typed
+    }
+
+    #[test]
+    fn test_us() {
+        let layout = LayoutId::Us.layout();
+        assert_eq!(type_keys(layout, &word("ecole")), "ecole");
+        assert_eq!(
+            type_keys(
+                layout,
+                &[Shift(b'2' as u32), Key(OEM_7), Shift(OEM_7), Key(0x20)]
+            ),
+            "@'\" "
+        );
+        assert_eq!(type_keys(layout, &[CapsLock(letter('a'))]), "A");
+        assert_eq!(type_keys(layout, &[CapsLock(b'1' as u32)]), "1");
+        assert_eq!(type_keys(layout, &[AltGr(letter('e'))]), "");
+    }
+
+    #[test]
+    fn test_uk() {
+        let layout = LayoutId::Uk.layout();
+        assert_eq!(
+            type_keys(
+                layout,
+                &[
+                    Shift(b'2' as u32),
+                    Shift(b'3' as u32),
+                    Shift(OEM_3),
+                    Key(OEM_7),
+                    AltGr(b'4' as u32),
+                ]
+            ),
+            "\"£@#€"
+        );
+        assert_eq!(type_keys(layout, &[AltGr(letter('e')), Shift(0xDF)]), "é¬");
+    }
+
+    #[test]
+    fn test_us_international_dead_keys() {
+        let layout = LayoutId::UsInternational.layout();
+        let table = [
+            (vec![Key(OEM_7), Key(letter('e'))], "é"),
+            (vec![Key(OEM_7), Key(0x20)], "'"),
+            (vec![Key(OEM_7), Key(letter('c'))], "ç"),
+            (vec![Key(OEM_7), Key(letter('t'))], "'t"),
+            (vec![Shift(OEM_7), Key(letter('u'))], "ü"),
+            (vec![Key(OEM_3), Key(letter('a'))], "à"),
+            (vec![Shift(b'6' as u32), Key(letter('o'))], "ô"),
+            (vec![Shift(OEM_3), Key(letter('n'))], "ñ"),
+            (vec![Key(OEM_7), Key(OEM_7)], "''"),
+            (vec![Key(OEM_7), Key(0x08)], "\u{8}"),
+            (vec![AltGr(letter('e')), CtrlAlt(letter('n'))], "éñ"),
+        ];
+        for (presses, expected) in table {
+            assert_eq!(type_keys(layout, &presses), expected);
+        }
+    }
+
+    #[test]
+    fn test_dead_key_waits_for_shift() {
+        let layout = LayoutId::UsInternational.layout();
+        assert_eq!(
+            type_keys(layout, &[Key(OEM_7), Key(SHIFT), Shift(letter('e'))]),
+            "É"
+        );
+    }
+
+    #[test]
+    fn test_canadian_french() {
+        let layout = LayoutId::CanadianFrench.layout();
+        let table = [
+            (vec![Key(OEM_4), Key(letter('e'))], "ê"),
+            (vec![Key(OEM_2)], "é"),
+            (vec![Shift(OEM_2)], "É"),
+            (vec![CapsLock(OEM_2)], "É"),
+            (vec![Key(OEM_6), Key(letter('c'))], "ç"),
+            (vec![Shift(OEM_6), Key(letter('i'))], "ï"),
+            (vec![Key(OEM_3), Key(letter('a'))], "à"),
+            (vec![AltGr(b'2' as u32), Shift(b'3' as u32)], "@/"),
+            (vec![AltGr(0x20)], "\u{a0}"),
+        ];
+        for (presses, expected) in table {
+            assert_eq!(type_keys(layout, &presses), expected);
+        }
+    }
+
+    #[test]
+    fn test_control_characters() {
+        let layout = LayoutId::Us.layout();
+        assert_eq!(
+            type_keys(layout, &[Ctrl(letter('v')), Ctrl(0x08), Key(0x08)]),
+            "\u{16}\u{7f}\u{8}"
+        );
+        assert_eq!(type_keys(layout, &[Ctrl(b'1' as u32)]), "");
+    }
+
+    fn layout_of_hkl(hkl: usize, variants: &[(u16, u32)]) -> Option<LayoutId> {
+        let klid = klid_from_hkl(hkl, |layout_id| {
+            variants
+                .iter()
+                .find(|(id, _)| *id == layout_id)
+                .map(|(_, klid)| *klid)
+        })?;
+        LayoutId::from_klid(klid)
+    }
+
+    #[test]
+    fn test_base_layouts() {
+        assert_eq!(layout_of_hkl(0x0409_0409, &[]), Some(LayoutId::Us));
+        assert_eq!(layout_of_hkl(0x0409_040C, &[]), Some(LayoutId::Us));
+        assert_eq!(layout_of_hkl(0x0809_0809, &[]), Some(LayoutId::Uk));
+        assert_eq!(
+            layout_of_hkl(0x1009_0C0C, &[]),
+            Some(LayoutId::CanadianFrench)
+        );
+        assert_eq!(layout_of_hkl(0x040C_040C, &[]), None);
+    }
+
+    // The handle of a variant depends on the system, only its KLID tells them apart
+    #[test]
+    fn test_layout_variants() {
+        let variants = [(0x001, 0x0001_0409), (0x002, 0x0002_0409)];
+        assert_eq!(layout_of_hkl(0xF001_0409, &variants), None);
+        assert_eq!(
+            layout_of_hkl(0xF002_0409, &variants),
+            Some(LayoutId::UsInternational)
+        );
+        assert_eq!(
+            layout_of_hkl(0xF002_040C, &variants),
+            Some(LayoutId::UsInternational)
+        );
+        assert_eq!(layout_of_hkl(0xF003_0409, &variants), None);
+    }
+
+    #[test]
+    fn test_composer_without_dead_key() {
+        let mut composer = DeadKeyComposer::default();
+        assert_eq!(composer.feed(KeyOutput::Char('a'), &[]), vec!['a']);
+        assert_eq!(composer.feed(KeyOutput::None, &[]), vec![]);
+        assert!(!composer.is_pending());
+        assert_eq!(composer.feed(KeyOutput::Dead('^'), &[]), vec![]);
+        assert!(composer.is_pending());
+    }
+}
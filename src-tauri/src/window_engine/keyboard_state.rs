@@ -0,0 +1,137 @@
+// Virtual key codes of the keys the state tracks, as Windows defines them
+pub const VK_SHIFT: u32 = 0x10;
+pub const VK_CONTROL: u32 = 0x11;
+pub const VK_MENU: u32 = 0x12;
+pub const VK_CAPITAL: u32 = 0x14;
+pub const VK_LWIN: u32 = 0x5B;
+pub const VK_RWIN: u32 = 0x5C;
+pub const VK_NUMLOCK: u32 = 0x90;
+pub const VK_SCROLL: u32 = 0x91;
+pub const VK_LSHIFT: u32 = 0xA0;
+pub const VK_RSHIFT: u32 = 0xA1;
+pub const VK_LCONTROL: u32 = 0xA2;
+pub const VK_RCONTROL: u32 = 0xA3;
+pub const VK_LMENU: u32 = 0xA4;
+pub const VK_RMENU: u32 = 0xA5;
+
+const DOWN: u8 = 0x80;
+const TOGGLED: u8 = 0x01;
+
+const TOGGLE_KEYS: [u32; 3] = [VK_CAPITAL, VK_NUMLOCK, VK_SCROLL];
+
+// Low level hooks report left and right modifiers, ToUnicodeEx also reads the generic key
+const SIDED_MODIFIERS: [(u32, u32, u32); 3] = [
+    (VK_SHIFT, VK_LSHIFT, VK_RSHIFT),
+    (VK_CONTROL, VK_LCONTROL, VK_RCONTROL),
+    (VK_MENU, VK_LMENU, VK_RMENU),
+];
+
+// The keyboard state as GetKeyboardState would report it when each key event was
+// received, rebuilt from the hook's key downs and ups since the translation stage
+// runs later and on another thread
+#[derive(Debug, Clone)]
+pub struct KeyboardState {
+    state: [u8; 256],
+}
+
+impl Default for KeyboardState {
+    fn default() -> Self {
+        Self { state: [0; 256] }
+    }
+}
+
+impl KeyboardState {
+    pub fn with_toggled(toggled: &[u32]) -> Self {
+        let mut keyboard_state = Self::default();
+        for virtual_key in toggled {
+            keyboard_state.state[*virtual_key as usize] |= TOGGLED;
+        }
+        keyboard_state
+    }
+
+    pub fn update(&mut self, virtual_key_code: u32, key_down: bool) {
+        let index = (virtual_key_code & 0xff) as usize;
+        let was_down = self.state[index] & DOWN != 0;
+
+        if key_down {
+            // Auto repeat sends more key downs, only the first one flips a lock
+            if !was_down && TOGGLE_KEYS.iter().any(|key| *key as usize == index) {
+                self.state[index] ^= TOGGLED;
+            }
+            self.state[index] |= DOWN;
+        } else {
+            self.state[index] &= !DOWN;
+        }
+
+        for (generic, left, right) in SIDED_MODIFIERS {
+            let down = self.is_down(left) || self.is_down(right);
+            if down {
+                self.state[generic as usize] |= DOWN;
+            } else {
+                self.state[generic as usize] &= !DOWN;
+            }
+        }
+    }
+
+    pub fn is_down(&self, virtual_key: u32) -> bool {
+        self.state[virtual_key as usize] & DOWN != 0
+    }
+
+    pub fn is_toggled(&self, virtual_key: u32) -> bool {
+        self.state[virtual_key as usize] & TOGGLED != 0
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 256] {
+        &self.state
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{KeyboardState, VK_CAPITAL, VK_LSHIFT, VK_MENU, VK_RMENU, VK_RSHIFT, VK_SHIFT};
+
+    const VK_A: u32 = 0x41;
+
+    #[test]
+    fn test_keys_down_and_up() {
+        let mut keyboard_state = KeyboardState::default();
+        keyboard_state.update(VK_A, true);
+        assert!(keyboard_state.is_down(VK_A));
+
+        keyboard_state.update(VK_A, false);
+        assert!(!keyboard_state.is_down(VK_A));
+    }
+
+    #[test]
+    fn test_generic_modifier_follows_both_sides() {
+        let mut keyboard_state = KeyboardState::default();
+        keyboard_state.update(VK_LSHIFT, true);
+        keyboard_state.update(VK_RSHIFT, true);
+        assert!(keyboard_state.is_down(VK_SHIFT));
+
+        keyboard_state.update(VK_LSHIFT, false);
+        assert!(keyboard_state.is_down(VK_SHIFT));
+
+        keyboard_state.update(VK_RSHIFT, false);
+        assert!(!keyboard_state.is_down(VK_SHIFT));
+
+        keyboard_state.update(VK_RMENU, true);
+        assert!(keyboard_state.is_down(VK_MENU));
+    }
+
+    #[test]
+    fn test_caps_lock_toggles_once_per_press() {
+        let mut keyboard_state = KeyboardState::with_toggled(&[VK_CAPITAL]);
+        assert!(keyboard_state.is_toggled(VK_CAPITAL));
+
+        keyboard_state.update(VK_CAPITAL, true);
+        keyboard_state.update(VK_CAPITAL, true);
+        keyboard_state.update(VK_CAPITAL, false);
+        assert!(!keyboard_state.is_toggled(VK_CAPITAL));
+        assert!(!keyboard_state.is_down(VK_CAPITAL));
+
+        keyboard_state.update(VK_CAPITAL, true);
+        keyboard_state.update(VK_CAPITAL, false);
+        assert!(keyboard_state.is_toggled(VK_CAPITAL));
+    }
+}
@@ -1,12 +1,59 @@
-use clavfrancais_engine::keys::Key;
+use clavfrancais_engine::keys::{Key, CHANGE_FOCUS_KEYS};
 
-pub trait KeyConverter {
+pub trait KeyConverter: Sized {
     fn to_virtual_key_code(&self) -> Option<u32>;
     fn from_virtual_key_code(virtual_key_code: u32) -> Self;
+    fn to_scan_code(&self) -> Option<u32>;
+    fn from_scan_code(scan_code: u32) -> Option<Self>;
+}
+
+// Scan codes of extended keys, such as the arrows or the right Ctrl, carry the
+// 0xE0 prefix in their high byte
+#[cfg(windows)]
+const EXTENDED_PREFIX: u32 = 0xE000;
+
+// The numpad Enter reports the same virtual key as Return
+const NUMPAD_RETURN_SCAN_CODE: u32 = 0xE01C;
+
+#[cfg(windows)]
+pub fn scan_code_with_prefix(scan_code: u32, extended: bool) -> u32 {
+    if extended {
+        scan_code | EXTENDED_PREFIX
+    } else {
+        scan_code
+    }
+}
+
+// The scan code without its prefix, as Windows APIs expect it
+#[cfg(windows)]
+pub fn scan_code_without_prefix(scan_code: u32) -> u32 {
+    scan_code & 0xFF
+}
+
+// A key the engine has no variant for, it is reported as Key::Unknown(code)
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct UnnamedKey {
+    pub name: &'static str,
+    pub virtual_key_code: u32,
+    pub scan_code: Option<u32>,
+    // Shortcut and application keys, the focused text may change under the caret
+    pub changes_focus: bool,
 }
 
 macro_rules! decl_keycodes {
-    ($($key:ident, $code:literal),*) => {
+    ($($key:ident, $code:literal, $scan_code:literal),*) => {
+        pub const VIRTUAL_KEYS: &[(Key, u32)] = &[
+            $(
+                (Key::$key, $code),
+            )*
+        ];
+
+        pub const SCAN_CODES: &[(Key, u32)] = &[
+            $(
+                (Key::$key, $scan_code),
+            )*
+        ];
+
         impl KeyConverter for Key {
             fn to_virtual_key_code(&self) -> Option<u32> {
                 match self {
@@ -26,122 +73,250 @@ macro_rules! decl_keycodes {
                     _ => Key::Unknown(virtual_key_code)
                 }
             }
+
+            fn to_scan_code(&self) -> Option<u32> {
+                match self {
+                    $(
+                        Key::$key => Some($scan_code),
+                    )*
+                    Key::Unknown(code) => unnamed_key(*code).and_then(|key| key.scan_code),
+                    _ => None,
+                }
+            }
+
+            fn from_scan_code(scan_code: u32) -> Option<Self> {
+                match scan_code {
+                    $(
+                        $scan_code => Some(Key::$key),
+                    )*
+                    NUMPAD_RETURN_SCAN_CODE => Some(Key::Return),
+                    _ => UNNAMED_KEYS
+                        .iter()
+                        .find(|key| key.scan_code == Some(scan_code))
+                        .map(|key| Key::Unknown(key.virtual_key_code)),
+                }
+            }
         }
     };
 }
 
+macro_rules! decl_unnamed_keycodes {
+    ($($name:ident, $code:literal, $scan_code:expr, $changes_focus:literal),*) => {
+        pub const UNNAMED_KEYS: &[UnnamedKey] = &[
+            $(
+                UnnamedKey {
+                    name: stringify!($name),
+                    virtual_key_code: $code,
+                    scan_code: $scan_code,
+                    changes_focus: $changes_focus,
+                },
+            )*
+        ];
+    };
+}
+
+pub fn unnamed_key(virtual_key_code: u32) -> Option<&'static UnnamedKey> {
+    UNNAMED_KEYS
+        .iter()
+        .find(|key| key.virtual_key_code == virtual_key_code)
+}
+
+pub fn changes_focus(key: &Key) -> bool {
+    match key {
+        Key::Unknown(code) => unnamed_key(*code).is_some_and(|key| key.changes_focus),
+        key => CHANGE_FOCUS_KEYS.contains(key),
+    }
+}
+
 // https://docs.microsoft.com/en-us/windows/win32/inputdev/virtual-key-codes
+// Scan codes are set 1, as the keyboard hook reports them
 decl_keycodes! {
-    Alt, 0xA4, // 164,
-    AltGr, 0xA5, //165,
-    Backspace, 0x08, // 8,
-    CapsLock, 0x14, // 20,
-    ControlLeft, 0xA2, // 162,
-    ControlRight, 0xA3, // 163,
-    Delete, 0x2E, // 46,
-    DownArrow, 0x28, // 40,
-    End, 0x23, // 35,
-    Escape, 0x1B, // 27,
-    F1, 0x70, // 112,
-    F10, 0x79, // 121,
-    F11, 0x7A, // 122,
-    F12, 0x7B, // 123,
-    F2, 0x71, // 113,
-    F3, 0x72, // 114,
-    F4, 0x73, // 115,
-    F5, 0x74, // 116,
-    F6, 0x75, // 117,
-    F7, 0x76, // 118,
-    F8, 0x77, // 119,
-    F9, 0x78, // 120,
-    Home, 0x24, // 36,
-    LeftArrow, 0x25, // 37,
-    MetaLeft, 0x5B, // 91,
-    PageDown, 0x22, // 34,
-    PageUp, 0x21, // 33,
-    Return, 0x0D, // 0x0D,
-    RightArrow, 0x27, // 39,
-    ShiftLeft, 0xA0, // 160,
-    ShiftRight, 0xA1, // 161,
-    Space, 0x20, // 32,
-    Tab, 0x09, // 0x09,
-    UpArrow, 0x26, // 38,
-    PrintScreen, 0x2C, // 44,
-    ScrollLock, 0x91, // 145,
-    Pause, 0x13, // 19,
-    NumLock, 0x90, // 144,
-    BackQuote, 0xC0, // 192,
-    Key1, 0x31, // 49,
-    Key2, 0x32, // 50,
-    Key3, 0x33, // 51,
-    Key4, 0x34, // 52,
-    Key5, 0x35, // 53,
-    Key6, 0x36, // 54,
-    Key7, 0x37, // 55,
-    Key8, 0x38, // 56,
-    Key9, 0x39, // 57,
-    Key0, 0x30, // 48,
-    Minus, 0xBD, // 189,
-    Equal, 0xBB, // 187,
-    Q, 0x51, // 81,
-    W, 0x57, // 87,
-    E, 0x45, // 69,
-    R, 0x52, // 82,
-    T, 0x54, // 84,
-    Y, 0x59, // 89,
-    U, 0x55, // 85,
-    I, 0x49, // 73,
-    O, 0x4F, // 79,
-    P, 0x50, // 80,
-    LeftBracket, 0xDB, // 219,
-    RightBracket, 0xDD, // 221,
-    A, 0x41, // 65,
-    S, 0x53, // 83,
-    D, 0x44, // 68,
-    F, 0x46, // 70,
-    G, 0x47, // 71,
-    H, 0x48, // 72,
-    J, 0x4A, // 74,
-    K, 0x4B, // 75,
-    L, 0x4C, // 76,
-    SemiColon, 0xBA, // 186,
-    Quote, 0xDE, // 222,
-    BackSlash, 0xDC, // 220,
-    IntlBackslash, 0xE2, // 226,
-    Z, 0x5A, // 90,
-    X, 0x58, // 88,
-    C, 0x43, // 67,
-    V, 0x56, // 86,
-    B, 0x42, // 66,
-    N, 0x4E, // 78,
-    M, 0x4D, // 77,
-    Comma, 0xBC, // 188,
-    Dot, 0xBE, // 190,
-    Slash, 0xBF, // 191,
-    Insert, 0x2D, // 45,
-    NumpadMinus, 0x6D, // 109,
-    NumpadPlus, 0x6B, // 107,
-    NumpadMultiply, 0x6A, // 106,
-    NumpadDivide, 0x6F, // 111,
-    Numpad0, 0x60, // 96,
-    Numpad1, 0x61, // 97,
-    Numpad2, 0x62, // 98,
-    Numpad3, 0x63, // 99,
-    Numpad4, 0x64, // 100,
-    Numpad5, 0x65, // 101,
-    Numpad6, 0x66, // 102,
-    Numpad7, 0x67, // 103,
-    Numpad8, 0x68, // 104,
-    Numpad9, 0x69, // 105,
-    NumpadDecimal, 0x6E // 110
+    Alt, 0xA4, 0x38, // 164
+    AltGr, 0xA5, 0xE038, // 165
+    Backspace, 0x08, 0x0E, // 8
+    CapsLock, 0x14, 0x3A, // 20
+    ControlLeft, 0xA2, 0x1D, // 162
+    ControlRight, 0xA3, 0xE01D, // 163
+    Delete, 0x2E, 0xE053, // 46
+    DownArrow, 0x28, 0xE050, // 40
+    End, 0x23, 0xE04F, // 35
+    Escape, 0x1B, 0x01, // 27
+    F1, 0x70, 0x3B, // 112
+    F10, 0x79, 0x44, // 121
+    F11, 0x7A, 0x57, // 122
+    F12, 0x7B, 0x58, // 123
+    F2, 0x71, 0x3C, // 113
+    F3, 0x72, 0x3D, // 114
+    F4, 0x73, 0x3E, // 115
+    F5, 0x74, 0x3F, // 116
+    F6, 0x75, 0x40, // 117
+    F7, 0x76, 0x41, // 118
+    F8, 0x77, 0x42, // 119
+    F9, 0x78, 0x43, // 120
+    Home, 0x24, 0xE047, // 36
+    LeftArrow, 0x25, 0xE04B, // 37
+    MetaLeft, 0x5B, 0xE05B, // 91
+    MetaRight, 0x5C, 0xE05C, // 92
+    PageDown, 0x22, 0xE051, // 34
+    PageUp, 0x21, 0xE049, // 33
+    Return, 0x0D, 0x1C, // 13
+    RightArrow, 0x27, 0xE04D, // 39
+    ShiftLeft, 0xA0, 0x2A, // 160
+    ShiftRight, 0xA1, 0x36, // 161
+    Space, 0x20, 0x39, // 32
+    Tab, 0x09, 0x0F, // 9
+    UpArrow, 0x26, 0xE048, // 38
+    PrintScreen, 0x2C, 0xE037, // 44
+    ScrollLock, 0x91, 0x46, // 145
+    Pause, 0x13, 0x45, // 19
+    NumLock, 0x90, 0xE045, // 144
+    BackQuote, 0xC0, 0x29, // 192
+    Key1, 0x31, 0x02, // 49
+    Key2, 0x32, 0x03, // 50
+    Key3, 0x33, 0x04, // 51
+    Key4, 0x34, 0x05, // 52
+    Key5, 0x35, 0x06, // 53
+    Key6, 0x36, 0x07, // 54
+    Key7, 0x37, 0x08, // 55
+    Key8, 0x38, 0x09, // 56
+    Key9, 0x39, 0x0A, // 57
+    Key0, 0x30, 0x0B, // 48
+    Minus, 0xBD, 0x0C, // 189
+    Equal, 0xBB, 0x0D, // 187
+    Q, 0x51, 0x10, // 81
+    W, 0x57, 0x11, // 87
+    E, 0x45, 0x12, // 69
+    R, 0x52, 0x13, // 82
+    T, 0x54, 0x14, // 84
+    Y, 0x59, 0x15, // 89
+    U, 0x55, 0x16, // 85
+    I, 0x49, 0x17, // 73
+    O, 0x4F, 0x18, // 79
+    P, 0x50, 0x19, // 80
+    LeftBracket, 0xDB, 0x1A, // 219
+    RightBracket, 0xDD, 0x1B, // 221
+    A, 0x41, 0x1E, // 65
+    S, 0x53, 0x1F, // 83
+    D, 0x44, 0x20, // 68
+    F, 0x46, 0x21, // 70
+    G, 0x47, 0x22, // 71
+    H, 0x48, 0x23, // 72
+    J, 0x4A, 0x24, // 74
+    K, 0x4B, 0x25, // 75
+    L, 0x4C, 0x26, // 76
+    SemiColon, 0xBA, 0x27, // 186
+    Quote, 0xDE, 0x28, // 222
+    BackSlash, 0xDC, 0x2B, // 220
+    IntlBackslash, 0xE2, 0x56, // 226
+    Z, 0x5A, 0x2C, // 90
+    X, 0x58, 0x2D, // 88
+    C, 0x43, 0x2E, // 67
+    V, 0x56, 0x2F, // 86
+    B, 0x42, 0x30, // 66
+    N, 0x4E, 0x31, // 78
+    M, 0x4D, 0x32, // 77
+    Comma, 0xBC, 0x33, // 188
+    Dot, 0xBE, 0x34, // 190
+    Slash, 0xBF, 0x35, // 191
+    Insert, 0x2D, 0xE052, // 45
+    NumpadMinus, 0x6D, 0x4A, // 109
+    NumpadPlus, 0x6B, 0x4E, // 107
+    NumpadMultiply, 0x6A, 0x37, // 106
+    NumpadDivide, 0x6F, 0xE035, // 111
+    Numpad0, 0x60, 0x52, // 96
+    Numpad1, 0x61, 0x4F, // 97
+    Numpad2, 0x62, 0x50, // 98
+    Numpad3, 0x63, 0x51, // 99
+    Numpad4, 0x64, 0x4B, // 100
+    Numpad5, 0x65, 0x4C, // 101
+    Numpad6, 0x66, 0x4D, // 102
+    Numpad7, 0x67, 0x47, // 103
+    Numpad8, 0x68, 0x48, // 104
+    Numpad9, 0x69, 0x49, // 105
+    NumpadDecimal, 0x6E, 0x53 // 110
+}
+
+// Virtual keys without a Key variant. The OEM keys share their position with
+// named keys depending on the layout, so they have no scan code of their own
+decl_unnamed_keycodes! {
+    Clear, 0x0C, None, false, // 12
+    Kana, 0x15, Some(0x70), true, // 21
+    Kanji, 0x19, None, true, // 25
+    Convert, 0x1C, Some(0x79), true, // 28
+    NonConvert, 0x1D, Some(0x7B), true, // 29
+    Apps, 0x5D, Some(0xE05D), true, // 93
+    Sleep, 0x5F, Some(0xE05F), true, // 95
+    NumpadSeparator, 0x6C, Some(0x7E), false, // 108
+    F13, 0x7C, Some(0x64), true, // 124
+    F14, 0x7D, Some(0x65), true, // 125
+    F15, 0x7E, Some(0x66), true, // 126
+    F16, 0x7F, Some(0x67), true, // 127
+    F17, 0x80, Some(0x68), true, // 128
+    F18, 0x81, Some(0x69), true, // 129
+    F19, 0x82, Some(0x6A), true, // 130
+    F20, 0x83, Some(0x6B), true, // 131
+    F21, 0x84, Some(0x6C), true, // 132
+    F22, 0x85, Some(0x6D), true, // 133
+    F23, 0x86, Some(0x6E), true, // 134
+    F24, 0x87, Some(0x76), true, // 135
+    BrowserBack, 0xA6, Some(0xE06A), true, // 166
+    BrowserForward, 0xA7, Some(0xE069), true, // 167
+    BrowserRefresh, 0xA8, Some(0xE067), true, // 168
+    BrowserStop, 0xA9, Some(0xE068), true, // 169
+    BrowserSearch, 0xAA, Some(0xE065), true, // 170
+    BrowserFavorites, 0xAB, Some(0xE066), true, // 171
+    BrowserHome, 0xAC, Some(0xE032), true, // 172
+    VolumeMute, 0xAD, Some(0xE020), false, // 173
+    VolumeDown, 0xAE, Some(0xE02E), false, // 174
+    VolumeUp, 0xAF, Some(0xE030), false, // 175
+    MediaNextTrack, 0xB0, Some(0xE019), false, // 176
+    MediaPrevTrack, 0xB1, Some(0xE010), false, // 177
+    MediaStop, 0xB2, Some(0xE024), false, // 178
+    MediaPlayPause, 0xB3, Some(0xE022), false, // 179
+    LaunchMail, 0xB4, Some(0xE06C), true, // 180
+    LaunchMediaSelect, 0xB5, Some(0xE06D), true, // 181
+    LaunchApp1, 0xB6, Some(0xE06B), true, // 182
+    LaunchApp2, 0xB7, Some(0xE021), true, // 183
+    Oem8, 0xDF, None, false, // 223
+    OemAx, 0xE1, None, false // 225
 }
 
 #[cfg(test)]
 mod test {
-    use super::KeyConverter;
-    use crate::keys::Key;
+    use std::collections::HashSet;
+
+    use clavfrancais_engine::keys::Key;
+
+    use super::{
+        changes_focus, KeyConverter, NUMPAD_RETURN_SCAN_CODE, SCAN_CODES, UNNAMED_KEYS,
+        VIRTUAL_KEYS,
+    };
+
+    // Every virtual key Windows assigns to a physical key. The generic Shift,
+    // Ctrl and Alt codes are left out, the hook reports the left or right one
+    fn physical_virtual_keys() -> Vec<u32> {
+        [
+            0x08..=0x09,
+            0x0C..=0x0D,
+            0x13..=0x15,
+            0x19..=0x19,
+            0x1B..=0x1D,
+            0x20..=0x28,
+            0x2C..=0x2E,
+            0x30..=0x39,
+            0x41..=0x5D,
+            0x5F..=0x87,
+            0x90..=0x91,
+            0xA0..=0xB7,
+            0xBA..=0xC0,
+            0xDB..=0xDF,
+            0xE1..=0xE2,
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
 
-    // use super::{code_from_key, key_from_code};
     #[test]
     fn test_reversible() {
         for code in 0..65535 {
@@ -153,4 +328,80 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_scan_codes_reversible() {
+        for (key, scan_code) in SCAN_CODES {
+            assert_eq!(key.to_scan_code(), Some(*scan_code), "{:?}", key);
+            assert_eq!(Key::from_scan_code(*scan_code), Some(*key), "{:?}", key);
+        }
+        for key in UNNAMED_KEYS {
+            let unknown = Key::Unknown(key.virtual_key_code);
+            assert_eq!(unknown.to_scan_code(), key.scan_code, "{}", key.name);
+            if let Some(scan_code) = key.scan_code {
+                assert_eq!(
+                    Key::from_scan_code(scan_code),
+                    Some(unknown),
+                    "{}",
+                    key.name
+                );
+            }
+        }
+        assert_eq!(
+            Key::from_scan_code(NUMPAD_RETURN_SCAN_CODE),
+            Some(Key::Return)
+        );
+        assert_eq!(Key::from_scan_code(0xE0FF), None);
+    }
+
+    #[test]
+    fn test_every_physical_key_is_mapped() {
+        for code in physical_virtual_keys() {
+            let named = VIRTUAL_KEYS.iter().any(|(_, c)| *c == code);
+            let unnamed = UNNAMED_KEYS.iter().any(|key| key.virtual_key_code == code);
+            assert!(named ^ unnamed, "{:#04X} must be mapped once", code);
+        }
+    }
+
+    #[test]
+    fn test_every_named_key_has_a_scan_code() {
+        for (key, _) in VIRTUAL_KEYS {
+            assert!(
+                SCAN_CODES.iter().any(|(k, _)| k == key),
+                "{:?} has no scan code",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn test_codes_are_unique() {
+        let mut virtual_key_codes = HashSet::new();
+        let mut scan_codes = HashSet::new();
+        for (_, code) in VIRTUAL_KEYS {
+            assert!(virtual_key_codes.insert(*code), "{:#04X}", code);
+        }
+        for (_, scan_code) in SCAN_CODES {
+            assert!(scan_codes.insert(*scan_code), "{:#06X}", scan_code);
+        }
+        for key in UNNAMED_KEYS {
+            assert!(
+                virtual_key_codes.insert(key.virtual_key_code),
+                "{}",
+                key.name
+            );
+            if let Some(scan_code) = key.scan_code {
+                assert!(scan_codes.insert(scan_code), "{}", key.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_changes_focus() {
+        assert!(changes_focus(&Key::Unknown(0x7C)));
+        assert!(changes_focus(&Key::Unknown(0x5D)));
+        assert!(changes_focus(&Key::Unknown(0xA6)));
+        assert!(!changes_focus(&Key::Unknown(0xAF)));
+        assert!(!changes_focus(&Key::Unknown(0xFF)));
+    }
 }
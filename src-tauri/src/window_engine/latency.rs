@@ -0,0 +1,120 @@
+use std::time::{Duration, Instant};
+
+use super::engine_event::EngineEvent;
+
+// The frontend shows a summary, sending each key's latency would cost more than measuring it
+pub const REPORT_INTERVAL: Duration = Duration::from_secs(2);
+// Keys past this many in one interval are not measured, the first ones are enough to summarize
+const MAX_SAMPLES: usize = 1024;
+
+// Time the engine took to handle each input event, from the hook callback to the
+// replacement being sent, reported as percentiles once per interval
+#[derive(Debug)]
+pub struct LatencyRecorder {
+    micros: Vec<u64>,
+    last_report: Instant,
+}
+
+impl LatencyRecorder {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            micros: Vec::with_capacity(MAX_SAMPLES),
+            last_report: now,
+        }
+    }
+
+    pub fn record(&mut self, latency: Duration) {
+        if self.micros.len() < MAX_SAMPLES {
+            self.micros.push(latency.as_micros() as u64);
+        }
+    }
+
+    // The summary of the latencies recorded since the last one, once the interval is
+    // over and something was typed
+    pub fn report(&mut self, now: Instant) -> Option<EngineEvent> {
+        if now.saturating_duration_since(self.last_report) < REPORT_INTERVAL {
+            return None;
+        }
+        self.last_report = now;
+        if self.micros.is_empty() {
+            return None;
+        }
+
+        self.micros.sort_unstable();
+        // Nearest rank, the smallest sample at least `percent` of them do not exceed
+        let percentile = |percent: usize| {
+            let rank = (self.micros.len() * percent).div_ceil(100);
+            self.micros[rank.max(1) - 1]
+        };
+        let event = EngineEvent::Latency {
+            samples: self.micros.len() as u32,
+            p50_micros: percentile(50),
+            p95_micros: percentile(95),
+            max_micros: percentile(100),
+        };
+        self.micros.clear();
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::{LatencyRecorder, MAX_SAMPLES, REPORT_INTERVAL};
+    use crate::window_engine::engine_event::EngineEvent;
+
+    #[test]
+    fn test_percentiles() {
+        let start = Instant::now();
+        let mut recorder = LatencyRecorder::new(start);
+        for micros in (1..=100).rev() {
+            recorder.record(Duration::from_micros(micros));
+        }
+        assert_eq!(
+            recorder.report(start + REPORT_INTERVAL),
+            Some(EngineEvent::Latency {
+                samples: 100,
+                p50_micros: 50,
+                p95_micros: 95,
+                max_micros: 100,
+            })
+        );
+
+        recorder.record(Duration::from_micros(300));
+        assert_eq!(
+            recorder.report(start + REPORT_INTERVAL * 2),
+            Some(EngineEvent::Latency {
+                samples: 1,
+                p50_micros: 300,
+                p95_micros: 300,
+                max_micros: 300,
+            })
+        );
+    }
+
+    #[test]
+    fn test_reports_once_per_interval() {
+        let start = Instant::now();
+        let mut recorder = LatencyRecorder::new(start);
+        recorder.record(Duration::from_micros(100));
+        assert_eq!(recorder.report(start + REPORT_INTERVAL / 2), None);
+        assert!(recorder.report(start + REPORT_INTERVAL).is_some());
+
+        // Nothing typed, nothing to report
+        assert_eq!(recorder.report(start + REPORT_INTERVAL * 3), None);
+    }
+
+    #[test]
+    fn test_samples_are_capped() {
+        let start = Instant::now();
+        let mut recorder = LatencyRecorder::new(start);
+        for _ in 0..MAX_SAMPLES * 2 {
+            recorder.record(Duration::from_micros(100));
+        }
+        assert!(matches!(
+            recorder.report(start + REPORT_INTERVAL),
+            Some(EngineEvent::Latency { samples, .. }) if samples as usize == MAX_SAMPLES
+        ));
+    }
+}
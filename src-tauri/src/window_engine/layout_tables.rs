@@ -0,0 +1,176 @@
+use super::keyboard_layout::{
+    KeyOutput::{self, Char as C, Dead as D, None as N},
+    KeyboardLayout, LayoutKey,
+};
+
+// Virtual key codes of the OEM keys, their position depends on the layout
+const OEM_1: u8 = 0xBA;
+const OEM_PLUS: u8 = 0xBB;
+const OEM_COMMA: u8 = 0xBC;
+const OEM_MINUS: u8 = 0xBD;
+const OEM_PERIOD: u8 = 0xBE;
+const OEM_2: u8 = 0xBF;
+const OEM_3: u8 = 0xC0;
+const OEM_4: u8 = 0xDB;
+const OEM_5: u8 = 0xDC;
+const OEM_6: u8 = 0xDD;
+const OEM_7: u8 = 0xDE;
+const OEM_8: u8 = 0xDF;
+const OEM_102: u8 = 0xE2;
+const SPACE: u8 = 0x20;
+
+// Normal, Shift, AltGr and Shift+AltGr
+const fn key(virtual_key: u8, outputs: [KeyOutput; 4]) -> LayoutKey {
+    LayoutKey {
+        virtual_key,
+        outputs,
+    }
+}
+
+// 00000409
+pub static US: KeyboardLayout = KeyboardLayout {
+    name: "US",
+    keys: &[
+        key(b'1', [C('1'), C('!'), N, N]),
+        key(b'2', [C('2'), C('@'), N, N]),
+        key(b'3', [C('3'), C('#'), N, N]),
+        key(b'4', [C('4'), C('$'), N, N]),
+        key(b'5', [C('5'), C('%'), N, N]),
+        key(b'6', [C('6'), C('^'), N, N]),
+        key(b'7', [C('7'), C('&'), N, N]),
+        key(b'8', [C('8'), C('*'), N, N]),
+        key(b'9', [C('9'), C('('), N, N]),
+        key(b'0', [C('0'), C(')'), N, N]),
+        key(OEM_MINUS, [C('-'), C('_'), N, N]),
+        key(OEM_PLUS, [C('='), C('+'), N, N]),
+        key(OEM_4, [C('['), C('{'), N, N]),
+        key(OEM_6, [C(']'), C('}'), N, N]),
+        key(OEM_5, [C('\\'), C('|'), N, N]),
+        key(OEM_1, [C(';'), C(':'), N, N]),
+        key(OEM_7, [C('\''), C('"'), N, N]),
+        key(OEM_3, [C('`'), C('~'), N, N]),
+        key(OEM_COMMA, [C(','), C('<'), N, N]),
+        key(OEM_PERIOD, [C('.'), C('>'), N, N]),
+        key(OEM_2, [C('/'), C('?'), N, N]),
+        key(OEM_102, [C('\\'), C('|'), N, N]),
+    ],
+    compositions: &[],
+};
+
+// 00000809
+pub static UK: KeyboardLayout = KeyboardLayout {
+    name: "United Kingdom",
+    keys: &[
+        key(b'1', [C('1'), C('!'), N, N]),
+        key(b'2', [C('2'), C('"'), N, N]),
+        key(b'3', [C('3'), C('£'), N, N]),
+        key(b'4', [C('4'), C('$'), C('€'), N]),
+        key(b'5', [C('5'), C('%'), N, N]),
+        key(b'6', [C('6'), C('^'), N, N]),
+        key(b'7', [C('7'), C('&'), N, N]),
+        key(b'8', [C('8'), C('*'), N, N]),
+        key(b'9', [C('9'), C('('), N, N]),
+        key(b'0', [C('0'), C(')'), N, N]),
+        key(OEM_MINUS, [C('-'), C('_'), N, N]),
+        key(OEM_PLUS, [C('='), C('+'), N, N]),
+        key(b'E', [C('e'), C('E'), C('é'), C('É')]),
+        key(b'U', [C('u'), C('U'), C('ú'), C('Ú')]),
+        key(b'I', [C('i'), C('I'), C('í'), C('Í')]),
+        key(b'O', [C('o'), C('O'), C('ó'), C('Ó')]),
+        key(OEM_4, [C('['), C('{'), N, N]),
+        key(OEM_6, [C(']'), C('}'), N, N]),
+        key(b'A', [C('a'), C('A'), C('á'), C('Á')]),
+        key(OEM_1, [C(';'), C(':'), N, N]),
+        key(OEM_3, [C('\''), C('@'), N, N]),
+        key(OEM_7, [C('#'), C('~'), N, N]),
+        key(OEM_8, [C('`'), C('¬'), C('¦'), N]),
+        key(OEM_5, [C('\\'), C('|'), N, N]),
+        key(OEM_102, [C('\\'), C('|'), N, N]),
+        key(OEM_COMMA, [C(','), C('<'), N, N]),
+        key(OEM_PERIOD, [C('.'), C('>'), N, N]),
+        key(OEM_2, [C('/'), C('?'), N, N]),
+    ],
+    compositions: &[],
+};
+
+// 00020409
+pub static US_INTERNATIONAL: KeyboardLayout = KeyboardLayout {
+    name: "United States-International",
+    keys: &[
+        key(b'1', [C('1'), C('!'), C('¡'), C('¹')]),
+        key(b'2', [C('2'), C('@'), C('²'), N]),
+        key(b'3', [C('3'), C('#'), C('³'), N]),
+        key(b'4', [C('4'), C('$'), C('¤'), C('£')]),
+        key(b'5', [C('5'), C('%'), C('€'), N]),
+        key(b'6', [C('6'), D('^'), C('¼'), N]),
+        key(b'7', [C('7'), C('&'), C('½'), N]),
+        key(b'8', [C('8'), C('*'), C('¾'), N]),
+        key(b'9', [C('9'), C('('), C('‘'), N]),
+        key(b'0', [C('0'), C(')'), C('’'), N]),
+        key(OEM_MINUS, [C('-'), C('_'), C('¥'), N]),
+        key(OEM_PLUS, [C('='), C('+'), C('×'), C('÷')]),
+        key(b'Q', [C('q'), C('Q'), C('ä'), C('Ä')]),
+        key(b'W', [C('w'), C('W'), C('å'), C('Å')]),
+        key(b'E', [C('e'), C('E'), C('é'), C('É')]),
+        key(b'R', [C('r'), C('R'), C('®'), N]),
+        key(b'T', [C('t'), C('T'), C('þ'), C('Þ')]),
+        key(b'Y', [C('y'), C('Y'), C('ü'), C('Ü')]),
+        key(b'U', [C('u'), C('U'), C('ú'), C('Ú')]),
+        key(b'I', [C('i'), C('I'), C('í'), C('Í')]),
+        key(b'O', [C('o'), C('O'), C('ó'), C('Ó')]),
+        key(b'P', [C('p'), C('P'), C('ö'), C('Ö')]),
+        key(OEM_4, [C('['), C('{'), C('«'), N]),
+        key(OEM_6, [C(']'), C('}'), C('»'), N]),
+        key(OEM_5, [C('\\'), C('|'), C('¬'), C('¦')]),
+        key(b'A', [C('a'), C('A'), C('á'), C('Á')]),
+        key(b'S', [C('s'), C('S'), C('ß'), C('§')]),
+        key(b'D', [C('d'), C('D'), C('ð'), C('Ð')]),
+        key(b'L', [C('l'), C('L'), C('ø'), C('Ø')]),
+        key(OEM_1, [C(';'), C(':'), C('¶'), C('°')]),
+        key(OEM_7, [D('\''), D('"'), C('´'), C('¨')]),
+        key(OEM_3, [D('`'), D('~'), N, N]),
+        key(b'Z', [C('z'), C('Z'), C('æ'), C('Æ')]),
+        key(b'C', [C('c'), C('C'), C('©'), C('¢')]),
+        key(b'N', [C('n'), C('N'), C('ñ'), C('Ñ')]),
+        key(b'M', [C('m'), C('M'), C('µ'), N]),
+        key(OEM_COMMA, [C(','), C('<'), C('ç'), C('Ç')]),
+        key(OEM_PERIOD, [C('.'), C('>'), N, N]),
+        key(OEM_2, [C('/'), C('?'), C('¿'), N]),
+        key(OEM_102, [C('\\'), C('|'), N, N]),
+    ],
+    compositions: &[('\'', 'c', 'ç'), ('\'', 'C', 'Ç')],
+};
+
+// 00001009
+pub static CANADIAN_FRENCH: KeyboardLayout = KeyboardLayout {
+    name: "Canadian French",
+    keys: &[
+        key(OEM_7, [C('#'), C('|'), C('\\'), N]),
+        key(b'1', [C('1'), C('!'), C('±'), N]),
+        key(b'2', [C('2'), C('"'), C('@'), N]),
+        key(b'3', [C('3'), C('/'), C('£'), N]),
+        key(b'4', [C('4'), C('$'), C('¢'), N]),
+        key(b'5', [C('5'), C('%'), C('¤'), N]),
+        key(b'6', [C('6'), C('?'), C('¬'), N]),
+        key(b'7', [C('7'), C('&'), C('¦'), N]),
+        key(b'8', [C('8'), C('*'), C('²'), N]),
+        key(b'9', [C('9'), C('('), C('³'), N]),
+        key(b'0', [C('0'), C(')'), C('¼'), N]),
+        key(OEM_MINUS, [C('-'), C('_'), C('½'), N]),
+        key(OEM_PLUS, [C('='), C('+'), C('¾'), N]),
+        key(b'O', [C('o'), C('O'), C('§'), N]),
+        key(b'P', [C('p'), C('P'), C('¶'), N]),
+        key(OEM_4, [D('^'), D('^'), C('['), N]),
+        key(OEM_6, [D('¸'), D('¨'), C(']'), N]),
+        key(OEM_1, [C(';'), C(':'), C('~'), N]),
+        key(OEM_3, [D('`'), D('`'), C('{'), N]),
+        key(OEM_5, [C('<'), C('>'), C('}'), N]),
+        key(OEM_102, [C('«'), C('»'), C('°'), N]),
+        key(b'M', [C('m'), C('M'), C('µ'), N]),
+        key(OEM_COMMA, [C(','), C('\''), D('¯'), N]),
+        key(OEM_PERIOD, [C('.'), C('.'), N, N]),
+        key(OEM_2, [C('é'), C('É'), D('´'), N]),
+        key(SPACE, [C(' '), C(' '), C('\u{a0}'), N]),
+    ],
+    compositions: &[],
+};
@@ -1,6 +1,34 @@
+pub mod accents;
+pub mod bypass;
+pub mod candidates;
+pub mod engine_event;
 pub mod input_listener;
 pub mod input_simulator;
+mod key_edit;
+mod keyboard_layout;
+mod keyboard_state;
+// The Windows hook and input simulator convert keys both ways
+#[cfg_attr(not(windows), allow(dead_code))]
 mod keys_converter;
+mod latency;
+mod layout_tables;
+pub mod modifiers;
+pub mod replacement;
+pub mod secure_input;
+pub mod snippets;
+mod supervisor;
+mod swallow_gate;
+// Only the Windows listener feeds the translator, it is tested everywhere
+#[cfg_attr(not(windows), allow(dead_code))]
+mod translator;
+pub mod trigger_rules;
+pub mod undo;
+#[allow(clippy::module_inception)]
 pub mod window_engine;
+#[cfg(windows)]
+mod windows_layouts;
+#[cfg(windows)]
+mod windows_listener;
+pub mod word_boundary;
 
-pub use window_engine::WindowEngine;
+pub use window_engine::{EngineOptions, WindowEngine};
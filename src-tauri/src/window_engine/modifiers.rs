@@ -0,0 +1,116 @@
+use super::keyboard_state::{
+    VK_LCONTROL, VK_LMENU, VK_LSHIFT, VK_LWIN, VK_RCONTROL, VK_RMENU, VK_RSHIFT, VK_RWIN,
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub meta: bool,
+    // Right Alt on layouts that have AltGr, Windows reports it together with a
+    // fake left Ctrl. On the others, such as US or UK, Right Alt is a plain Alt
+    pub alt_gr: bool,
+}
+
+impl Modifiers {
+    pub fn from_keyboard_state(keyboard_state: &[u8; 256]) -> Self {
+        let is_down = |virtual_key: u32| keyboard_state[virtual_key as usize] & 0x80 != 0;
+
+        Self {
+            shift: is_down(VK_LSHIFT) || is_down(VK_RSHIFT),
+            ctrl: is_down(VK_LCONTROL) || is_down(VK_RCONTROL),
+            alt: is_down(VK_LMENU) || is_down(VK_RMENU),
+            meta: is_down(VK_LWIN) || is_down(VK_RWIN),
+            alt_gr: is_down(VK_RMENU) && is_down(VK_LCONTROL),
+        }
+    }
+
+    // A shortcut rather than text, the character it produces must not be composed.
+    // Characters typed with AltGr are text even though Ctrl and Alt are both down
+    pub fn is_chord(&self) -> bool {
+        if self.meta {
+            return true;
+        }
+        if self.alt_gr {
+            return false;
+        }
+        self.ctrl || self.alt
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Modifiers;
+    use crate::window_engine::keyboard_state::{
+        VK_LCONTROL, VK_LMENU, VK_LSHIFT, VK_LWIN, VK_RMENU,
+    };
+
+    fn keyboard_state(down: &[u32]) -> [u8; 256] {
+        let mut state = [0_u8; 256];
+        for virtual_key in down {
+            state[*virtual_key as usize] = 0x80;
+        }
+        state
+    }
+
+    #[test]
+    fn test_from_keyboard_state() {
+        assert_eq!(
+            Modifiers::from_keyboard_state(&keyboard_state(&[])),
+            Modifiers::default()
+        );
+        assert_eq!(
+            Modifiers::from_keyboard_state(&keyboard_state(&[VK_LSHIFT, VK_LCONTROL])),
+            Modifiers {
+                shift: true,
+                ctrl: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            Modifiers::from_keyboard_state(&keyboard_state(&[VK_LCONTROL, VK_RMENU])),
+            Modifiers {
+                ctrl: true,
+                alt: true,
+                alt_gr: true,
+                ..Default::default()
+            }
+        );
+        // Layouts without AltGr report Right Alt alone
+        assert_eq!(
+            Modifiers::from_keyboard_state(&keyboard_state(&[VK_RMENU])),
+            Modifiers {
+                alt: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_toggled_keys_are_not_down() {
+        let mut state = [0_u8; 256];
+        state[VK_LCONTROL as usize] = 0x01;
+        assert_eq!(Modifiers::from_keyboard_state(&state), Modifiers::default());
+    }
+
+    #[test]
+    fn test_is_chord() {
+        let table = [
+            (vec![], false),
+            (vec![VK_LSHIFT], false),
+            (vec![VK_LCONTROL], true),
+            (vec![VK_LMENU], true),
+            (vec![VK_LWIN], true),
+            (vec![VK_LCONTROL, VK_LMENU], true),
+            (vec![VK_LCONTROL, VK_RMENU], false),
+            (vec![VK_RMENU], true),
+            (vec![VK_LSHIFT, VK_LCONTROL, VK_RMENU], false),
+            (vec![VK_LWIN, VK_RMENU], true),
+        ];
+        for (down, expected) in table {
+            let modifiers = Modifiers::from_keyboard_state(&keyboard_state(&down));
+            assert_eq!(modifiers.is_chord(), expected, "{:?}", modifiers);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use clavfrancais_engine::engine::CombinationTarget;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Default)]
+pub enum ReplacementMode {
+    // The key reaches the application, then it is erased and the result typed
+    #[default]
+    Retype,
+    // The hook holds the key until the engine decides, a key completing a
+    // combination never reaches the application and only the difference is typed
+    Swallow,
+}
+
+// The edit sent to the application once a combination is found
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Replacement {
+    pub backspaces: usize,
+    pub text: String,
+}
+
+// Replaces the word a boundary just ended, the boundary is typed back after `text`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Expansion {
+    // Characters of the word on screen
+    pub replaced_len: usize,
+    pub text: String,
+}
+
+impl Expansion {
+    pub fn replacement(&self, swallowed: bool) -> Replacement {
+        Replacement {
+            backspaces: if swallowed {
+                self.replaced_len
+            } else {
+                self.replaced_len + 1
+            },
+            text: self.text.clone(),
+        }
+    }
+}
+
+// Whether the key that produced `target` should be kept from the application
+pub fn should_swallow(target: Option<&CombinationTarget>) -> bool {
+    target.is_some()
+}
+
+pub fn replacement(target: &CombinationTarget, swallowed: bool) -> Replacement {
+    // Characters the application shows that the combination replaces, the key
+    // that triggered it included
+    let (replaced, text) = match target {
+        CombinationTarget::Replace(c) => (1, c.to_string()),
+        CombinationTarget::Combine(a) => (2, a.to_string()),
+        CombinationTarget::Revert(a, b) => (2, format!("{}{}", a, b)),
+    };
+
+    Replacement {
+        backspaces: if swallowed { replaced - 1 } else { replaced },
+        text,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use clavfrancais_engine::engine::CombinationTarget;
+
+    use super::{replacement, should_swallow, Replacement};
+
+    fn edit(backspaces: usize, text: &str) -> Replacement {
+        Replacement {
+            backspaces,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_should_swallow() {
+        assert!(!should_swallow(None));
+        assert!(should_swallow(Some(&CombinationTarget::Replace('€'))));
+        assert!(should_swallow(Some(&CombinationTarget::Combine('é'))));
+        assert!(should_swallow(Some(&CombinationTarget::Revert('e', 'w'))));
+    }
+
+    #[test]
+    fn test_retype() {
+        assert_eq!(
+            replacement(&CombinationTarget::Replace('€'), false),
+            edit(1, "€")
+        );
+        assert_eq!(
+            replacement(&CombinationTarget::Combine('é'), false),
+            edit(2, "é")
+        );
+        assert_eq!(
+            replacement(&CombinationTarget::Revert('e', 'w'), false),
+            edit(2, "ew")
+        );
+    }
+
+    #[test]
+    fn test_swallow_only_sends_the_difference() {
+        assert_eq!(
+            replacement(&CombinationTarget::Replace('€'), true),
+            edit(0, "€")
+        );
+        assert_eq!(
+            replacement(&CombinationTarget::Combine('é'), true),
+            edit(1, "é")
+        );
+        assert_eq!(
+            replacement(&CombinationTarget::Revert('e', 'w'), true),
+            edit(1, "ew")
+        );
+    }
+}
@@ -0,0 +1,244 @@
+#[cfg(windows)]
+use std::ffi::c_void;
+#[cfg(windows)]
+use std::mem::size_of;
+
+#[cfg(windows)]
+use windows::core::PWSTR;
+#[cfg(windows)]
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+#[cfg(windows)]
+use windows::Win32::System::StationsAndDesktops::{
+    CloseDesktop, GetUserObjectInformationW, OpenInputDesktop, DESKTOP_CONTROL_FLAGS,
+    DESKTOP_READOBJECTS, UOI_NAME,
+};
+#[cfg(windows)]
+use windows::Win32::System::Threading::{
+    OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+#[cfg(windows)]
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetForegroundWindow, GetGUIThreadInfo, GetWindowLongW, GetWindowThreadProcessId, ES_PASSWORD,
+    GUITHREADINFO, GWL_STYLE,
+};
+
+// The desktop user applications run on, UAC prompts and the lock screen use their own
+#[cfg(windows)]
+const DEFAULT_DESKTOP: &str = "Default";
+#[cfg(windows)]
+const MAX_PATH_LEN: usize = 1024;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SecureContext {
+    PasswordField,
+    SecureDesktop,
+    ExcludedApp(String),
+}
+
+// Tells the engine whether the focused input must be left alone, the engine
+// neither processes nor reports the keys typed there
+pub trait SecureInputDetector {
+    fn detect(&mut self) -> Option<SecureContext>;
+}
+
+// The executable name from a full image path, lowercase
+fn exe_name(path: &str) -> String {
+    path.rsplit(['\\', '/'])
+        .next()
+        .unwrap_or(path)
+        .to_lowercase()
+}
+
+// The executable names of the apps listed in the settings, lowercase. Only the
+// Windows detector looks them up
+#[cfg_attr(not(windows), allow(dead_code))]
+#[derive(Debug, Default)]
+pub struct ExcludedApps {
+    apps: Vec<String>,
+}
+
+impl ExcludedApps {
+    pub fn new(excluded_apps: &[String]) -> Self {
+        Self {
+            apps: excluded_apps
+                .iter()
+                .map(|app| exe_name(app.trim()))
+                .filter(|app| !app.is_empty())
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.apps.is_empty()
+    }
+
+    pub fn contains(&self, exe_name: &str) -> bool {
+        self.apps.iter().any(|app| app == exe_name)
+    }
+}
+
+// The executable name of the foreground window's process, None for our own windows
+#[cfg(windows)]
+pub fn foreground_app() -> Option<String> {
+    let mut process_id = 0;
+    unsafe { GetWindowThreadProcessId(GetForegroundWindow(), Some(&mut process_id)) };
+    if process_id == 0 || process_id == std::process::id() {
+        return None;
+    }
+    unsafe { WindowsSecureInput::process_path(process_id) }.map(|path| exe_name(&path))
+}
+
+// Other applications cannot be looked into where the engine cannot run
+#[cfg(not(windows))]
+pub fn foreground_app() -> Option<String> {
+    None
+}
+
+// Nothing is typed into other applications without the keyboard hooks
+#[cfg(not(windows))]
+pub struct UnsupportedSecureInput;
+
+#[cfg(not(windows))]
+impl UnsupportedSecureInput {
+    pub fn new(_excluded_apps: &[String]) -> Self {
+        Self
+    }
+}
+
+#[cfg(not(windows))]
+impl SecureInputDetector for UnsupportedSecureInput {
+    fn detect(&mut self) -> Option<SecureContext> {
+        None
+    }
+}
+
+#[cfg(windows)]
+pub struct WindowsSecureInput {
+    excluded_apps: ExcludedApps,
+    // The foreground process last looked up and its executable name
+    last_process: Option<(u32, Option<String>)>,
+}
+
+#[cfg(windows)]
+impl WindowsSecureInput {
+    pub fn new(excluded_apps: &[String]) -> Self {
+        Self {
+            excluded_apps: ExcludedApps::new(excluded_apps),
+            last_process: None,
+        }
+    }
+
+    // Fails with access denied while the secure desktop has the input
+    unsafe fn on_default_desktop() -> bool {
+        let Ok(desktop) = OpenInputDesktop(DESKTOP_CONTROL_FLAGS(0), false, DESKTOP_READOBJECTS)
+        else {
+            return false;
+        };
+
+        let mut name = [0_u16; 64];
+        let read = GetUserObjectInformationW(
+            HANDLE(desktop.0),
+            UOI_NAME,
+            Some(name.as_mut_ptr() as *mut c_void),
+            (name.len() * size_of::<u16>()) as u32,
+            None,
+        );
+        let _ = CloseDesktop(desktop);
+
+        let len = name.iter().position(|c| *c == 0).unwrap_or(name.len());
+        read.is_ok() && String::from_utf16_lossy(&name[..len]) == DEFAULT_DESKTOP
+    }
+
+    // Only classic edit controls expose the password style, browsers and
+    // custom controls are covered through the excluded apps
+    unsafe fn is_password_field(thread_id: u32) -> bool {
+        let mut info = GUITHREADINFO {
+            cbSize: size_of::<GUITHREADINFO>() as u32,
+            ..Default::default()
+        };
+        if GetGUIThreadInfo(thread_id, &mut info).is_err() || info.hwndFocus.is_invalid() {
+            return false;
+        }
+
+        GetWindowLongW(info.hwndFocus, GWL_STYLE) & ES_PASSWORD != 0
+    }
+
+    unsafe fn process_path(process_id: u32) -> Option<String> {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id).ok()?;
+
+        let mut path = [0_u16; MAX_PATH_LEN];
+        let mut len = path.len() as u32;
+        let queried = QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR(path.as_mut_ptr()),
+            &mut len,
+        );
+        let _ = CloseHandle(process);
+
+        queried.ok()?;
+        Some(String::from_utf16_lossy(&path[..len as usize]))
+    }
+
+    fn process_name(&mut self, process_id: u32) -> Option<String> {
+        match &self.last_process {
+            Some((last_id, name)) if *last_id == process_id => name.clone(),
+            _ => {
+                let name = unsafe { Self::process_path(process_id) }.map(|path| exe_name(&path));
+                self.last_process = Some((process_id, name.clone()));
+                name
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+impl SecureInputDetector for WindowsSecureInput {
+    fn detect(&mut self) -> Option<SecureContext> {
+        if !unsafe { Self::on_default_desktop() } {
+            return Some(SecureContext::SecureDesktop);
+        }
+
+        let mut process_id = 0;
+        let thread_id =
+            unsafe { GetWindowThreadProcessId(GetForegroundWindow(), Some(&mut process_id)) };
+        if unsafe { Self::is_password_field(thread_id) } {
+            return Some(SecureContext::PasswordField);
+        }
+
+        if self.excluded_apps.is_empty() {
+            return None;
+        }
+        self.process_name(process_id)
+            .filter(|name| self.excluded_apps.contains(name))
+            .map(SecureContext::ExcludedApp)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{exe_name, ExcludedApps};
+
+    #[test]
+    fn test_exe_name() {
+        assert_eq!(
+            exe_name(r"C:\Program Files\KeePass\KeePass.exe"),
+            "keepass.exe"
+        );
+        assert_eq!(exe_name("1Password.exe"), "1password.exe");
+    }
+
+    #[test]
+    fn test_excluded_apps() {
+        let excluded_apps = ExcludedApps::new(&[
+            "KeePass.exe".to_string(),
+            r" C:\Tools\putty.exe ".to_string(),
+            "".to_string(),
+        ]);
+        assert!(excluded_apps.contains("keepass.exe"));
+        assert!(excluded_apps.contains("putty.exe"));
+        assert!(!excluded_apps.contains("notepad.exe"));
+        assert!(!excluded_apps.contains(""));
+        assert!(ExcludedApps::new(&[]).is_empty());
+    }
+}
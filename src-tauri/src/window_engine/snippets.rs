@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use clavfrancais_engine::engine::CombinationTarget;
+use serde::{Deserialize, Serialize};
+
+use super::replacement::Expansion;
+use super::window_engine::apply_combination;
+use super::word_boundary::BoundaryKind;
+
+pub const MAX_ABBREVIATION_LEN: usize = 32;
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    pub abbreviation: String,
+    pub expansion: String,
+}
+
+impl Snippet {
+    // Abbreviations are typed as a word of their own, between spaces
+    pub fn validate(&self) -> Result<(), String> {
+        let len = self.abbreviation.chars().count();
+        if len == 0 {
+            return Err("The abbreviation is empty".to_string());
+        }
+        if len > MAX_ABBREVIATION_LEN {
+            return Err(format!(
+                "The abbreviation is longer than {} characters",
+                MAX_ABBREVIATION_LEN
+            ));
+        }
+        if self.abbreviation.chars().any(char::is_whitespace) {
+            return Err("The abbreviation contains a space".to_string());
+        }
+        if self.expansion.is_empty() {
+            return Err("The expansion is empty".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SnippetMatcher {
+    expansions: HashMap<String, String>,
+    // What the application shows since the last whitespace
+    token: String,
+    // Characters typed past the longest abbreviation, the token cannot match until they are erased
+    overflow: usize,
+}
+
+impl SnippetMatcher {
+    pub fn new(snippets: &[Snippet]) -> Self {
+        Self {
+            expansions: snippets
+                .iter()
+                .filter(|snippet| snippet.validate().is_ok())
+                .map(|snippet| (snippet.abbreviation.clone(), snippet.expansion.clone()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.token.clear();
+        self.overflow = 0;
+    }
+
+    pub fn backspace(&mut self) {
+        if self.overflow > 0 {
+            self.overflow -= 1;
+        } else {
+            self.token.pop();
+        }
+    }
+
+    pub fn combined(&mut self, target: &CombinationTarget) {
+        if self.overflow == 0 {
+            apply_combination(&mut self.token, target);
+        }
+    }
+
+    // Returns the expansion when `c` ends a word that is an abbreviation
+    pub fn insert(&mut self, c: char, boundary: BoundaryKind) -> Option<Expansion> {
+        let expansion = match boundary {
+            BoundaryKind::Boundary if self.overflow == 0 => {
+                self.expansions.get(&self.token).map(|text| Expansion {
+                    replaced_len: self.token.chars().count(),
+                    text: format!("{}{}", text, c),
+                })
+            }
+            _ => None,
+        };
+
+        if expansion.is_some() {
+            // Punctuation ending an abbreviation can start the next one
+            self.clear();
+        }
+        if c.is_whitespace() {
+            self.clear();
+        } else if self.token.chars().count() < MAX_ABBREVIATION_LEN {
+            self.token.push(c);
+        } else {
+            self.overflow += 1;
+        }
+        expansion
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Snippet, SnippetMatcher, MAX_ABBREVIATION_LEN};
+    use crate::window_engine::replacement::Replacement;
+    use crate::window_engine::word_boundary::WordBoundary;
+
+    fn snippet(abbreviation: &str, expansion: &str) -> Snippet {
+        Snippet {
+            abbreviation: abbreviation.to_string(),
+            expansion: expansion.to_string(),
+        }
+    }
+
+    // The replacements the matcher asks for while `text` is typed
+    fn expand(text: &str) -> Vec<Replacement> {
+        let word_boundary = WordBoundary::default();
+        let mut matcher = SnippetMatcher::new(&[
+            snippet(";svp", "s'il vous plaît"),
+            snippet(";adr", "12 rue de la Paix"),
+        ]);
+
+        let mut previous_char = None;
+        let mut replacements = vec![];
+        for c in text.chars() {
+            if c == '\u{8}' {
+                matcher.backspace();
+                previous_char = None;
+                continue;
+            }
+            let boundary = word_boundary.classify(previous_char, c);
+            if let Some(expansion) = matcher.insert(c, boundary) {
+                replacements.push(expansion.replacement(false));
+            }
+            previous_char = Some(c);
+        }
+        replacements
+    }
+
+    fn edit(backspaces: usize, text: &str) -> Replacement {
+        Replacement {
+            backspaces,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_expands_on_boundary() {
+        assert_eq!(expand(";svp "), [edit(5, "s'il vous plaît ")]);
+        assert_eq!(expand("merci ;svp."), [edit(5, "s'il vous plaît.")]);
+        // The boundary ending one abbreviation can start the next word
+        assert_eq!(
+            expand(";adr;svp,"),
+            [edit(5, "12 rue de la Paix;"), edit(5, "s'il vous plaît,")]
+        );
+    }
+
+    #[test]
+    fn test_only_whole_words() {
+        assert!(expand("x;svp ").is_empty());
+        assert!(expand(";svpx ").is_empty());
+        assert!(expand(";svp").is_empty());
+    }
+
+    #[test]
+    fn test_backspace() {
+        assert_eq!(expand(";svx\u{8}p "), [edit(5, "s'il vous plaît ")]);
+    }
+
+    #[test]
+    fn test_long_words() {
+        let long_word = "a".repeat(MAX_ABBREVIATION_LEN + 4);
+        assert!(expand(&format!("{};svp ", long_word)).is_empty());
+        let erased = "\u{8}".repeat(MAX_ABBREVIATION_LEN + 4);
+        assert_eq!(
+            expand(&format!("{}{};svp ", long_word, erased)),
+            [edit(5, "s'il vous plaît ")]
+        );
+    }
+
+    #[test]
+    fn test_swallowed_trigger() {
+        let mut matcher = SnippetMatcher::new(&[snippet(";svp", "s'il vous plaît")]);
+        let word_boundary = WordBoundary::default();
+        let mut previous_char = None;
+        let mut expansion = None;
+        for c in ";svp ".chars() {
+            expansion = matcher.insert(c, word_boundary.classify(previous_char, c));
+            previous_char = Some(c);
+        }
+        assert_eq!(
+            expansion.unwrap().replacement(true),
+            edit(4, "s'il vous plaît ")
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(snippet(";svp", "s'il vous plaît").validate().is_ok());
+        assert!(snippet("", "x").validate().is_err());
+        assert!(snippet("s v p", "x").validate().is_err());
+        assert!(snippet(";svp", "").validate().is_err());
+        assert!(snippet(&"a".repeat(MAX_ABBREVIATION_LEN + 1), "x")
+            .validate()
+            .is_err());
+    }
+}
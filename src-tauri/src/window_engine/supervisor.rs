@@ -0,0 +1,378 @@
+use clavfrancais_engine::char_buffer::CharBuffer;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+
+use super::engine_event::EngineEvent;
+use super::input_listener::{InputListener, MouseKeyEvent};
+use super::window_engine::WindowEngineState;
+
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    next: Duration,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            next: initial,
+        }
+    }
+
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = (self.next * 2).min(self.max);
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        self.next = self.initial;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SupervisorConfig {
+    // How long the engine waits for an event before checking the hook is still installed
+    pub health_check_interval: Duration,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            health_check_interval: Duration::from_millis(500),
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum SessionEnd {
+    Stopped,
+    HookLost,
+}
+
+// Keeps the engine listening until `running` is cleared, reinstalling the hooks
+// whenever they fail to install, are silently removed by Windows or the engine panics
+pub struct Supervisor<'a> {
+    running: &'a AtomicBool,
+    config: SupervisorConfig,
+}
+
+impl<'a> Supervisor<'a> {
+    pub fn new(running: &'a AtomicBool, config: SupervisorConfig) -> Self {
+        Self { running, config }
+    }
+
+    fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    // Returns false when a listener thread did not exit in time
+    pub(super) fn run<L, T>(
+        &self,
+        engine: &mut WindowEngineState<T>,
+        events: &Sender<EngineEvent>,
+        sender: &Sender<MouseKeyEvent>,
+        receiver: &Receiver<MouseKeyEvent>,
+    ) -> bool
+    where
+        L: InputListener,
+        T: CharBuffer,
+    {
+        let mut backoff = Backoff::new(self.config.initial_backoff, self.config.max_backoff);
+        let mut listeners_stopped = true;
+        let _ = events.send(EngineEvent::Started);
+
+        while self.is_running() {
+            let mut listener =
+                match L::start_mouse_key_listening(sender.clone(), engine.swallow_gate()) {
+                    Ok(listener) => listener,
+                    Err(message) => {
+                        let _ = events.send(EngineEvent::HookFailed { message });
+                        self.wait_before_restart(backoff.next_delay(), events, receiver);
+                        continue;
+                    }
+                };
+            let _ = events.send(EngineEvent::HookInstalled);
+
+            let session_start = Instant::now();
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                self.run_session(engine, &listener, receiver)
+            }));
+            listeners_stopped &= listener.stop();
+
+            match result {
+                Ok(SessionEnd::Stopped) => break,
+                Ok(SessionEnd::HookLost) => {
+                    let _ = events.send(EngineEvent::HookLost);
+                }
+                Err(panic) => {
+                    engine.clear();
+                    let message = panic
+                        .downcast_ref::<&str>()
+                        .map(|message| message.to_string())
+                        .or_else(|| panic.downcast_ref::<String>().cloned())
+                        .unwrap_or_else(|| "Engine thread panicked".to_string());
+                    let _ = events.send(EngineEvent::Crashed { message });
+                }
+            }
+
+            if session_start.elapsed() > self.config.max_backoff {
+                backoff.reset();
+            }
+            self.wait_before_restart(backoff.next_delay(), events, receiver);
+        }
+
+        let _ = events.send(EngineEvent::Stopped);
+        listeners_stopped
+    }
+
+    fn run_session<L, T>(
+        &self,
+        engine: &mut WindowEngineState<T>,
+        listener: &L,
+        receiver: &Receiver<MouseKeyEvent>,
+    ) -> SessionEnd
+    where
+        L: InputListener,
+        T: CharBuffer,
+    {
+        loop {
+            let event = receiver.recv_timeout(self.config.health_check_interval);
+            if !self.is_running() {
+                return SessionEnd::Stopped;
+            }
+            // Also reached while idle, the health checks wake the loop up
+            engine.report_latency(Instant::now());
+
+            match event {
+                Ok(event) => engine.handle_event(event),
+                Err(RecvTimeoutError::Timeout) => {
+                    if !listener.is_hook_alive() {
+                        return SessionEnd::HookLost;
+                    }
+                }
+                // The supervisor holds a sender, this only happens if it is gone
+                Err(RecvTimeoutError::Disconnected) => return SessionEnd::Stopped,
+            }
+        }
+    }
+
+    // Input received while waiting is dropped, the engine was not listening anyway
+    fn wait_before_restart(
+        &self,
+        delay: Duration,
+        events: &Sender<EngineEvent>,
+        receiver: &Receiver<MouseKeyEvent>,
+    ) {
+        if !self.is_running() {
+            return;
+        }
+        let _ = events.send(EngineEvent::Restarting {
+            delay_ms: delay.as_millis() as u64,
+        });
+
+        let deadline = Instant::now() + delay;
+        while self.is_running() {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            if let Err(RecvTimeoutError::Disconnected) = receiver.recv_timeout(deadline - now) {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use clavfrancais_engine::{
+        char_buffer::StackSizedCharBuffer, engine::setup_key_combination_map,
+    };
+    use lazy_static::lazy_static;
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::{self, Sender};
+    use std::sync::{Arc, Mutex};
+    use std::thread::{self, JoinHandle};
+    use std::time::{Duration, Instant};
+
+    use super::{Backoff, Supervisor, SupervisorConfig};
+    use crate::window_engine::{
+        engine_event::EngineEvent,
+        input_listener::{InputListener, MouseKeyEvent},
+        swallow_gate::SwallowGate,
+        window_engine::{EngineOptions, WindowEngineState},
+    };
+
+    #[derive(Clone, Copy)]
+    enum Fault {
+        FailInstall,
+        // Sends a few events then exits as if the hook thread died
+        ExitAfterEvents,
+        // Keeps the sender alive but reports the hook as removed
+        LoseHook,
+        // Makes the health check panic on the engine thread
+        Panic,
+    }
+
+    lazy_static! {
+        static ref SCRIPT: Mutex<VecDeque<Fault>> = Mutex::new(VecDeque::new());
+        static ref STARTS: Mutex<usize> = Mutex::new(0);
+    }
+
+    struct FaultyListener {
+        fault: Option<Fault>,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    impl InputListener for FaultyListener {
+        fn start_mouse_key_listening(
+            sender: Sender<MouseKeyEvent>,
+            _swallow_gate: Option<Arc<SwallowGate>>,
+        ) -> Result<Self, String> {
+            *STARTS.lock().unwrap() += 1;
+            let fault = SCRIPT.lock().unwrap().pop_front();
+            match fault {
+                Some(Fault::FailInstall) => Err("hook refused".to_string()),
+                Some(Fault::ExitAfterEvents) => {
+                    let thread = thread::spawn(move || {
+                        let received_at = Instant::now();
+                        let _ = sender.send(MouseKeyEvent::Mouse { received_at });
+                        let _ = sender.send(MouseKeyEvent::Mouse { received_at });
+                    });
+                    Ok(Self {
+                        fault,
+                        thread: Some(thread),
+                    })
+                }
+                fault => Ok(Self {
+                    fault,
+                    thread: None,
+                }),
+            }
+        }
+
+        fn start_shortcut_listening(_sender: Sender<()>) -> Result<Self, String> {
+            Err("not supported".to_string())
+        }
+
+        fn is_hook_alive(&self) -> bool {
+            match self.fault {
+                Some(Fault::ExitAfterEvents) => self
+                    .thread
+                    .as_ref()
+                    .is_some_and(|thread| !thread.is_finished()),
+                Some(Fault::LoseHook) => false,
+                Some(Fault::Panic) => panic!("injected panic"),
+                _ => true,
+            }
+        }
+
+        fn stop(&mut self) -> bool {
+            if let Some(thread) = self.thread.take() {
+                thread.join().unwrap();
+            }
+            true
+        }
+    }
+
+    fn test_config() -> SupervisorConfig {
+        SupervisorConfig {
+            health_check_interval: Duration::from_millis(10),
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+        }
+    }
+
+    #[test]
+    fn test_backoff() {
+        let mut backoff = Backoff::new(Duration::from_millis(100), Duration::from_millis(500));
+        let delays: Vec<u128> = (0..5).map(|_| backoff.next_delay().as_millis()).collect();
+        assert_eq!(delays, vec![100, 200, 400, 500, 500]);
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_recovers_from_faults() {
+        *SCRIPT.lock().unwrap() = VecDeque::from([
+            Fault::FailInstall,
+            Fault::FailInstall,
+            Fault::ExitAfterEvents,
+            Fault::LoseHook,
+            Fault::Panic,
+        ]);
+
+        static RUNNING: AtomicBool = AtomicBool::new(true);
+        let (events_sender, events_receiver) = mpsc::channel();
+
+        let supervisor_thread = thread::spawn(move || {
+            let mut engine = WindowEngineState::new(
+                setup_key_combination_map(),
+                StackSizedCharBuffer::<30>::default(),
+                EngineOptions::default(),
+                events_sender.clone(),
+            );
+            let (sender, receiver) = mpsc::channel();
+            let supervisor = Supervisor::new(&RUNNING, test_config());
+            supervisor.run::<FaultyListener, _>(&mut engine, &events_sender, &sender, &receiver)
+        });
+
+        // Every fault is followed by a restart, the sixth session is healthy
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while *STARTS.lock().unwrap() < 6 {
+            assert!(Instant::now() < deadline, "the engine did not recover");
+            thread::sleep(Duration::from_millis(5));
+        }
+        RUNNING.store(false, Ordering::SeqCst);
+        assert!(supervisor_thread.join().unwrap());
+
+        let events: Vec<EngineEvent> = events_receiver
+            .try_iter()
+            .filter(|event| {
+                !matches!(
+                    event,
+                    EngineEvent::Restarting { .. } | EngineEvent::Latency { .. }
+                )
+            })
+            .collect();
+
+        assert_eq!(events[0], EngineEvent::Started);
+        assert_eq!(
+            events
+                .iter()
+                .filter(|event| matches!(event, EngineEvent::HookFailed { .. }))
+                .count(),
+            2
+        );
+        assert_eq!(
+            events
+                .iter()
+                .filter(|event| **event == EngineEvent::HookLost)
+                .count(),
+            2
+        );
+        assert!(events.contains(&EngineEvent::Crashed {
+            message: "injected panic".to_string()
+        }));
+        assert_eq!(
+            events
+                .iter()
+                .filter(|event| **event == EngineEvent::HookInstalled)
+                .count(),
+            4
+        );
+        assert_eq!(events.last(), Some(&EngineEvent::Stopped));
+    }
+}
@@ -0,0 +1,150 @@
+use std::sync::{Condvar, Mutex, PoisonError};
+use std::time::Duration;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Verdict {
+    Pending,
+    Swallow,
+    Pass,
+}
+
+#[derive(Debug)]
+struct Slot {
+    sequence: u64,
+    verdict: Verdict,
+}
+
+// Lets the hook callback ask the engine whether the key it is holding completes a
+// combination. The hook waits a bounded time for the answer, whoever settles a key
+// first wins, so the engine always knows if the key reached the application
+#[derive(Debug)]
+pub struct SwallowGate {
+    slot: Mutex<Slot>,
+    settled: Condvar,
+}
+
+impl Default for SwallowGate {
+    fn default() -> Self {
+        Self {
+            slot: Mutex::new(Slot {
+                sequence: 0,
+                verdict: Verdict::Pass,
+            }),
+            settled: Condvar::new(),
+        }
+    }
+}
+
+impl SwallowGate {
+    // Called by the hook before it sends the key to the engine, returns the
+    // sequence number the key is answered for
+    pub fn open(&self) -> u64 {
+        let mut slot = self.slot.lock().unwrap_or_else(PoisonError::into_inner);
+        slot.sequence += 1;
+        slot.verdict = Verdict::Pending;
+        slot.sequence
+    }
+
+    // Called by the hook, true when the key must not reach the application.
+    // A key the engine did not answer for in time is let through
+    pub fn wait(&self, sequence: u64, timeout: Duration) -> bool {
+        let slot = self.slot.lock().unwrap_or_else(PoisonError::into_inner);
+        let (mut slot, _) = self
+            .settled
+            .wait_timeout_while(slot, timeout, |slot| {
+                slot.sequence == sequence && slot.verdict == Verdict::Pending
+            })
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if slot.sequence != sequence {
+            return false;
+        }
+        if slot.verdict == Verdict::Pending {
+            slot.verdict = Verdict::Pass;
+        }
+        slot.verdict == Verdict::Swallow
+    }
+
+    // Called by the engine once it handled the key, returns whether the key was
+    // swallowed, false when the hook already let it through
+    pub fn resolve(&self, sequence: u64, swallow: bool) -> bool {
+        let mut slot = self.slot.lock().unwrap_or_else(PoisonError::into_inner);
+        if slot.sequence != sequence || slot.verdict != Verdict::Pending {
+            return false;
+        }
+
+        slot.verdict = if swallow {
+            Verdict::Swallow
+        } else {
+            Verdict::Pass
+        };
+        self.settled.notify_all();
+        swallow
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::SwallowGate;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn test_engine_answers_in_time() {
+        for swallow in [true, false] {
+            let gate = Arc::new(SwallowGate::default());
+            let sequence = gate.open();
+
+            let engine = {
+                let gate = gate.clone();
+                thread::spawn(move || gate.resolve(sequence, swallow))
+            };
+
+            assert_eq!(gate.wait(sequence, TIMEOUT), swallow);
+            assert_eq!(engine.join().unwrap(), swallow);
+        }
+    }
+
+    #[test]
+    fn test_answer_before_wait() {
+        let gate = SwallowGate::default();
+        let sequence = gate.open();
+        assert!(gate.resolve(sequence, true));
+        assert!(gate.wait(sequence, Duration::ZERO));
+    }
+
+    #[test]
+    fn test_late_answer_is_ignored() {
+        let gate = SwallowGate::default();
+        let sequence = gate.open();
+        assert!(!gate.wait(sequence, Duration::from_millis(1)));
+
+        // The key already reached the application, the engine must retype instead
+        assert!(!gate.resolve(sequence, true));
+    }
+
+    #[test]
+    fn test_stale_answer_is_ignored() {
+        let gate = SwallowGate::default();
+        let sequence = gate.open();
+        assert!(!gate.wait(sequence, Duration::from_millis(1)));
+        let next_sequence = gate.open();
+
+        assert!(!gate.resolve(sequence, true));
+        assert!(gate.resolve(next_sequence, true));
+        assert!(gate.wait(next_sequence, TIMEOUT));
+    }
+
+    #[test]
+    fn test_answer_only_once() {
+        let gate = SwallowGate::default();
+        let sequence = gate.open();
+        assert!(!gate.resolve(sequence, false));
+        assert!(!gate.resolve(sequence, true));
+        assert!(!gate.wait(sequence, TIMEOUT));
+    }
+}
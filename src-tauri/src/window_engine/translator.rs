@@ -0,0 +1,338 @@
+use super::input_listener::{MouseKeyEvent, RawInputEvent};
+use super::keyboard_layout::{klid_from_hkl, DeadKeyComposer, KeyOutput, LayoutId};
+use super::keyboard_state::{KeyboardState, VK_CAPITAL};
+use super::keys_converter::KeyConverter;
+use super::modifiers::Modifiers;
+use super::swallow_gate::SwallowGate;
+use clavfrancais_engine::keys::Key;
+use log::debug;
+use std::char;
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Arc;
+use std::time::Instant;
+
+// Typed by SendInput as a character rather than a key, the character is the scan code
+const VK_PACKET: u32 = 0xE7;
+
+// What the translator asks the system, everything else it does the same on any platform
+pub(super) trait SystemLayouts {
+    // The lock keys toggled before the hook was installed
+    fn toggled_keys(&self) -> Vec<u32>;
+    // The HKL of the layout the focused window types with
+    fn foreground_layout(&self) -> usize;
+    // The KLID of the layout variant whose "Layout Id" is `layout_id`
+    fn variant_klid(&self, layout_id: u16) -> Option<u32>;
+    // What a key types on a layout there is no table for
+    fn key_output(
+        &self,
+        virtual_key_code: u32,
+        scan_code: u32,
+        keyboard_state: &[u8; 256],
+        hkl: usize,
+    ) -> KeyOutput;
+}
+
+// Computes the characters of the raw events recorded by the hook. Known layouts
+// are translated from embedded tables, others through the system, in both cases
+// dead keys are composed here rather than by the system
+pub(super) struct KeyTranslator<L: SystemLayouts> {
+    layouts: L,
+    keyboard_state: KeyboardState,
+    composer: DeadKeyComposer,
+    // The layout of the focused window, and the known layout it is when it is one
+    layout: Option<(usize, Option<LayoutId>)>,
+    // Released here for keys the engine cannot swallow
+    swallow_gate: Option<Arc<SwallowGate>>,
+}
+
+impl<L: SystemLayouts> KeyTranslator<L> {
+    pub(super) fn new(layouts: L, swallow_gate: Option<Arc<SwallowGate>>) -> Self {
+        // Locks toggled before the hook was installed are only known to the system
+        let keyboard_state = KeyboardState::with_toggled(&layouts.toggled_keys());
+
+        Self {
+            layouts,
+            keyboard_state,
+            composer: DeadKeyComposer::default(),
+            layout: None,
+            swallow_gate,
+        }
+    }
+
+    // Returns once the hook thread is gone or the engine stopped listening
+    pub(super) fn run(
+        mut self,
+        raw_events: Receiver<RawInputEvent>,
+        sender: Sender<MouseKeyEvent>,
+    ) {
+        for raw_event in raw_events {
+            for event in self.translate(raw_event) {
+                if sender.send(event).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn translate(&mut self, raw_event: RawInputEvent) -> Vec<MouseKeyEvent> {
+        match raw_event {
+            RawInputEvent::Mouse { received_at } => vec![MouseKeyEvent::Mouse { received_at }],
+            RawInputEvent::Key {
+                virtual_key_code,
+                scan_code,
+                key_down,
+                injected,
+                sequence,
+                received_at,
+            } => {
+                // Injected keys still move the keyboard state, but they were typed
+                // by the engine or another tool and must not be composed
+                self.keyboard_state.update(virtual_key_code, key_down);
+                if injected {
+                    return vec![];
+                }
+                if !key_down {
+                    let key = Key::from_virtual_key_code(virtual_key_code);
+                    return vec![MouseKeyEvent::KeyUp { key }];
+                }
+
+                let modifiers = Modifiers::from_keyboard_state(self.keyboard_state.as_bytes());
+                let composing = self.composer.is_pending();
+                let chars = if virtual_key_code == VK_PACKET {
+                    char::from_u32(scan_code).into_iter().collect()
+                } else {
+                    self.key_chars(virtual_key_code, scan_code, modifiers)
+                };
+
+                // The application still waits on the dead key, and a key typing two
+                // characters cannot be partly swallowed
+                let sequence = match (sequence, &self.swallow_gate) {
+                    (Some(sequence), Some(gate)) if composing || chars.len() > 1 => {
+                        gate.resolve(sequence, false);
+                        None
+                    }
+                    _ => sequence,
+                };
+
+                let key = Key::from_virtual_key_code(virtual_key_code);
+                if chars.is_empty() {
+                    return vec![Self::key_event(
+                        None,
+                        key,
+                        scan_code,
+                        modifiers,
+                        sequence,
+                        received_at,
+                    )];
+                }
+                chars
+                    .into_iter()
+                    .map(|c| {
+                        Self::key_event(Some(c), key, scan_code, modifiers, sequence, received_at)
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    fn key_event(
+        unicode_char: Option<char>,
+        key: Key,
+        scan_code: u32,
+        modifiers: Modifiers,
+        sequence: Option<u64>,
+        received_at: Instant,
+    ) -> MouseKeyEvent {
+        MouseKeyEvent::Key {
+            unicode_char,
+            key,
+            scan_code,
+            modifiers,
+            sequence,
+            received_at,
+        }
+    }
+
+    fn key_chars(
+        &mut self,
+        virtual_key_code: u32,
+        scan_code: u32,
+        modifiers: Modifiers,
+    ) -> Vec<char> {
+        let hkl = self.layouts.foreground_layout();
+        let layout_id = match self.layout {
+            Some((layout, layout_id)) if layout == hkl => layout_id,
+            _ => {
+                // The registry is only read when the layout changes
+                let klid = klid_from_hkl(hkl, |layout_id| self.layouts.variant_klid(layout_id));
+                let layout_id = klid.and_then(LayoutId::from_klid);
+                self.layout = Some((hkl, layout_id));
+                let name = layout_id.map_or("system", |layout_id| layout_id.layout().name);
+                debug!(name, klid; "Keyboard layout {:08X}", hkl);
+                layout_id
+            }
+        };
+
+        match layout_id {
+            Some(layout_id) => {
+                let layout = layout_id.layout();
+                let caps_lock = self.keyboard_state.is_toggled(VK_CAPITAL);
+                let output = layout.output(virtual_key_code, modifiers, caps_lock);
+                self.composer.feed(output, layout.compositions)
+            }
+            None => {
+                let output = self.layouts.key_output(
+                    virtual_key_code,
+                    scan_code,
+                    self.keyboard_state.as_bytes(),
+                    hkl,
+                );
+                self.composer.feed(output, &[])
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{KeyTranslator, SystemLayouts};
+    use crate::window_engine::input_listener::{MouseKeyEvent, RawInputEvent};
+    use crate::window_engine::keyboard_layout::KeyOutput;
+    use crate::window_engine::keyboard_state::VK_CAPITAL;
+    use std::cell::Cell;
+    use std::time::Instant;
+
+    const OEM_6: u32 = 0xDD;
+    const OEM_7: u32 = 0xDE;
+    const US: usize = 0x0409_0409;
+    const US_INTERNATIONAL: usize = 0xF002_0409;
+    const FRENCH: usize = 0x040C_040C;
+
+    // A system whose only variant is US-International, and whose own layout types
+    // ^ as a dead key and the letters as they are named
+    struct FakeLayouts {
+        hkl: Cell<usize>,
+        toggled: Vec<u32>,
+        registry_reads: Cell<usize>,
+    }
+
+    impl SystemLayouts for FakeLayouts {
+        fn toggled_keys(&self) -> Vec<u32> {
+            self.toggled.clone()
+        }
+
+        fn foreground_layout(&self) -> usize {
+            self.hkl.get()
+        }
+
+        fn variant_klid(&self, layout_id: u16) -> Option<u32> {
+            self.registry_reads.set(self.registry_reads.get() + 1);
+            (layout_id == 0x002).then_some(0x0002_0409)
+        }
+
+        fn key_output(
+            &self,
+            virtual_key_code: u32,
+            _scan_code: u32,
+            _keyboard_state: &[u8; 256],
+            _hkl: usize,
+        ) -> KeyOutput {
+            match virtual_key_code {
+                OEM_6 => KeyOutput::Dead('^'),
+                0x41..=0x5A => {
+                    KeyOutput::Char((virtual_key_code as u8 as char).to_ascii_lowercase())
+                }
+                _ => KeyOutput::None,
+            }
+        }
+    }
+
+    fn translator(hkl: usize, toggled: &[u32]) -> KeyTranslator<FakeLayouts> {
+        let layouts = FakeLayouts {
+            hkl: Cell::new(hkl),
+            toggled: toggled.to_vec(),
+            registry_reads: Cell::new(0),
+        };
+        KeyTranslator::new(layouts, None)
+    }
+
+    fn key(virtual_key_code: u32, key_down: bool) -> RawInputEvent {
+        RawInputEvent::Key {
+            virtual_key_code,
+            scan_code: 0,
+            key_down,
+            injected: false,
+            sequence: None,
+            received_at: Instant::now(),
+        }
+    }
+
+    // Presses and releases each key, returning the characters the engine is sent
+    fn type_keys(translator: &mut KeyTranslator<FakeLayouts>, keys: &[u32]) -> String {
+        let mut typed = String::new();
+        for virtual_key_code in keys {
+            for raw_event in [key(*virtual_key_code, true), key(*virtual_key_code, false)] {
+                for event in translator.translate(raw_event) {
+                    if let MouseKeyEvent::Key {
+                        unicode_char: Some(c),
+                        ..
+                    } = event
+                    {
+                        typed.push(c);
+                    }
+                }
+            }
+        }
+        typed
+    }
+
+    #[test]
+    fn test_known_layouts() {
+        let mut translator = translator(US, &[]);
+        assert_eq!(type_keys(&mut translator, &[OEM_7, 0x45]), "'e");
+
+        translator.layouts.hkl.set(US_INTERNATIONAL);
+        assert_eq!(type_keys(&mut translator, &[OEM_7, 0x45]), "é");
+        assert_eq!(type_keys(&mut translator, &[OEM_7, 0x20]), "'");
+    }
+
+    #[test]
+    fn test_registry_read_once_per_layout() {
+        let mut translator = translator(US_INTERNATIONAL, &[]);
+        type_keys(&mut translator, &[0x41, 0x42, 0x43]);
+        assert_eq!(translator.layouts.registry_reads.get(), 1);
+
+        translator.layouts.hkl.set(US);
+        type_keys(&mut translator, &[0x41]);
+        translator.layouts.hkl.set(US_INTERNATIONAL);
+        type_keys(&mut translator, &[0x41]);
+        assert_eq!(translator.layouts.registry_reads.get(), 2);
+    }
+
+    #[test]
+    fn test_system_layout_dead_keys() {
+        let mut translator = translator(FRENCH, &[]);
+        assert_eq!(type_keys(&mut translator, &[OEM_6, 0x45]), "ê");
+        assert_eq!(type_keys(&mut translator, &[OEM_6, 0x54]), "^t");
+    }
+
+    #[test]
+    fn test_caps_lock_toggled_before_hook() {
+        let mut translator = translator(US, &[VK_CAPITAL]);
+        assert_eq!(type_keys(&mut translator, &[0x45]), "E");
+    }
+
+    #[test]
+    fn test_injected_keys_are_not_typed() {
+        let mut translator = translator(US, &[]);
+        let injected = RawInputEvent::Key {
+            virtual_key_code: 0x45,
+            scan_code: 0,
+            key_down: true,
+            injected: true,
+            sequence: None,
+            received_at: Instant::now(),
+        };
+        assert!(translator.translate(injected).is_empty());
+    }
+}
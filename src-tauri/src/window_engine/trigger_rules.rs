@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Default)]
+pub enum PhysicalLayout {
+    #[default]
+    Qwerty,
+    Azerty,
+    Qwertz,
+}
+
+// Set 1 scan codes of the keys the layouts move, named after the letter they type on QWERTY
+const SCAN_CODE_Q: u32 = 0x10;
+const SCAN_CODE_W: u32 = 0x11;
+const SCAN_CODE_Y: u32 = 0x15;
+const SCAN_CODE_A: u32 = 0x1E;
+const SCAN_CODE_F: u32 = 0x21;
+const SCAN_CODE_Z: u32 = 0x2C;
+const SCAN_CODE_X: u32 = 0x2D;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Trigger {
+    // Whatever key types this character
+    Char(char),
+    // The key at this position, whatever letter it types. Set 1 scan codes
+    // identify a physical key whatever the layout
+    ScanCode(u32),
+}
+
+// Makes the combination engine see `engine_char` when the trigger is typed, the
+// combinations are written for QWERTY characters
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TriggerRule {
+    pub trigger: Trigger,
+    pub engine_char: char,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct TriggerRules {
+    rules: Vec<TriggerRule>,
+}
+
+impl TriggerRules {
+    pub fn new(rules: Vec<TriggerRule>) -> Self {
+        Self { rules }
+    }
+
+    // The keys the combinations are typed with follow the keycaps, whichever layout
+    // Windows types with. The accents stay on w, f and x, rare in French on every
+    // layout, and the key at the QWERTY position of w never combines on AZERTY, it
+    // types z, which ends words such as chez or avez
+    pub fn for_layout(layout: PhysicalLayout) -> Self {
+        let keys: &[(u32, char)] = match layout {
+            PhysicalLayout::Qwerty => &[],
+            PhysicalLayout::Azerty => &[
+                (SCAN_CODE_Z, 'w'),
+                (SCAN_CODE_F, 'f'),
+                (SCAN_CODE_X, 'x'),
+                (SCAN_CODE_W, 'z'),
+                (SCAN_CODE_Q, 'a'),
+                (SCAN_CODE_A, 'q'),
+            ],
+            PhysicalLayout::Qwertz => &[
+                (SCAN_CODE_W, 'w'),
+                (SCAN_CODE_F, 'f'),
+                (SCAN_CODE_X, 'x'),
+                (SCAN_CODE_Y, 'z'),
+                (SCAN_CODE_Z, 'y'),
+            ],
+        };
+        Self::new(
+            keys.iter()
+                .map(|(scan_code, engine_char)| TriggerRule {
+                    trigger: Trigger::ScanCode(*scan_code),
+                    engine_char: *engine_char,
+                })
+                .collect(),
+        )
+    }
+
+    // Rules of the user's own, they win over the ones of the layout
+    pub fn with_custom_rules(mut self, rules: &[TriggerRule]) -> Self {
+        self.rules.splice(0..0, rules.iter().copied());
+        self
+    }
+
+    // The character the combination engine sees for a typed one. Rules only apply
+    // to letters, symbols typed with AltGr on the same key are left alone
+    pub fn engine_char(&self, scan_code: u32, typed: char) -> char {
+        if !typed.is_alphabetic() {
+            return typed;
+        }
+
+        let rule = self.rules.iter().find(|rule| match rule.trigger {
+            Trigger::Char(c) => c == typed.to_lowercase().next().unwrap_or(typed),
+            Trigger::ScanCode(rule_scan_code) => rule_scan_code == scan_code,
+        });
+        let Some(rule) = rule else {
+            return typed;
+        };
+
+        if typed.is_uppercase() {
+            rule.engine_char
+                .to_uppercase()
+                .next()
+                .unwrap_or(rule.engine_char)
+        } else {
+            rule.engine_char
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PhysicalLayout, Trigger, TriggerRule, TriggerRules};
+
+    // Named after the letter the key types on QWERTY
+    const SCAN_CODE_Q: u32 = 0x10;
+    const SCAN_CODE_W: u32 = 0x11;
+    const SCAN_CODE_E: u32 = 0x12;
+    const SCAN_CODE_Y: u32 = 0x15;
+    const SCAN_CODE_F: u32 = 0x21;
+    const SCAN_CODE_Z: u32 = 0x2C;
+    const SCAN_CODE_X: u32 = 0x2D;
+
+    #[test]
+    fn test_qwerty_keeps_characters() {
+        let rules = TriggerRules::for_layout(PhysicalLayout::Qwerty);
+        assert_eq!(rules.engine_char(SCAN_CODE_W, 'w'), 'w');
+        assert_eq!(rules.engine_char(SCAN_CODE_Z, 'z'), 'z');
+    }
+
+    #[test]
+    fn test_azerty_keeps_z() {
+        let rules = TriggerRules::for_layout(PhysicalLayout::Azerty);
+        // The QWERTY position of w types z on AZERTY, a common letter in French
+        assert_eq!(rules.engine_char(SCAN_CODE_W, 'z'), 'z');
+        assert_eq!(rules.engine_char(SCAN_CODE_W, 'Z'), 'Z');
+        // The triggers are the keys typing w, f and x
+        assert_eq!(rules.engine_char(SCAN_CODE_Z, 'w'), 'w');
+        assert_eq!(rules.engine_char(SCAN_CODE_F, 'f'), 'f');
+        assert_eq!(rules.engine_char(SCAN_CODE_X, 'x'), 'x');
+        assert_eq!(rules.engine_char(SCAN_CODE_E, 'e'), 'e');
+    }
+
+    // An AZERTY keyboard typing with a QWERTY layout still combines from its keycaps
+    #[test]
+    fn test_azerty_keycaps() {
+        let rules = TriggerRules::for_layout(PhysicalLayout::Azerty);
+        assert_eq!(rules.engine_char(SCAN_CODE_W, 'w'), 'z');
+        assert_eq!(rules.engine_char(SCAN_CODE_Z, 'z'), 'w');
+        assert_eq!(rules.engine_char(SCAN_CODE_Q, 'q'), 'a');
+        assert_eq!(rules.engine_char(SCAN_CODE_Q, 'Q'), 'A');
+    }
+
+    #[test]
+    fn test_qwertz_keycaps() {
+        let rules = TriggerRules::for_layout(PhysicalLayout::Qwertz);
+        assert_eq!(rules.engine_char(SCAN_CODE_W, 'w'), 'w');
+        assert_eq!(rules.engine_char(SCAN_CODE_Y, 'z'), 'z');
+        assert_eq!(rules.engine_char(SCAN_CODE_Y, 'y'), 'z');
+        assert_eq!(rules.engine_char(SCAN_CODE_Z, 'z'), 'y');
+    }
+
+    #[test]
+    fn test_custom_rules_win() {
+        let rules = TriggerRules::for_layout(PhysicalLayout::Azerty).with_custom_rules(&[
+            TriggerRule {
+                trigger: Trigger::ScanCode(SCAN_CODE_Z),
+                engine_char: 'k',
+            },
+            TriggerRule {
+                trigger: Trigger::ScanCode(SCAN_CODE_E),
+                engine_char: 'f',
+            },
+        ]);
+        assert_eq!(rules.engine_char(SCAN_CODE_Z, 'w'), 'k');
+        assert_eq!(rules.engine_char(SCAN_CODE_E, 'e'), 'f');
+        assert_eq!(rules.engine_char(SCAN_CODE_W, 'z'), 'z');
+    }
+
+    #[test]
+    fn test_scan_code_rules() {
+        let rules = TriggerRules::new(vec![TriggerRule {
+            trigger: Trigger::ScanCode(SCAN_CODE_W),
+            engine_char: 'w',
+        }]);
+        assert_eq!(rules.engine_char(SCAN_CODE_W, 'z'), 'w');
+        assert_eq!(rules.engine_char(SCAN_CODE_W, 'Z'), 'W');
+        assert_eq!(rules.engine_char(SCAN_CODE_Z, 'w'), 'w');
+        // Rules only apply to letters
+        assert_eq!(rules.engine_char(SCAN_CODE_W, '<'), '<');
+    }
+
+    #[test]
+    fn test_char_rules() {
+        let rules = TriggerRules::new(vec![TriggerRule {
+            trigger: Trigger::Char('q'),
+            engine_char: 'w',
+        }]);
+        assert_eq!(rules.engine_char(0, 'q'), 'w');
+        assert_eq!(rules.engine_char(0, 'Q'), 'W');
+        assert_eq!(rules.engine_char(0, 'a'), 'a');
+    }
+
+    #[test]
+    fn test_serialize() {
+        let rule = TriggerRule {
+            trigger: Trigger::ScanCode(SCAN_CODE_Z),
+            engine_char: 'w',
+        };
+        let json = r#"{"trigger":{"scanCode":44},"engineChar":"w"}"#;
+        assert_eq!(serde_json::to_string(&rule).unwrap(), json);
+        assert_eq!(serde_json::from_str::<TriggerRule>(json).unwrap(), rule);
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::VecDeque;
+
+use clavfrancais_engine::keys::Key;
+use serde::{Deserialize, Serialize};
+
+use super::modifiers::Modifiers;
+use super::replacement::Replacement;
+
+// Combinations that can still be undone
+const HISTORY_LEN: usize = 8;
+// Text typed since the focus last changed that the history can reach back into
+const MAX_TRACKED_CHARS: usize = 256;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, Default)]
+pub enum UndoKey {
+    #[default]
+    Off,
+    // Only right after the combination, and only when the key can be swallowed,
+    // otherwise the application already deleted the previous word
+    CtrlBackspace,
+    Pause,
+    ScrollLock,
+}
+
+impl UndoKey {
+    pub fn matches(&self, key: &Key, modifiers: Modifiers) -> bool {
+        match self {
+            UndoKey::Off => false,
+            UndoKey::CtrlBackspace => *key == Key::Backspace && modifiers.ctrl && !modifiers.alt,
+            UndoKey::Pause => *key == Key::Pause,
+            UndoKey::ScrollLock => *key == Key::ScrollLock,
+        }
+    }
+}
+
+// A combination applied at `at` in the tracked text
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Rewrite {
+    at: usize,
+    // What was on screen before the combination, the raw keystrokes
+    before: String,
+    // What the combination typed instead
+    after: String,
+}
+
+#[derive(Debug, Default)]
+pub struct RewriteHistory {
+    text: Vec<char>,
+    rewrites: VecDeque<Rewrite>,
+}
+
+impl RewriteHistory {
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.rewrites.clear();
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.text.push(c);
+
+        if self.text.len() > MAX_TRACKED_CHARS {
+            let removed = self.text.len() - MAX_TRACKED_CHARS;
+            self.text.drain(..removed);
+            self.rewrites.retain(|rewrite| rewrite.at >= removed);
+            for rewrite in self.rewrites.iter_mut() {
+                rewrite.at -= removed;
+            }
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        self.text.pop();
+        let len = self.text.len();
+        self.rewrites
+            .retain(|rewrite| rewrite.at + rewrite.after.chars().count() <= len);
+    }
+
+    // Records a replacement sent after the key that triggered it was inserted
+    pub fn record(&mut self, replacement: &Replacement) {
+        if replacement.backspaces > self.text.len() {
+            // Part of what it replaced was typed before the history was cleared
+            self.clear();
+            return;
+        }
+
+        let at = self.text.len() - replacement.backspaces;
+        let before = self.text.drain(at..).collect();
+        self.text.extend(replacement.text.chars());
+
+        if self.rewrites.len() == HISTORY_LEN {
+            self.rewrites.pop_front();
+        }
+        self.rewrites.push_back(Rewrite {
+            at,
+            before,
+            after: replacement.text.clone(),
+        });
+    }
+
+    fn still_on_screen(&self, rewrite: &Rewrite) -> bool {
+        let after: Vec<char> = rewrite.after.chars().collect();
+        self.text.get(rewrite.at..rewrite.at + after.len()) == Some(after.as_slice())
+    }
+
+    // The edit restoring the raw keystrokes of the last combination. With
+    // `immediate`, only when nothing was typed since
+    pub fn undo(&mut self, immediate: bool) -> Option<Replacement> {
+        while let Some(rewrite) = self.rewrites.pop_back() {
+            if !self.still_on_screen(&rewrite) {
+                continue;
+            }
+
+            let end = rewrite.at + rewrite.after.chars().count();
+            if immediate && end != self.text.len() {
+                self.rewrites.push_back(rewrite);
+                return None;
+            }
+
+            let tail: Vec<char> = self
+                .text
+                .drain(rewrite.at..)
+                .skip(end - rewrite.at)
+                .collect();
+            let backspaces = rewrite.after.chars().count() + tail.len();
+            self.text.extend(rewrite.before.chars());
+            self.text.extend(tail.iter());
+
+            let text = rewrite.before.chars().chain(tail).collect();
+            return Some(Replacement { backspaces, text });
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use clavfrancais_engine::keys::Key;
+
+    use super::{RewriteHistory, UndoKey, HISTORY_LEN};
+    use crate::window_engine::modifiers::Modifiers;
+    use crate::window_engine::replacement::Replacement;
+
+    fn edit(backspaces: usize, text: &str) -> Replacement {
+        Replacement {
+            backspaces,
+            text: text.to_string(),
+        }
+    }
+
+    fn type_text(history: &mut RewriteHistory, text: &str) {
+        for c in text.chars() {
+            history.insert(c);
+        }
+    }
+
+    fn text(history: &RewriteHistory) -> String {
+        history.text.iter().collect()
+    }
+
+    #[test]
+    fn test_undo_last_combination() {
+        let mut history = RewriteHistory::default();
+        type_text(&mut history, "ew");
+        history.record(&edit(2, "é"));
+        assert_eq!(text(&history), "é");
+
+        assert_eq!(history.undo(true), Some(edit(1, "ew")));
+        assert_eq!(text(&history), "ew");
+        assert_eq!(history.undo(true), None);
+    }
+
+    #[test]
+    fn test_undo_keeps_text_typed_after() {
+        let mut history = RewriteHistory::default();
+        type_text(&mut history, "ew");
+        history.record(&edit(2, "é"));
+        type_text(&mut history, "co");
+
+        assert_eq!(history.undo(true), None);
+        assert_eq!(history.undo(false), Some(edit(3, "ewco")));
+        assert_eq!(text(&history), "ewco");
+    }
+
+    #[test]
+    fn test_undo_successive_combinations() {
+        let mut history = RewriteHistory::default();
+        type_text(&mut history, "ew");
+        history.record(&edit(2, "é"));
+        type_text(&mut history, "tew");
+        history.record(&edit(2, "é"));
+
+        assert_eq!(history.undo(true), Some(edit(1, "ew")));
+        assert_eq!(history.undo(false), Some(edit(4, "ewtew")));
+        assert_eq!(text(&history), "ewtew");
+    }
+
+    #[test]
+    fn test_backspace_drops_erased_combinations() {
+        let mut history = RewriteHistory::default();
+        type_text(&mut history, "ew");
+        history.record(&edit(2, "é"));
+        history.backspace();
+        history.insert('e');
+
+        assert_eq!(history.undo(false), None);
+    }
+
+    #[test]
+    fn test_clear_forgets_combinations() {
+        let mut history = RewriteHistory::default();
+        type_text(&mut history, "ew");
+        history.record(&edit(2, "é"));
+        history.clear();
+        assert_eq!(history.undo(false), None);
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut history = RewriteHistory::default();
+        for _ in 0..HISTORY_LEN + 2 {
+            type_text(&mut history, "ew");
+            history.record(&edit(2, "é"));
+        }
+
+        let mut undone = 0;
+        while history.undo(false).is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, HISTORY_LEN);
+    }
+
+    #[test]
+    fn test_undo_key_matches() {
+        let ctrl = Modifiers {
+            ctrl: true,
+            ..Default::default()
+        };
+        assert!(UndoKey::CtrlBackspace.matches(&Key::Backspace, ctrl));
+        assert!(!UndoKey::CtrlBackspace.matches(&Key::Backspace, Modifiers::default()));
+        assert!(UndoKey::Pause.matches(&Key::Pause, Modifiers::default()));
+        assert!(!UndoKey::Off.matches(&Key::Pause, Modifiers::default()));
+    }
+}
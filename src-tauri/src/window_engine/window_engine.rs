@@ -1,99 +1,1344 @@
 use clavfrancais_engine::{
     char_buffer::CharBuffer,
     engine::{CombinationTarget, Engine, KeyCombinationMap},
-    keys::{Key, CHANGE_FOCUS_KEYS},
+    keys::Key,
 };
-use std::sync::mpsc::{self, Receiver, Sender};
+use log::{log_enabled, trace, Level};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
 
-use super::input_listener::{InputListener, MouseKeyEvent, WindowsListener as InputListenerImpl};
-use super::input_simulator::{InputSimulator, WindowsInputSimulator as InputSimulatorImpl};
+use super::accents::{AccentCompleter, AccentOptions};
+use super::bypass::{Bypass, BypassOptions};
+use super::candidates::Suggestion;
+use super::engine_event::EngineEvent;
+#[cfg(not(windows))]
+use super::input_listener::UnsupportedListener as InputListenerImpl;
+use super::input_listener::{InputListener, MouseKeyEvent};
+use super::input_simulator::InputSimulator;
+#[cfg(not(windows))]
+use super::input_simulator::UnsupportedSimulator as InputSimulatorImpl;
+#[cfg(windows)]
+use super::input_simulator::WindowsInputSimulator as InputSimulatorImpl;
+use super::key_edit::{candidate_number, classify_key, KeyEdit};
+use super::latency::LatencyRecorder;
+use super::modifiers::Modifiers;
+use super::replacement::{replacement, should_swallow, Expansion, Replacement, ReplacementMode};
+use super::secure_input::SecureInputDetector;
+#[cfg(not(windows))]
+use super::secure_input::UnsupportedSecureInput as SecureInputImpl;
+#[cfg(windows)]
+use super::secure_input::WindowsSecureInput as SecureInputImpl;
+use super::snippets::{Snippet, SnippetMatcher};
+use super::supervisor::{Supervisor, SupervisorConfig};
+use super::swallow_gate::SwallowGate;
+use super::trigger_rules::TriggerRules;
+use super::undo::{RewriteHistory, UndoKey};
+#[cfg(windows)]
+use super::windows_listener::WindowsListener as InputListenerImpl;
+use super::word_boundary::{BoundaryKind, WordBoundary};
+use crate::logging::typed;
+use crate::utils::{join_timeout, spawn_timed, TimedJoinHandle};
 
-pub struct WindowEngine;
+static ENGINE_LOCK: Mutex<()> = Mutex::new(());
 
-struct WindowEngineState<T>
+// Longer than the listener's own stop timeout so its result can be reported
+const STOP_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub struct WindowEngine {
+    running: Arc<AtomicBool>,
+    waker: Sender<MouseKeyEvent>,
+    // Returns whether the hook threads exited in time
+    thread: Option<TimedJoinHandle<bool>>,
+}
+
+pub(super) struct WindowEngineState<T>
 where
     T: CharBuffer,
 {
     input_controller: Engine<T>,
+    word_boundary: WordBoundary,
+    trigger_rules: TriggerRules,
+    bypass: Bypass,
+    undo_key: UndoKey,
+    // Combinations applied since the focus last changed
+    history: RewriteHistory,
+    snippets: SnippetMatcher,
+    accents: AccentCompleter,
+    // Sent by handle_event once the hook is answered, like a combination
+    pending_expansion: Option<Expansion>,
+    // A spelling picked with Alt and a number key, a chord that types nothing to erase
+    pending_pick: Option<Expansion>,
+    // Spellings of the last word shown until the next key, Alt and a number key
+    // pick one
+    suggestion: Option<Suggestion>,
+    // The current word is not completed from the word list, part of it was
+    // bypassed or the engine does not know where it starts
+    keep_word: bool,
+    last_char: Option<char>,
+    // What the current word looks like on screen, sent to the frontend as a live preview
+    preview: String,
+    events: Sender<EngineEvent>,
+    // Set in swallow mode, answers the hook holding the key being handled
+    swallow_gate: Option<Arc<SwallowGate>>,
+    secure_input: Box<dyn SecureInputDetector>,
+    in_secure_input: bool,
+    latency: LatencyRecorder,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct EngineOptions {
+    pub word_boundary: WordBoundary,
+    pub replacement_mode: ReplacementMode,
+    pub trigger_rules: TriggerRules,
+    pub bypass: BypassOptions,
+    pub undo_key: UndoKey,
+    // Executable names the engine leaves alone, such as password managers
+    pub excluded_apps: Vec<String>,
+    pub snippets: Vec<Snippet>,
+    pub accents: AccentOptions,
+}
+
+// Applies a combination to `text` the same way the input simulator applies it on screen
+pub(super) fn apply_combination(text: &mut String, target: &CombinationTarget) {
+    match target {
+        CombinationTarget::Replace(c) => {
+            text.pop();
+            text.push(*c);
+        }
+        CombinationTarget::Combine(a) => {
+            text.pop();
+            text.pop();
+            text.push(*a);
+        }
+        CombinationTarget::Revert(a, b) => {
+            text.pop();
+            text.pop();
+            text.push(*a);
+            text.push(*b);
+        }
+    }
 }
 
 impl<T> WindowEngineState<T>
 where
     T: CharBuffer,
 {
-    fn new(combination_map: KeyCombinationMap, char_buffer: T) -> Self {
+    pub(super) fn new(
+        combination_map: KeyCombinationMap,
+        char_buffer: T,
+        options: EngineOptions,
+        events: Sender<EngineEvent>,
+    ) -> Self {
+        let swallow_gate = (options.replacement_mode == ReplacementMode::Swallow)
+            .then(|| Arc::new(SwallowGate::default()));
+
         Self {
             input_controller: Engine::new(combination_map, char_buffer),
+            word_boundary: options.word_boundary,
+            trigger_rules: options.trigger_rules,
+            bypass: Bypass::new(options.bypass),
+            undo_key: options.undo_key,
+            history: RewriteHistory::default(),
+            snippets: SnippetMatcher::new(&options.snippets),
+            accents: AccentCompleter::new(options.accents),
+            pending_expansion: None,
+            pending_pick: None,
+            suggestion: None,
+            keep_word: true,
+            last_char: None,
+            preview: String::new(),
+            events,
+            swallow_gate,
+            secure_input: Box::new(SecureInputImpl::new(&options.excluded_apps)),
+            in_secure_input: false,
+            latency: LatencyRecorder::new(Instant::now()),
         }
     }
-    fn handle_event(&mut self, receiver: Receiver<MouseKeyEvent>) {
-        loop {
-            let Ok(event) = receiver.recv() else {
-                return;
+
+    #[cfg(test)]
+    fn set_secure_input(&mut self, secure_input: Box<dyn SecureInputDetector>) {
+        self.secure_input = secure_input;
+    }
+
+    // Checked before each key, nothing typed in a secure context is kept
+    fn check_secure_input(&mut self) -> bool {
+        let secure = self.secure_input.detect().is_some();
+        if secure != self.in_secure_input {
+            self.in_secure_input = secure;
+            if secure {
+                self.clear();
+                let _ = self.events.send(EngineEvent::Buffer {
+                    content: String::new(),
+                });
+            }
+            let _ = self
+                .events
+                .send(EngineEvent::SecureInput { active: secure });
+        }
+        secure
+    }
+
+    pub(super) fn swallow_gate(&self) -> Option<Arc<SwallowGate>> {
+        self.swallow_gate.clone()
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.input_controller.clear_char_buffer();
+        self.history.clear();
+        self.snippets.clear();
+        self.dismiss_suggestion();
+        self.keep_word = true;
+        self.last_char = None;
+        self.preview.clear();
+    }
+
+    // The preview starts with the boundary that ended the previous word
+    fn current_word(&self) -> &str {
+        self.preview
+            .strip_prefix(|c: char| !c.is_alphabetic())
+            .unwrap_or(&self.preview)
+    }
+
+    // Accents the word `boundary` ends from the word list, or shows its
+    // spellings when it has several
+    fn complete_word(&mut self, boundary: char) -> Option<(EngineEvent, Expansion)> {
+        let word = self.current_word();
+        if let Some(expansion) = self.accents.complete(word, boundary) {
+            let event = EngineEvent::AccentCompletion {
+                output: expansion.text.clone(),
             };
+            return Some((event, expansion));
+        }
+
+        let suggestion = self.accents.suggest(word, boundary)?;
+        let _ = self.events.send(EngineEvent::Candidates {
+            candidates: suggestion.candidates.clone(),
+        });
+        self.suggestion = Some(suggestion);
+        None
+    }
+
+    fn dismiss_suggestion(&mut self) -> Option<Suggestion> {
+        let suggestion = self.suggestion.take()?;
+        let _ = self
+            .events
+            .send(EngineEvent::Candidates { candidates: vec![] });
+        Some(suggestion)
+    }
+
+    // Alt and a number key pick one of the spellings shown, any other key only
+    // hides them and goes on as usual
+    fn answer_suggestion(&mut self, number: Option<char>) -> bool {
+        let Some(suggestion) = self.dismiss_suggestion() else {
+            return false;
+        };
+        let Some(number) = number else {
+            return false;
+        };
+        let Some((word, expansion)) = suggestion.select(number) else {
+            return false;
+        };
 
-            match event {
-                MouseKeyEvent::Mouse => {
-                    self.input_controller.clear_char_buffer();
-                    continue;
+        self.accents.pick(word);
+        let _ = self.events.send(EngineEvent::CandidatePicked);
+        // Only the word and its boundary are replaced, the number key is swallowed
+        self.history.record(&expansion.replacement(true));
+        let _ = self.events.send(EngineEvent::AccentCompletion {
+            output: expansion.text.clone(),
+        });
+        self.pending_pick = Some(expansion);
+        true
+    }
+
+    fn handle_key(
+        &mut self,
+        unicode_char: Option<char>,
+        key: Key,
+        scan_code: u32,
+        modifiers: Modifiers,
+    ) -> Option<CombinationTarget> {
+        self.bypass.key_down(&key);
+
+        let edit = classify_key(&key, unicode_char, modifiers);
+        let number = candidate_number(&key, modifiers);
+        if (edit != KeyEdit::Ignore || number.is_some()) && self.answer_suggestion(number) {
+            return None;
+        }
+
+        let unicode_char = match edit {
+            KeyEdit::Ignore | KeyEdit::DeleteForward => return None,
+            KeyEdit::Invalidate => {
+                self.clear();
+                return None;
+            }
+            KeyEdit::Backspace => {
+                self.input_controller.backspace();
+                self.history.backspace();
+                self.snippets.backspace();
+                self.last_char = None;
+                self.preview.pop();
+                if self.preview.is_empty() {
+                    // Erased back into the previous word
+                    self.keep_word = true;
                 }
-                MouseKeyEvent::Key { unicode_char, key } => {
-                    if CHANGE_FOCUS_KEYS.contains(&key) {
-                        self.input_controller.clear_char_buffer();
-                        continue;
-                    }
-
-                    let Some(unicode_char) = unicode_char else {
-                        continue;
-                    };
+                return None;
+            }
+            KeyEdit::Insert(unicode_char) => unicode_char,
+        };
+        self.history.insert(unicode_char);
 
-                    if key == Key::Backspace {
-                        self.input_controller.backspace();
-                        continue;
-                    }
+        let previous_char = self.last_char.replace(unicode_char);
 
-                    let target = self.input_controller.add_char(unicode_char);
+        let boundary = self.word_boundary.classify(previous_char, unicode_char);
+        let bypassed = self.bypass.skips(boundary);
+        let expansion = match self.snippets.insert(unicode_char, boundary) {
+            Some(expansion) => Some((
+                EngineEvent::Snippet {
+                    output: expansion.text.clone(),
+                },
+                expansion,
+            )),
+            None if boundary == BoundaryKind::Boundary && !bypassed && !self.keep_word => {
+                self.complete_word(unicode_char)
+            }
+            None => None,
+        };
+        self.keep_word = match boundary {
+            BoundaryKind::None => self.keep_word || bypassed,
+            _ => false,
+        };
 
-                    let Some(target) = target else {
-                        continue;
-                    };
+        match boundary {
+            BoundaryKind::None => {}
+            BoundaryKind::Boundary => {
+                self.input_controller.clear_char_buffer();
+                self.preview.clear();
+            }
+            BoundaryKind::Elision => {
+                self.input_controller.clear_char_buffer();
+                self.preview.clear();
+                return None;
+            }
+        }
+
+        self.preview.push(unicode_char);
+        if bypassed {
+            // Typed as is, what follows must not combine with what came before
+            self.input_controller.clear_char_buffer();
+            return None;
+        }
+
+        if let Some((event, expansion)) = expansion {
+            self.history.record(&expansion.replacement(false));
+            let _ = self.events.send(event);
+            self.pending_expansion = Some(expansion);
+            return None;
+        }
+
+        let engine_char = self.trigger_rules.engine_char(scan_code, unicode_char);
+        let target = match self.input_controller.add_char(engine_char)? {
+            // Reverting types the trigger back as it was typed, not as the engine saw it
+            CombinationTarget::Revert(a, b) if b == engine_char => {
+                CombinationTarget::Revert(a, unicode_char)
+            }
+            target => target,
+        };
+        apply_combination(&mut self.preview, &target);
+        self.snippets.combined(&target);
+        self.history.record(&replacement(&target, false));
+        let _ = self.events.send(EngineEvent::combination(&target));
+        Some(target)
+    }
+
+    pub(super) fn handle_event(&mut self, event: MouseKeyEvent) {
+        if let MouseKeyEvent::Key { sequence, .. } = event {
+            if self.check_secure_input() {
+                self.resolve(sequence, false);
+                return;
+            }
+        }
 
-                    match target {
-                        CombinationTarget::Replace(c) => {
-                            InputSimulatorImpl::backspace();
-                            InputSimulatorImpl::character(c);
-                        }
-                        CombinationTarget::Combine(a) => {
-                            InputSimulatorImpl::backspace();
-                            InputSimulatorImpl::backspace();
-                            InputSimulatorImpl::character(a);
-                        }
-                        CombinationTarget::Revert(a, b) => {
-                            InputSimulatorImpl::backspace();
-                            InputSimulatorImpl::backspace();
-                            InputSimulatorImpl::character(a);
-                            InputSimulatorImpl::character(b);
-                        }
-                    }
+        let previous_preview = self.preview.clone();
+
+        let (edit, received_at) = match event {
+            MouseKeyEvent::Wake => return,
+            MouseKeyEvent::KeyUp { key } => {
+                self.bypass.key_up(&key);
+                return;
+            }
+            MouseKeyEvent::Mouse { received_at } => {
+                self.clear();
+                (None, received_at)
+            }
+            MouseKeyEvent::Key {
+                unicode_char,
+                key,
+                scan_code,
+                modifiers,
+                sequence,
+                received_at,
+            } => {
+                // Past the secure input check, the character only when allowed
+                if log_enabled!(Level::Trace) {
+                    let c = unicode_char.map(String::from).unwrap_or_default();
+                    trace!(sequence, text = typed(&c); "Key");
+                }
+                if self.undo_key.matches(&key, modifiers) {
+                    (self.undo(sequence), received_at)
+                } else {
+                    let target = self.handle_key(unicode_char, key, scan_code, modifiers);
+                    let expansion = self.pending_expansion.take();
+                    let pick = self.pending_pick.take();
+                    // Answered first, the hook is holding the key until then
+                    let swallow =
+                        should_swallow(target.as_ref()) || expansion.is_some() || pick.is_some();
+                    let swallowed = self.resolve(sequence, swallow);
+                    let edit = match (pick, expansion) {
+                        // Left to the application when not swallowed, the chord types
+                        // nothing the replacement has to erase
+                        (Some(pick), _) => Some(pick.replacement(true)),
+                        (None, Some(expansion)) => Some(expansion.replacement(swallowed)),
+                        (None, None) => target.map(|target| replacement(&target, swallowed)),
+                    };
+                    (edit, received_at)
                 }
             }
+        };
+
+        if self.preview != previous_preview {
+            let _ = self.events.send(EngineEvent::Buffer {
+                content: self.preview.clone(),
+            });
+        }
+
+        if let Some(edit) = edit {
+            Self::simulate(edit);
+        }
+
+        self.latency.record(received_at.elapsed());
+    }
+
+    // Sends a summary of the latencies once per report interval
+    pub(super) fn report_latency(&mut self, now: Instant) {
+        if let Some(event) = self.latency.report(now) {
+            let _ = self.events.send(event);
+        }
+    }
+
+    // Whether the key the hook is holding was swallowed
+    fn resolve(&self, sequence: Option<u64>, swallow: bool) -> bool {
+        match (&self.swallow_gate, sequence) {
+            (Some(gate), Some(sequence)) => gate.resolve(sequence, swallow),
+            _ => false,
+        }
+    }
+
+    // Restores the raw keystrokes of the last combination
+    fn undo(&mut self, sequence: Option<u64>) -> Option<Replacement> {
+        let immediate = self.undo_key == UndoKey::CtrlBackspace;
+        self.dismiss_suggestion();
+        let undo = self.history.undo(immediate);
+        let swallowed = self.resolve(sequence, undo.is_some());
+        if immediate && !swallowed {
+            // Ctrl+Backspace reached the application, which erased the previous word
+            self.clear();
+            return None;
+        }
+        let undo = undo?;
+
+        // The restored keystrokes must not combine again
+        self.input_controller.clear_char_buffer();
+        self.snippets.clear();
+        self.last_char = undo.text.chars().last();
+        for _ in 0..undo.backspaces {
+            self.preview.pop();
+        }
+        self.preview.push_str(&undo.text);
+        if let Some(end) = self.preview.rfind(char::is_whitespace) {
+            self.preview.drain(..=end);
+        }
+
+        let _ = self.events.send(EngineEvent::Undo {
+            output: undo.text.clone(),
+        });
+        Some(undo)
+    }
+
+    fn simulate(replacement: Replacement) {
+        for _ in 0..replacement.backspaces {
+            InputSimulatorImpl::backspace();
+        }
+        for c in replacement.text.chars() {
+            InputSimulatorImpl::character(c);
         }
     }
 }
 
 impl WindowEngine {
-    pub fn start(combination_map: KeyCombinationMap, char_buffer: impl CharBuffer) {
+    pub fn start<T>(
+        combination_map: KeyCombinationMap,
+        char_buffer: T,
+        options: EngineOptions,
+        events: Sender<EngineEvent>,
+    ) -> Self
+    where
+        T: CharBuffer + Send + 'static,
+    {
+        Self::start_with::<InputListenerImpl, T>(
+            combination_map,
+            char_buffer,
+            options,
+            events,
+            SupervisorConfig::default(),
+        )
+    }
+
+    fn start_with<L, T>(
+        combination_map: KeyCombinationMap,
+        char_buffer: T,
+        options: EngineOptions,
+        events: Sender<EngineEvent>,
+        config: SupervisorConfig,
+    ) -> Self
+    where
+        L: InputListener,
+        T: CharBuffer + Send + 'static,
+    {
+        let running = Arc::new(AtomicBool::new(true));
         let (sender, receiver) = mpsc::channel::<MouseKeyEvent>();
-        InputListenerImpl::start_mouse_key_listening(sender);
-        let mut engine = WindowEngineState::new(combination_map, char_buffer);
-        engine.handle_event(receiver);
+
+        let thread = {
+            let running = running.clone();
+            let sender = sender.clone();
+            spawn_timed(move || {
+                // An engine that is still shutting down keeps its hooks until it exits,
+                // the next one waits for it so two engines never process the same keys
+                let _engine_lock = ENGINE_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+
+                let mut engine =
+                    WindowEngineState::new(combination_map, char_buffer, options, events.clone());
+                let supervisor = Supervisor::new(&running, config);
+                supervisor.run::<L, T>(&mut engine, &events, &sender, &receiver)
+            })
+        };
+
+        Self {
+            running,
+            waker: sender,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        let thread_alive = self
+            .thread
+            .as_ref()
+            .is_some_and(|thread| !thread.is_finished());
+        self.running.load(Ordering::SeqCst) && thread_alive
+    }
+
+    // Wakes the engine thread up and waits for it and its hook thread to exit.
+    // Returns false when they did not exit in time and were left detached
+    pub fn stop(&mut self) -> bool {
+        self.running.store(false, Ordering::SeqCst);
+        let Some(thread) = self.thread.take() else {
+            return true;
+        };
+
+        let _ = self.waker.send(MouseKeyEvent::Wake);
+        join_timeout(thread, STOP_TIMEOUT).unwrap_or(false)
+    }
+
+    pub fn listen_toggle_shortcut(sender: Sender<()>) -> Result<InputListenerImpl, String> {
+        InputListenerImpl::start_shortcut_listening(sender)
+    }
+}
+
+impl Drop for WindowEngine {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use clavfrancais_engine::{
+        char_buffer::StackSizedCharBuffer,
+        engine::{setup_key_combination_map, CombinationTarget},
+        keys::Key,
+    };
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+    use std::thread::{self, JoinHandle};
+    use std::time::{Duration, Instant};
+
+    use super::{apply_combination, EngineOptions, Replacement, WindowEngine, WindowEngineState};
+    use crate::window_engine::{
+        accents::AccentOptions,
+        bypass::{BypassKey, BypassMode, BypassOptions},
+        candidates::CandidatePicks,
+        engine_event::{CombinationKind, EngineEvent},
+        input_listener::{InputListener, MouseKeyEvent},
+        latency::REPORT_INTERVAL,
+        modifiers::Modifiers,
+        secure_input::{SecureContext, SecureInputDetector},
+        snippets::Snippet,
+        supervisor::SupervisorConfig,
+        swallow_gate::SwallowGate,
+        trigger_rules::{PhysicalLayout, TriggerRules},
+        undo::UndoKey,
+    };
+
+    type TestEngine = WindowEngineState<StackSizedCharBuffer<30>>;
+
+    // Builds the engine the tests type into, with the options they vary
+    #[derive(Default)]
+    struct EngineBuilder {
+        options: EngineOptions,
+    }
+
+    impl EngineBuilder {
+        fn trigger_rules(mut self, trigger_rules: TriggerRules) -> Self {
+            self.options.trigger_rules = trigger_rules;
+            self
+        }
+
+        fn bypass(mut self, mode: BypassMode) -> Self {
+            self.options.bypass = BypassOptions {
+                key: BypassKey::ScrollLock,
+                mode,
+            };
+            self
+        }
+
+        fn undo_key(mut self, undo_key: UndoKey) -> Self {
+            self.options.undo_key = undo_key;
+            self
+        }
+
+        fn snippets(mut self, snippets: Vec<Snippet>) -> Self {
+            self.options.snippets = snippets;
+            self
+        }
+
+        fn accents(mut self, accents: AccentOptions) -> Self {
+            self.options.accents = accents;
+            self
+        }
+
+        fn build_with_events(self) -> (TestEngine, Receiver<EngineEvent>) {
+            let (sender, receiver) = mpsc::channel();
+            let engine = WindowEngineState::new(
+                setup_key_combination_map(),
+                StackSizedCharBuffer::<30>::default(),
+                self.options,
+                sender,
+            );
+            (engine, receiver)
+        }
+
+        fn build(self) -> TestEngine {
+            self.build_with_events().0
+        }
+    }
+
+    fn new_engine() -> TestEngine {
+        EngineBuilder::default().build()
+    }
+
+    // Types `text` one character at a time and applies the combinations the
+    // same way the input simulator would, returning what ends up on screen
+    fn type_text(text: &str) -> String {
+        let mut engine = new_engine();
+        let mut screen = String::new();
+
+        for c in text.chars() {
+            screen.push(c);
+            let key = if c == ' ' {
+                Key::Space
+            } else {
+                Key::Unknown(0)
+            };
+            if let Some(target) = engine.handle_key(Some(c), key, 0, Modifiers::default()) {
+                apply_combination(&mut screen, &target);
+            }
+        }
+
+        screen
+    }
+
+    #[test]
+    fn test_combination_inside_word() {
+        assert_eq!(type_text("ewlewgant"), "élégant");
+        assert_eq!(type_text("garccon"), "garçon");
+    }
+
+    #[test]
+    fn test_no_combination_across_boundaries() {
+        assert_eq!(type_text("la eau"), "la eau");
+        assert_eq!(type_text("voila.e"), "voila.e");
+        assert_eq!(type_text("(a)e"), "(a)e");
+    }
+
+    #[test]
+    fn test_apostrophe_elision() {
+        assert_eq!(type_text("l'ewcole"), "l'école");
+        assert_eq!(type_text("qu'eetre"), "qu'être");
+        assert_eq!(type_text("aujourd'hui"), "aujourd'hui");
+    }
+
+    #[test]
+    fn test_punctuation_heavy_text() {
+        assert_eq!(
+            type_text("ewtait-ce vrai? oui, c'ewtait l'ewtew: <trefs beau>!"),
+            "était-ce vrai? oui, c'était l'été: «très beau»!"
+        );
+    }
+
+    #[test]
+    fn test_boundary_can_still_be_replaced() {
+        assert_eq!(type_text("total: 5$"), "total: 5€");
+    }
+
+    #[test]
+    fn test_backspace_keeps_buffer_in_sync() {
+        let mut engine = new_engine();
+        engine.handle_key(Some('e'), Key::E, 0, Modifiers::default());
+        engine.handle_key(Some('k'), Key::K, 0, Modifiers::default());
+        engine.handle_key(Some('\u{8}'), Key::Backspace, 0, Modifiers::default());
+        assert!(matches!(
+            engine.handle_key(Some('w'), Key::W, 0, Modifiers::default()),
+            Some(CombinationTarget::Combine('é'))
+        ));
+    }
+
+    #[test]
+    fn test_delete_keeps_buffer_in_sync() {
+        let mut engine = new_engine();
+        engine.handle_key(Some('e'), Key::E, 0, Modifiers::default());
+        engine.handle_key(None, Key::Delete, 0, Modifiers::default());
+        assert!(matches!(
+            engine.handle_key(Some('w'), Key::W, 0, Modifiers::default()),
+            Some(CombinationTarget::Combine('é'))
+        ));
+    }
+
+    #[test]
+    fn test_edits_invalidate_buffer() {
+        let edits = [
+            (Some('\u{7f}'), Key::Backspace),
+            (Some('\u{1a}'), Key::Z),
+            (Some('\u{16}'), Key::V),
+            (Some('\u{18}'), Key::X),
+            (Some('\u{1}'), Key::A),
+            (None, Key::Insert),
+            (None, Key::LeftArrow),
+            (None, Key::Home),
+            (None, Key::End),
+        ];
+        for (unicode_char, key) in edits {
+            let mut engine = new_engine();
+            engine.handle_key(Some('e'), Key::E, 0, Modifiers::default());
+            engine.handle_key(unicode_char, key, 0, Modifiers::default());
+            assert!(engine
+                .handle_key(Some('w'), Key::W, 0, Modifiers::default())
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn test_chords_are_not_composed() {
+        let alt = Modifiers {
+            alt: true,
+            ..Default::default()
+        };
+        let mut engine = new_engine();
+        engine.handle_key(Some('e'), Key::E, 0, Modifiers::default());
+        assert!(engine.handle_key(Some('e'), Key::E, 0, alt).is_none());
+        assert!(engine
+            .handle_key(Some('w'), Key::W, 0, Modifiers::default())
+            .is_none());
+    }
+
+    #[test]
+    fn test_preview_follows_current_word() {
+        let mut engine = new_engine();
+        for c in "la ewcol".chars() {
+            engine.handle_key(Some(c), Key::Unknown(0), 0, Modifiers::default());
+        }
+        assert_eq!(engine.preview, " écol");
+
+        engine.handle_key(Some('\u{8}'), Key::Backspace, 0, Modifiers::default());
+        assert_eq!(engine.preview, " éco");
+
+        engine.handle_key(None, Key::LeftArrow, 0, Modifiers::default());
+        assert_eq!(engine.preview, "");
+    }
+
+    #[test]
+    fn test_combination_events() {
+        let (mut engine, receiver) = EngineBuilder::default().build_with_events();
+        for c in "eww".chars() {
+            engine.handle_key(Some(c), Key::Unknown(0), 0, Modifiers::default());
+        }
+        let events: Vec<EngineEvent> = receiver.try_iter().collect();
+        assert_eq!(
+            events,
+            vec![
+                EngineEvent::Combination {
+                    kind: CombinationKind::Combine,
+                    output: "é".to_string(),
+                },
+                EngineEvent::Combination {
+                    kind: CombinationKind::Revert,
+                    output: "ew".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_azerty_keeps_words_ending_in_z() {
+        let mut engine = EngineBuilder::default()
+            .trigger_rules(TriggerRules::for_layout(PhysicalLayout::Azerty))
+            .build();
+
+        // On AZERTY z is typed at the QWERTY position of w
+        let keys = [
+            ('c', Key::C, 0x2E),
+            ('h', Key::H, 0x23),
+            ('e', Key::E, 0x12),
+            ('z', Key::W, 0x11),
+        ];
+        for (c, key, scan_code) in keys {
+            assert!(engine
+                .handle_key(Some(c), key, scan_code, Modifiers::default())
+                .is_none());
+        }
+        assert_eq!(engine.preview, "chez");
+        assert!(engine
+            .handle_key(Some(' '), Key::Space, 0x39, Modifiers::default())
+            .is_none());
+
+        // The acute accent is on the key typing w
+        engine.handle_key(Some('e'), Key::E, 0x12, Modifiers::default());
+        assert!(matches!(
+            engine.handle_key(Some('w'), Key::Z, 0x2C, Modifiers::default()),
+            Some(CombinationTarget::Combine('é'))
+        ));
+        assert!(engine
+            .handle_key(Some(' '), Key::Space, 0x39, Modifiers::default())
+            .is_none());
+
+        // The keycaps still combine when Windows types with the QWERTY layout
+        engine.handle_key(Some('e'), Key::E, 0x12, Modifiers::default());
+        assert!(engine
+            .handle_key(Some('w'), Key::W, 0x11, Modifiers::default())
+            .is_none());
+        engine.handle_key(Some('e'), Key::E, 0x12, Modifiers::default());
+        assert!(matches!(
+            engine.handle_key(Some('z'), Key::Z, 0x2C, Modifiers::default()),
+            Some(CombinationTarget::Combine('é'))
+        ));
+    }
+
+    #[test]
+    fn test_bypass_next_key() {
+        let mut engine = EngineBuilder::default().bypass(BypassMode::NextKey).build();
+        engine.handle_key(Some('e'), Key::E, 0, Modifiers::default());
+        engine.handle_key(None, Key::ScrollLock, 0, Modifiers::default());
+        assert!(engine
+            .handle_key(Some('e'), Key::E, 0, Modifiers::default())
+            .is_none());
+        assert_eq!(engine.preview, "ee");
+
+        // The bypassed e does not combine with what follows either
+        assert!(engine
+            .handle_key(Some('e'), Key::E, 0, Modifiers::default())
+            .is_none());
+        assert!(matches!(
+            engine.handle_key(Some('e'), Key::E, 0, Modifiers::default()),
+            Some(CombinationTarget::Combine('ê'))
+        ));
+    }
+
+    #[test]
+    fn test_bypass_hold() {
+        let mut engine = EngineBuilder::default().bypass(BypassMode::Hold).build();
+        engine.handle_event(MouseKeyEvent::Key {
+            unicode_char: None,
+            key: Key::ScrollLock,
+            scan_code: 0x46,
+            modifiers: Modifiers::default(),
+            sequence: None,
+            received_at: Instant::now(),
+        });
+        engine.handle_key(Some('e'), Key::E, 0, Modifiers::default());
+        assert!(engine
+            .handle_key(Some('e'), Key::E, 0, Modifiers::default())
+            .is_none());
+
+        engine.handle_event(MouseKeyEvent::KeyUp {
+            key: Key::ScrollLock,
+        });
+        engine.handle_key(Some('e'), Key::E, 0, Modifiers::default());
+        assert!(matches!(
+            engine.handle_key(Some('e'), Key::E, 0, Modifiers::default()),
+            Some(CombinationTarget::Combine('ê'))
+        ));
+    }
+
+    #[test]
+    fn test_undo_restores_keystrokes() {
+        let mut engine = EngineBuilder::default().undo_key(UndoKey::Pause).build();
+        engine.handle_key(Some('e'), Key::E, 0, Modifiers::default());
+        engine.handle_key(Some('w'), Key::W, 0, Modifiers::default());
+        assert_eq!(engine.preview, "é");
+
+        assert_eq!(
+            engine.undo(None),
+            Some(Replacement {
+                backspaces: 1,
+                text: "ew".to_string(),
+            })
+        );
+        assert_eq!(engine.preview, "ew");
+        assert!(engine
+            .handle_key(Some('w'), Key::W, 0, Modifiers::default())
+            .is_none());
+        assert_eq!(engine.undo(None), None);
+    }
+
+    #[test]
+    fn test_ctrl_backspace_needs_swallow_mode() {
+        let mut engine = EngineBuilder::default()
+            .undo_key(UndoKey::CtrlBackspace)
+            .build();
+        engine.handle_key(Some('e'), Key::E, 0, Modifiers::default());
+        engine.handle_key(Some('w'), Key::W, 0, Modifiers::default());
+
+        // Without a gate the application already erased the word
+        assert_eq!(engine.undo(None), None);
+        assert_eq!(engine.preview, "");
+    }
+
+    #[test]
+    fn test_focus_change_clears_history() {
+        let mut engine = EngineBuilder::default().undo_key(UndoKey::Pause).build();
+        engine.handle_key(Some('e'), Key::E, 0, Modifiers::default());
+        engine.handle_key(Some('w'), Key::W, 0, Modifiers::default());
+        engine.handle_key(None, Key::LeftArrow, 0, Modifiers::default());
+        assert_eq!(engine.undo(None), None);
+    }
+
+    #[test]
+    fn test_snippet_expansion() {
+        let (mut engine, receiver) = EngineBuilder::default()
+            .snippets(vec![Snippet {
+                abbreviation: ";svp".to_string(),
+                expansion: "s'il vous plaît".to_string(),
+            }])
+            .undo_key(UndoKey::Pause)
+            .build_with_events();
+
+        for c in ";svp".chars() {
+            engine.handle_key(Some(c), Key::Unknown(0), 0, Modifiers::default());
+            assert_eq!(engine.pending_expansion.take(), None);
+        }
+        assert!(engine
+            .handle_key(Some(' '), Key::Space, 0, Modifiers::default())
+            .is_none());
+        assert_eq!(
+            engine
+                .pending_expansion
+                .take()
+                .map(|expansion| expansion.replacement(false)),
+            Some(Replacement {
+                backspaces: 5,
+                text: "s'il vous plaît ".to_string(),
+            })
+        );
+        assert!(receiver.try_iter().any(|event| event
+            == EngineEvent::Snippet {
+                output: "s'il vous plaît ".to_string(),
+            }));
+
+        // The undo key types the abbreviation back
+        assert_eq!(
+            engine.undo(None),
+            Some(Replacement {
+                backspaces: 16,
+                text: ";svp ".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_accent_completion() {
+        let (mut engine, receiver) = EngineBuilder::default()
+            .accents(AccentOptions {
+                enabled: true,
+                ..AccentOptions::default()
+            })
+            .undo_key(UndoKey::Pause)
+            .build_with_events();
+        let type_text = |engine: &mut WindowEngineState<_>, text: &str| {
+            for c in text.chars() {
+                engine.handle_key(Some(c), Key::Unknown(0), 0, Modifiers::default());
+            }
+            engine
+                .pending_expansion
+                .take()
+                .map(|expansion| expansion.replacement(false))
+        };
+
+        // Where the first word starts is unknown
+        assert_eq!(type_text(&mut engine, "deja "), None);
+        assert_eq!(
+            type_text(&mut engine, "deja,"),
+            Some(Replacement {
+                backspaces: 5,
+                text: "déjà,".to_string(),
+            })
+        );
+        assert!(receiver.try_iter().any(|event| event
+            == EngineEvent::AccentCompletion {
+                output: "déjà,".to_string(),
+            }));
+        assert_eq!(type_text(&mut engine, "ou "), None);
+        assert_eq!(type_text(&mut engine, "maison "), None);
+        assert_eq!(type_text(&mut engine, "ma2tres "), None);
+
+        // The undo key types the word back
+        type_text(&mut engine, "tres ");
+        assert_eq!(
+            engine.undo(None),
+            Some(Replacement {
+                backspaces: 5,
+                text: "tres ".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_accent_suggestions() {
+        let picks = CandidatePicks::default();
+        let (mut engine, receiver) = EngineBuilder::default()
+            .accents(AccentOptions {
+                suggestions: true,
+                picks: picks.clone(),
+                ..AccentOptions::default()
+            })
+            .undo_key(UndoKey::Pause)
+            .build_with_events();
+        let type_text = |engine: &mut WindowEngineState<_>, text: &str| {
+            for c in text.chars() {
+                engine.handle_key(Some(c), Key::Unknown(0), 0, Modifiers::default());
+            }
+            engine
+                .pending_expansion
+                .take()
+                .map(|expansion| expansion.replacement(false))
+        };
+        let alt = Modifiers {
+            alt: true,
+            ..Modifiers::default()
+        };
+        let pick = |engine: &mut WindowEngineState<_>, key: Key| {
+            engine.handle_key(None, Key::Alt, 0, alt);
+            engine.handle_key(Some('é'), key, 0, alt);
+            engine
+                .pending_pick
+                .take()
+                .map(|expansion| expansion.replacement(true))
+        };
+        let candidates = |receiver: &Receiver<EngineEvent>| -> Vec<Vec<String>> {
+            receiver
+                .try_iter()
+                .filter_map(|event| match event {
+                    EngineEvent::Candidates { candidates } => Some(candidates),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        type_text(&mut engine, "x ");
+        assert_eq!(type_text(&mut engine, "ou "), None);
+        assert_eq!(candidates(&receiver), [["ou", "où"]]);
+
+        // A plain number key hides them and is typed
+        assert_eq!(type_text(&mut engine, "2"), None);
+        assert_eq!(engine.pending_pick, None);
+        assert_eq!(candidates(&receiver), [Vec::<String>::new()]);
+        assert_eq!(engine.preview, " 2");
+        type_text(&mut engine, " a ");
+        candidates(&receiver);
+        assert_eq!(type_text(&mut engine, "2"), None);
+        assert_eq!(engine.pending_pick, None);
+        assert_eq!(engine.preview, " 2");
+
+        // Alt and the number key replace the word, whatever character the key types
+        type_text(&mut engine, " ou ");
+        candidates(&receiver);
+        assert_eq!(
+            pick(&mut engine, Key::Key2),
+            Some(Replacement {
+                backspaces: 3,
+                text: "où ".to_string(),
+            })
+        );
+        assert_eq!(candidates(&receiver), [Vec::<String>::new()]);
+        // Counted where the app saves them from
+        assert_eq!(picks.counts(), [("où".to_string(), 1)].into());
+
+        // Picked first from now on
+        type_text(&mut engine, "ou ");
+        assert_eq!(candidates(&receiver), [["où", "ou"]]);
+
+        // Any other key hides them and is typed
+        assert_eq!(type_text(&mut engine, "x"), None);
+        assert_eq!(candidates(&receiver), [Vec::<String>::new()]);
+        assert_eq!(engine.preview, " x");
+
+        // The undo key types the word back
+        type_text(&mut engine, " la ");
+        pick(&mut engine, Key::Key2);
+        assert_eq!(
+            engine.undo(None),
+            Some(Replacement {
+                backspaces: 3,
+                text: "la ".to_string(),
+            })
+        );
+    }
+
+    struct FakeSecureInput(Rc<RefCell<Option<SecureContext>>>);
+
+    impl SecureInputDetector for FakeSecureInput {
+        fn detect(&mut self) -> Option<SecureContext> {
+            self.0.borrow().clone()
+        }
+    }
+
+    fn key_event(unicode_char: char, key: Key) -> MouseKeyEvent {
+        MouseKeyEvent::Key {
+            unicode_char: Some(unicode_char),
+            key,
+            scan_code: 0,
+            modifiers: Modifiers::default(),
+            sequence: None,
+            received_at: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_secure_input_pauses_the_engine() {
+        let (mut engine, receiver) = EngineBuilder::default().build_with_events();
+        let context = Rc::new(RefCell::new(None));
+        engine.set_secure_input(Box::new(FakeSecureInput(context.clone())));
+
+        engine.handle_event(key_event('e', Key::E));
+        assert_eq!(engine.preview, "e");
+
+        *context.borrow_mut() = Some(SecureContext::PasswordField);
+        engine.handle_event(key_event('w', Key::W));
+        engine.handle_event(key_event('a', Key::A));
+        assert_eq!(engine.preview, "");
+
+        *context.borrow_mut() = None;
+        engine.handle_event(key_event('b', Key::B));
+        assert_eq!(engine.preview, "b");
+
+        assert_eq!(
+            receiver.try_iter().collect::<Vec<_>>(),
+            vec![
+                EngineEvent::Buffer {
+                    content: "e".to_string()
+                },
+                EngineEvent::Buffer {
+                    content: String::new()
+                },
+                EngineEvent::SecureInput { active: true },
+                EngineEvent::SecureInput { active: false },
+                EngineEvent::Buffer {
+                    content: "b".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_secure_input_is_not_timed() {
+        let (mut engine, receiver) = EngineBuilder::default().build_with_events();
+        engine.set_secure_input(Box::new(FakeSecureInput(Rc::new(RefCell::new(Some(
+            SecureContext::SecureDesktop,
+        ))))));
+
+        engine.handle_event(key_event('e', Key::E));
+        engine.report_latency(Instant::now() + REPORT_INTERVAL);
+        assert!(!receiver
+            .try_iter()
+            .any(|event| matches!(event, EngineEvent::Latency { .. })));
+    }
+
+    static LIFECYCLE_LOCK: Mutex<()> = Mutex::new(());
+    static ACTIVE_LISTENERS: AtomicUsize = AtomicUsize::new(0);
+    static MAX_ACTIVE_LISTENERS: AtomicUsize = AtomicUsize::new(0);
+    static LISTENER_STARTS: AtomicUsize = AtomicUsize::new(0);
+
+    // Counts how many listeners are hooked at the same time, each one runs
+    // a thread standing in for the hook thread until it is stopped
+    struct CountingListener {
+        stop_sender: Option<Sender<()>>,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    impl InputListener for CountingListener {
+        fn start_mouse_key_listening(
+            _sender: Sender<MouseKeyEvent>,
+            _swallow_gate: Option<Arc<SwallowGate>>,
+        ) -> Result<Self, String> {
+            let active = ACTIVE_LISTENERS.fetch_add(1, Ordering::SeqCst) + 1;
+            MAX_ACTIVE_LISTENERS.fetch_max(active, Ordering::SeqCst);
+            LISTENER_STARTS.fetch_add(1, Ordering::SeqCst);
+
+            let (stop_sender, stop_receiver) = mpsc::channel::<()>();
+            let thread = thread::spawn(move || {
+                let _ = stop_receiver.recv();
+            });
+            Ok(Self {
+                stop_sender: Some(stop_sender),
+                thread: Some(thread),
+            })
+        }
+
+        fn start_shortcut_listening(_sender: Sender<()>) -> Result<Self, String> {
+            Err("not supported".to_string())
+        }
+
+        fn is_hook_alive(&self) -> bool {
+            true
+        }
+
+        fn stop(&mut self) -> bool {
+            self.stop_sender = None;
+            let Some(thread) = self.thread.take() else {
+                return true;
+            };
+            thread.join().unwrap();
+            ACTIVE_LISTENERS.fetch_sub(1, Ordering::SeqCst);
+            true
+        }
+    }
+
+    impl Drop for CountingListener {
+        fn drop(&mut self) {
+            let _ = self.stop();
+        }
+    }
+
+    fn lock_lifecycle() -> MutexGuard<'static, ()> {
+        let guard = LIFECYCLE_LOCK
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        ACTIVE_LISTENERS.store(0, Ordering::SeqCst);
+        MAX_ACTIVE_LISTENERS.store(0, Ordering::SeqCst);
+        LISTENER_STARTS.store(0, Ordering::SeqCst);
+        guard
+    }
+
+    fn start_counting_engine() -> WindowEngine {
+        let (events, _) = mpsc::channel();
+        WindowEngine::start_with::<CountingListener, _>(
+            setup_key_combination_map(),
+            StackSizedCharBuffer::<30>::default(),
+            EngineOptions::default(),
+            events,
+            SupervisorConfig {
+                health_check_interval: Duration::from_millis(1),
+                initial_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(1),
+            },
+        )
+    }
+
+    fn join(mut engine: WindowEngine) {
+        assert!(engine.stop());
+    }
+
+    fn wait_for_starts(starts: usize) {
+        while LISTENER_STARTS.load(Ordering::SeqCst) < starts {
+            thread::sleep(Duration::from_millis(1));
+        }
     }
 
-    pub fn stop() {
-        InputListenerImpl::stop_mouse_key_listening();
+    #[test]
+    fn test_double_start_waits_for_running_engine() {
+        let _lock = lock_lifecycle();
+
+        let first = start_counting_engine();
+        wait_for_starts(1);
+        let second = start_counting_engine();
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(LISTENER_STARTS.load(Ordering::SeqCst), 1);
+        assert!(first.is_running());
+
+        join(first);
+        wait_for_starts(2);
+        join(second);
+
+        assert_eq!(MAX_ACTIVE_LISTENERS.load(Ordering::SeqCst), 1);
+        assert_eq!(ACTIVE_LISTENERS.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_stop_before_start() {
+        let _lock = lock_lifecycle();
+
+        let mut engine = start_counting_engine();
+        assert!(engine.stop());
+        assert!(engine.stop());
+        assert!(!engine.is_running());
+
+        assert_eq!(ACTIVE_LISTENERS.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_rapid_toggling() {
+        let _lock = lock_lifecycle();
+
+        let mut engines = vec![];
+        for _ in 0..100 {
+            let engine = start_counting_engine();
+            engine.running.store(false, Ordering::SeqCst);
+            engines.push(engine);
+        }
+        engines.push(start_counting_engine());
+        for engine in engines {
+            join(engine);
+        }
+
+        assert!(MAX_ACTIVE_LISTENERS.load(Ordering::SeqCst) <= 1);
+        assert_eq!(ACTIVE_LISTENERS.load(Ordering::SeqCst), 0);
     }
 
-    pub fn set_toggle_channel(sender: Sender<()>) {
-        InputListenerImpl::start_shortcut_listening(sender);
+    #[test]
+    fn test_toggle_without_leaking_threads() {
+        let _lock = lock_lifecycle();
+
+        // Joining checks the engine thread exited, the listener only counts
+        // itself inactive once its thread is joined
+        for _ in 0..1000 {
+            let engine = start_counting_engine();
+            join(engine);
+        }
+
+        assert_eq!(ACTIVE_LISTENERS.load(Ordering::SeqCst), 0);
     }
 }
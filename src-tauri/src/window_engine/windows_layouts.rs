@@ -0,0 +1,128 @@
+use super::keyboard_layout::KeyOutput;
+use super::keyboard_state::{VK_CAPITAL, VK_NUMLOCK, VK_SCROLL};
+use super::keys_converter::scan_code_without_prefix;
+use super::translator::SystemLayouts;
+use std::char;
+use std::ffi::c_void;
+use std::mem::size_of_val;
+use windows::core::{w, PCWSTR, PWSTR};
+use windows::Win32::System::Registry::{
+    RegCloseKey, RegEnumKeyExW, RegGetValueW, RegOpenKeyExW, HKEY, HKEY_LOCAL_MACHINE, KEY_READ,
+    RRF_RT_REG_SZ,
+};
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetKeyState, GetKeyboardLayout, ToUnicodeEx, HKL,
+};
+use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+const BUFFER_LEN: usize = 32;
+// Leaves the keyboard state untouched, so the dead key the focused application is
+// waiting on is not consumed. Supported since Windows 10 1607
+const TO_UNICODE_KEEP_STATE: u32 = 0x4;
+
+pub(super) struct WindowsLayouts;
+
+impl SystemLayouts for WindowsLayouts {
+    fn toggled_keys(&self) -> Vec<u32> {
+        [VK_CAPITAL, VK_NUMLOCK, VK_SCROLL]
+            .into_iter()
+            .filter(|virtual_key| unsafe { GetKeyState(*virtual_key as i32) } & 1 != 0)
+            .collect()
+    }
+
+    // This thread has a layout of its own, the focused window's is the one typed with
+    fn foreground_layout(&self) -> usize {
+        let thread_id = unsafe { GetWindowThreadProcessId(GetForegroundWindow(), None) };
+        unsafe { GetKeyboardLayout(thread_id) }.0 as usize
+    }
+
+    fn variant_klid(&self, layout_id: u16) -> Option<u32> {
+        unsafe { Self::registry_klid(layout_id) }
+    }
+
+    fn key_output(
+        &self,
+        virtual_key_code: u32,
+        scan_code: u32,
+        keyboard_state: &[u8; 256],
+        hkl: usize,
+    ) -> KeyOutput {
+        let mut buff = [0_u16; BUFFER_LEN];
+        let len = unsafe {
+            ToUnicodeEx(
+                virtual_key_code,
+                scan_code_without_prefix(scan_code),
+                keyboard_state,
+                &mut buff,
+                TO_UNICODE_KEEP_STATE,
+                HKL(hkl as *mut c_void),
+            )
+        };
+
+        let first_char = char::decode_utf16(buff).next().and_then(|c| c.ok());
+        match (len, first_char) {
+            // The buffer holds what the dead key types alone
+            (-1, Some(c)) => KeyOutput::Dead(c),
+            (1, Some(c)) => KeyOutput::Char(c),
+            _ => KeyOutput::None,
+        }
+    }
+}
+
+impl WindowsLayouts {
+    unsafe fn registry_klid(layout_id: u16) -> Option<u32> {
+        let mut layouts = HKEY::default();
+        let opened = RegOpenKeyExW(
+            HKEY_LOCAL_MACHINE,
+            w!(r"SYSTEM\CurrentControlSet\Control\Keyboard Layouts"),
+            0,
+            KEY_READ,
+            &mut layouts,
+        );
+        if opened.is_err() {
+            return None;
+        }
+
+        let mut klid = None;
+        let mut name = [0_u16; 256];
+        let mut value = [0_u16; 16];
+        for index in 0.. {
+            let mut name_len = name.len() as u32;
+            let listed = RegEnumKeyExW(
+                layouts,
+                index,
+                PWSTR(name.as_mut_ptr()),
+                &mut name_len,
+                None,
+                PWSTR::null(),
+                None,
+                None,
+            );
+            if listed.is_err() {
+                break;
+            }
+
+            value.fill(0);
+            let mut value_len = size_of_val(&value) as u32;
+            let read = RegGetValueW(
+                layouts,
+                PCWSTR(name.as_ptr()),
+                w!("Layout Id"),
+                RRF_RT_REG_SZ,
+                None,
+                Some(value.as_mut_ptr() as *mut c_void),
+                Some(&mut value_len),
+            );
+            let value = String::from_utf16_lossy(&value);
+            if read.is_ok()
+                && u16::from_str_radix(value.trim_end_matches('\0'), 16) == Ok(layout_id)
+            {
+                let name = String::from_utf16_lossy(&name[..name_len as usize]);
+                klid = u32::from_str_radix(&name, 16).ok();
+                break;
+            }
+        }
+        let _ = RegCloseKey(layouts);
+        klid
+    }
+}
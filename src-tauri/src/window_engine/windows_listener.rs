@@ -0,0 +1,349 @@
+use super::input_listener::{InputListener, MouseKeyEvent, RawInputEvent};
+use super::keys_converter::{scan_code_with_prefix, KeyConverter};
+use super::swallow_gate::SwallowGate;
+use super::translator::KeyTranslator;
+use super::windows_layouts::WindowsLayouts;
+use crate::utils::{join_timeout, spawn_timed, TimedJoinHandle};
+use clavfrancais_engine::keys::Key;
+use log::{debug, warn};
+use std::cell::RefCell;
+use std::mem::size_of;
+use std::ptr::null_mut;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use windows::Win32::Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::SystemInformation::GetTickCount;
+use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetKeyState, GetLastInputInfo, LASTINPUTINFO, VK_CONTROL, VK_MENU,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CallNextHookEx, PeekMessageW, PostThreadMessageW, SetWindowsHookExW, UnhookWindowsHookEx,
+    WaitMessage, HC_ACTION, HHOOK, HOOKPROC, KBDLLHOOKSTRUCT, LLKHF_EXTENDED, LLKHF_INJECTED,
+    PEEK_MESSAGE_REMOVE_TYPE, WH_KEYBOARD_LL, WH_MOUSE_LL, WINDOWS_HOOK_ID, WM_KEYDOWN, WM_KEYUP,
+    WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MBUTTONDOWN, WM_MBUTTONUP, WM_NULL, WM_RBUTTONDOWN,
+    WM_RBUTTONUP, WM_SYSKEYDOWN, WM_SYSKEYUP,
+};
+
+const STOP_TIMEOUT: Duration = Duration::from_secs(1);
+// Far below LowLevelHooksTimeout, a key the engine did not answer for by then is let through
+const SWALLOW_DECISION_TIMEOUT: Duration = Duration::from_millis(50);
+// Input the system saw this long after the last hook callback means the hook is gone
+const HOOK_LOST_THRESHOLD_MS: u32 = 5000;
+
+enum HookSender {
+    MouseKey {
+        sender: Sender<RawInputEvent>,
+        swallow_gate: Option<Arc<SwallowGate>>,
+    },
+    Shortcut(Sender<()>),
+}
+
+// Owned by the hook thread, hook callbacks run on the thread that installed them
+struct HookState {
+    sender: HookSender,
+    last_hook_tick: Arc<AtomicU32>,
+}
+
+thread_local! {
+    static HOOK_STATE: RefCell<Option<HookState>> = const { RefCell::new(None) };
+}
+
+pub struct WindowsListener {
+    thread_id: u32,
+    listening: Arc<AtomicBool>,
+    last_hook_tick: Arc<AtomicU32>,
+    thread: Option<TimedJoinHandle<()>>,
+    translator: Option<TimedJoinHandle<()>>,
+}
+
+impl WindowsListener {
+    unsafe fn process_shortcut_event(
+        sender: &Sender<()>,
+        code: i32,
+        param: WPARAM,
+        lpdata: LPARAM,
+    ) {
+        if code as u32 != HC_ACTION {
+            return;
+        }
+        match param.0 as u32 {
+            WM_KEYDOWN | WM_SYSKEYDOWN => {
+                let keyboard_struct = *(lpdata.0 as *const KBDLLHOOKSTRUCT);
+                let virtual_key_code = keyboard_struct.vkCode;
+
+                let key = Key::from_virtual_key_code(virtual_key_code);
+                match key {
+                    Key::ControlLeft | Key::ControlRight => {
+                        let alt_state = GetKeyState(VK_MENU.0.into());
+                        let alt_down = alt_state < 0;
+                        if alt_down {
+                            let _ = sender.send(());
+                        }
+                    }
+                    Key::Alt => {
+                        let ctrl_state = GetKeyState(VK_CONTROL.0.into());
+                        let ctrl_down = ctrl_state < 0;
+                        if ctrl_down {
+                            let _ = sender.send(());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => (),
+        }
+    }
+
+    // Runs inside the hook callback, only records the event so the callback returns
+    // right away, the characters are computed by the translation stage.
+    // Returns true when the key must not reach the application
+    unsafe fn process_mouse_key_event(
+        sender: &Sender<RawInputEvent>,
+        swallow_gate: Option<&SwallowGate>,
+        code: i32,
+        param: WPARAM,
+        lpdata: LPARAM,
+        received_at: Instant,
+    ) -> bool {
+        if code as u32 != HC_ACTION {
+            return false;
+        }
+        let message = param.0 as u32;
+        match message {
+            WM_KEYDOWN | WM_SYSKEYDOWN | WM_KEYUP | WM_SYSKEYUP => {
+                let keyboard_struct = *(lpdata.0 as *const KBDLLHOOKSTRUCT);
+                let key_down = message == WM_KEYDOWN || message == WM_SYSKEYDOWN;
+                let injected = (keyboard_struct.flags & LLKHF_INJECTED).0 != 0;
+                let extended = (keyboard_struct.flags & LLKHF_EXTENDED).0 != 0;
+
+                // Only keys the engine handles can be swallowed
+                let swallow_gate = swallow_gate.filter(|_| key_down && !injected);
+                let sequence = swallow_gate.map(|swallow_gate| swallow_gate.open());
+
+                let sent = sender.send(RawInputEvent::Key {
+                    virtual_key_code: keyboard_struct.vkCode,
+                    scan_code: scan_code_with_prefix(keyboard_struct.scanCode, extended),
+                    key_down,
+                    injected,
+                    sequence,
+                    received_at,
+                });
+
+                match (swallow_gate, sequence) {
+                    (Some(swallow_gate), Some(sequence)) if sent.is_ok() => {
+                        swallow_gate.wait(sequence, SWALLOW_DECISION_TIMEOUT)
+                    }
+                    _ => false,
+                }
+            }
+            WM_RBUTTONDOWN | WM_RBUTTONUP | WM_LBUTTONDOWN | WM_LBUTTONUP | WM_MBUTTONDOWN
+            | WM_MBUTTONUP => {
+                let _ = sender.send(RawInputEvent::Mouse { received_at });
+                false
+            }
+            _ => false,
+        }
+    }
+
+    unsafe extern "system" fn raw_hook_callback(
+        code: i32,
+        param: WPARAM,
+        lpdata: LPARAM,
+    ) -> LRESULT {
+        let received_at = Instant::now();
+        let swallow = HOOK_STATE.with_borrow_mut(|hook_state| {
+            let Some(hook_state) = hook_state else {
+                return false;
+            };
+            hook_state
+                .last_hook_tick
+                .store(GetTickCount(), Ordering::Relaxed);
+
+            match &hook_state.sender {
+                HookSender::MouseKey {
+                    sender,
+                    swallow_gate,
+                } => Self::process_mouse_key_event(
+                    sender,
+                    swallow_gate.as_deref(),
+                    code,
+                    param,
+                    lpdata,
+                    received_at,
+                ),
+                HookSender::Shortcut(sender) => {
+                    Self::process_shortcut_event(sender, code, param, lpdata);
+                    false
+                }
+            }
+        });
+
+        // A non-zero result keeps the key from the rest of the hook chain and the application
+        if swallow {
+            return LRESULT(1);
+        }
+        CallNextHookEx(HHOOK(null_mut()), code, param, lpdata)
+    }
+
+    fn spawn_hook_thread(
+        sender: HookSender,
+        hook_ids: &'static [WINDOWS_HOOK_ID],
+    ) -> Result<Self, String> {
+        let listening = Arc::new(AtomicBool::new(true));
+        let last_hook_tick = Arc::new(AtomicU32::new(unsafe { GetTickCount() }));
+        let (installed_sender, installed_receiver) = mpsc::sync_channel::<Result<u32, String>>(1);
+
+        let thread = {
+            let listening = listening.clone();
+            let last_hook_tick = last_hook_tick.clone();
+            spawn_timed(move || unsafe {
+                let mut hooks = vec![];
+                for hook_id in hook_ids {
+                    let callback: HOOKPROC = Some(Self::raw_hook_callback);
+                    match SetWindowsHookExW(*hook_id, callback, HINSTANCE(null_mut()), 0) {
+                        Ok(hook) => hooks.push(hook),
+                        Err(error) => {
+                            for hook in hooks {
+                                let _ = UnhookWindowsHookEx(hook);
+                            }
+                            let _ = installed_sender.send(Err(error.message()));
+                            return;
+                        }
+                    }
+                }
+
+                HOOK_STATE.set(Some(HookState {
+                    sender,
+                    last_hook_tick,
+                }));
+                let _ = installed_sender.send(Ok(GetCurrentThreadId()));
+
+                loop {
+                    if WaitMessage().is_err() {
+                        break;
+                    }
+
+                    if !listening.load(Ordering::SeqCst) {
+                        break;
+                    }
+
+                    let _ = PeekMessageW(
+                        null_mut(),
+                        HWND(null_mut()),
+                        0,
+                        0,
+                        PEEK_MESSAGE_REMOVE_TYPE(0),
+                    );
+                }
+
+                for hook in hooks {
+                    let _ = UnhookWindowsHookEx(hook);
+                }
+
+                HOOK_STATE.set(None);
+            })
+        };
+
+        match installed_receiver.recv() {
+            Ok(Ok(thread_id)) => {
+                debug!(thread_id, hooks = hook_ids.len(); "Hooks installed");
+                Ok(Self {
+                    thread_id,
+                    listening,
+                    last_hook_tick,
+                    thread: Some(thread),
+                    translator: None,
+                })
+            }
+            Ok(Err(message)) => Err(message),
+            Err(_) => Err("Listener thread exited before installing the hooks".to_string()),
+        }
+    }
+}
+
+impl InputListener for WindowsListener {
+    fn start_mouse_key_listening(
+        sender: Sender<MouseKeyEvent>,
+        swallow_gate: Option<Arc<SwallowGate>>,
+    ) -> Result<Self, String> {
+        // The translation stage exits once the hook thread drops the raw sender
+        let (raw_sender, raw_receiver) = mpsc::channel::<RawInputEvent>();
+        let translator = {
+            let swallow_gate = swallow_gate.clone();
+            spawn_timed(move || {
+                KeyTranslator::new(WindowsLayouts, swallow_gate).run(raw_receiver, sender)
+            })
+        };
+
+        let hook_sender = HookSender::MouseKey {
+            sender: raw_sender,
+            swallow_gate,
+        };
+        match Self::spawn_hook_thread(hook_sender, &[WH_KEYBOARD_LL, WH_MOUSE_LL]) {
+            Ok(mut listener) => {
+                listener.translator = Some(translator);
+                Ok(listener)
+            }
+            Err(message) => {
+                let _ = join_timeout(translator, STOP_TIMEOUT);
+                Err(message)
+            }
+        }
+    }
+
+    fn start_shortcut_listening(sender: Sender<()>) -> Result<Self, String> {
+        Self::spawn_hook_thread(HookSender::Shortcut(sender), &[WH_KEYBOARD_LL])
+    }
+
+    fn is_hook_alive(&self) -> bool {
+        let is_finished = |thread: &Option<TimedJoinHandle<()>>| {
+            thread.as_ref().is_some_and(|thread| thread.is_finished())
+        };
+        if self.thread.is_none() || is_finished(&self.thread) || is_finished(&self.translator) {
+            return false;
+        }
+
+        let mut last_input = LASTINPUTINFO {
+            cbSize: size_of::<LASTINPUTINFO>() as u32,
+            dwTime: 0,
+        };
+        unsafe {
+            if !GetLastInputInfo(&mut last_input).as_bool() {
+                return true;
+            }
+        }
+
+        // Tick counts wrap around after 49 days, compare the signed difference
+        let last_hook_tick = self.last_hook_tick.load(Ordering::Relaxed);
+        let unseen_input_ms = last_input.dwTime.wrapping_sub(last_hook_tick) as i32;
+        unseen_input_ms < HOOK_LOST_THRESHOLD_MS as i32
+    }
+
+    fn stop(&mut self) -> bool {
+        self.listening.store(false, Ordering::SeqCst);
+        let hook_stopped = self.thread.take().is_none_or(|thread| {
+            // Wakes the hook thread up from WaitMessage so it unhooks right away
+            unsafe {
+                let _ = PostThreadMessageW(self.thread_id, WM_NULL, WPARAM(0), LPARAM(0));
+            }
+            join_timeout(thread, STOP_TIMEOUT).is_some()
+        });
+        let translator_stopped = self
+            .translator
+            .take()
+            .is_none_or(|translator| join_timeout(translator, STOP_TIMEOUT).is_some());
+        if !hook_stopped || !translator_stopped {
+            warn!(hook_stopped, translator_stopped; "Listener threads did not exit in time");
+        }
+        hook_stopped && translator_stopped
+    }
+}
+
+impl Drop for WindowsListener {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
@@ -0,0 +1,135 @@
+pub const DEFAULT_BOUNDARY_CHARS: &str = ".,;:!?()[]{}\"/-";
+
+const APOSTROPHES: [char; 2] = ['\'', '’'];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BoundaryKind {
+    None,
+    // The character ends the current word, it can still trigger single character
+    // combinations such as `$` -> `€` but nothing before it can be combined with it
+    Boundary,
+    // An apostrophe right after a letter (l', d', qu'...), the next letter starts a new word
+    Elision,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct WordBoundary {
+    boundary_chars: Vec<char>,
+    apostrophe_elision: bool,
+}
+
+impl Default for WordBoundary {
+    fn default() -> Self {
+        Self::new(DEFAULT_BOUNDARY_CHARS, true)
+    }
+}
+
+impl WordBoundary {
+    pub fn new(boundary_chars: &str, apostrophe_elision: bool) -> Self {
+        Self {
+            boundary_chars: boundary_chars.chars().collect(),
+            apostrophe_elision,
+        }
+    }
+
+    pub fn classify(&self, previous_char: Option<char>, c: char) -> BoundaryKind {
+        if APOSTROPHES.contains(&c) {
+            if !self.apostrophe_elision {
+                return BoundaryKind::None;
+            }
+            return match previous_char {
+                Some(previous_char) if previous_char.is_alphabetic() => BoundaryKind::Elision,
+                _ => BoundaryKind::Boundary,
+            };
+        }
+
+        if c.is_whitespace() || self.boundary_chars.contains(&c) {
+            return BoundaryKind::Boundary;
+        }
+
+        BoundaryKind::None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BoundaryKind, WordBoundary};
+
+    fn boundaries(word_boundary: &WordBoundary, text: &str) -> Vec<(char, BoundaryKind)> {
+        let mut previous_char = None;
+        let mut result = vec![];
+        for c in text.chars() {
+            let kind = word_boundary.classify(previous_char, c);
+            if kind != BoundaryKind::None {
+                result.push((c, kind));
+            }
+            previous_char = Some(c);
+        }
+        result
+    }
+
+    #[test]
+    fn test_letters_are_not_boundaries() {
+        let word_boundary = WordBoundary::default();
+        assert!(boundaries(&word_boundary, "lecoleestfermee").is_empty());
+    }
+
+    #[test]
+    fn test_punctuation_heavy_text() {
+        let word_boundary = WordBoundary::default();
+        assert_eq!(
+            boundaries(
+                &word_boundary,
+                "Quoi?! (vraiment), oui; non: \"peut-etre\"."
+            ),
+            vec![
+                ('?', BoundaryKind::Boundary),
+                ('!', BoundaryKind::Boundary),
+                (' ', BoundaryKind::Boundary),
+                ('(', BoundaryKind::Boundary),
+                (')', BoundaryKind::Boundary),
+                (',', BoundaryKind::Boundary),
+                (' ', BoundaryKind::Boundary),
+                (';', BoundaryKind::Boundary),
+                (' ', BoundaryKind::Boundary),
+                (':', BoundaryKind::Boundary),
+                (' ', BoundaryKind::Boundary),
+                ('"', BoundaryKind::Boundary),
+                ('-', BoundaryKind::Boundary),
+                ('"', BoundaryKind::Boundary),
+                ('.', BoundaryKind::Boundary),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apostrophe_elision() {
+        let word_boundary = WordBoundary::default();
+        assert_eq!(
+            boundaries(&word_boundary, "l'eecole qu’il"),
+            vec![
+                ('\'', BoundaryKind::Elision),
+                (' ', BoundaryKind::Boundary),
+                ('’', BoundaryKind::Elision),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apostrophe_as_quote() {
+        let word_boundary = WordBoundary::default();
+        assert_eq!(
+            boundaries(&word_boundary, " 'a"),
+            vec![
+                (' ', BoundaryKind::Boundary),
+                ('\'', BoundaryKind::Boundary)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apostrophe_elision_disabled() {
+        let word_boundary = WordBoundary::new("", false);
+        assert!(boundaries(&word_boundary, "l'ecole,").is_empty());
+    }
+}
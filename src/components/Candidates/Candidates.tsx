@@ -0,0 +1,39 @@
+import { Component, createEffect, createSignal, For, onCleanup } from "solid-js";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import styles from "./candidates.module.css";
+
+// The spellings of the word just typed, picked with Alt and the number keys
+const Candidates: Component<{}> = () => {
+    const [candidates, setCandidates] = createSignal<string[]>([]);
+
+    // The window is transparent around the list
+    document.documentElement.style.background = "transparent";
+
+    createEffect(async () => {
+        setCandidates(await invoke<string[]>("get_candidates_command"));
+    });
+
+    const unlisten = listen<string[]>("show_candidates", (event) => {
+        setCandidates(event.payload);
+    });
+
+    onCleanup(() => {
+        unlisten.then((f) => f());
+    });
+
+    return (
+        <ol class={styles.candidates}>
+            <For each={candidates()}>
+                {(candidate, index) => (
+                    <li class={styles.candidate}>
+                        <span class={styles.number}>{index() + 1}</span>
+                        {candidate}
+                    </li>
+                )}
+            </For>
+        </ol>
+    );
+};
+
+export default Candidates;
@@ -0,0 +1,128 @@
+import { Component, createEffect, createSignal, onCleanup } from "solid-js";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import Panel from "../Panel/Panel";
+import styles from "./engine-status.module.css";
+import { EngineEvent, EngineStatus as Status } from "../../types/engine-event";
+
+const statusFromEvent = (event: EngineEvent): Status | undefined => {
+    switch (event.type) {
+        case "started":
+        case "hookInstalled":
+            return Status.Running;
+        case "stopped":
+            return Status.Stopped;
+        case "crashed":
+            return Status.Crashed;
+        case "hookFailed":
+            return Status.HookFailed;
+        case "hookLost":
+            return Status.Recovering;
+        default:
+            return undefined;
+    }
+};
+
+const EngineStatus: Component<{}> = () => {
+    const [status, setStatus] = createSignal<Status>(Status.Stopped);
+    const [preview, setPreview] = createSignal("");
+    const [error, setError] = createSignal("");
+    const [latency, setLatency] = createSignal<
+        { p50Micros: number; p95Micros: number } | undefined
+    >(undefined);
+    const [secureInput, setSecureInput] = createSignal(false);
+    const [pausedUntil, setPausedUntil] = createSignal<Date | undefined>(undefined);
+
+    const updatePause = (remaining: number | null) => {
+        setPausedUntil(remaining === null ? undefined : new Date(Date.now() + remaining * 1000));
+    };
+
+    createEffect(async () => {
+        const value = await invoke<Status>("get_engine_status_command");
+        setStatus(value);
+        updatePause(await invoke<number | null>("get_pause_remaining_command"));
+    });
+
+    const unlistenPause = listen<number | null>("pause_changed", (event) => {
+        updatePause(event.payload);
+    });
+
+    const unlisten = listen<EngineEvent>("engine_event", (event) => {
+        const engineEvent = event.payload;
+        const newStatus = statusFromEvent(engineEvent);
+        if (newStatus !== undefined) {
+            setStatus(newStatus);
+        }
+
+        if (engineEvent.type === "buffer") {
+            setPreview(engineEvent.content);
+        } else if (engineEvent.type === "crashed" || engineEvent.type === "hookFailed") {
+            setError(engineEvent.message);
+        } else if (engineEvent.type === "started") {
+            setError("");
+        } else if (engineEvent.type === "stopped") {
+            setPreview("");
+            setLatency(undefined);
+            setSecureInput(false);
+        } else if (engineEvent.type === "secureInput") {
+            setSecureInput(engineEvent.active);
+        } else if (engineEvent.type === "latency") {
+            setLatency({
+                p50Micros: engineEvent.p50Micros,
+                p95Micros: engineEvent.p95Micros,
+            });
+        }
+    });
+
+    onCleanup(() => {
+        unlisten.then((f) => f());
+        unlistenPause.then((f) => f());
+    });
+
+    const indicatorClass = () => {
+        if (status() === Status.Running) {
+            return `${styles.indicator} ${styles.running}`;
+        }
+        if (status() === Status.Crashed || status() === Status.HookFailed) {
+            return `${styles.indicator} ${styles.failed}`;
+        }
+        if (status() === Status.Recovering) {
+            return `${styles.indicator} ${styles.recovering}`;
+        }
+        return styles.indicator;
+    };
+
+    return (
+        <section class={styles.section}>
+            <Panel maxWidth={500}>
+                <div class={styles.container}>
+                    <span class={styles.status} title={error()}>
+                        <span class={indicatorClass()} />
+                        {status()}
+                    </span>
+                    <span class={styles.preview}>
+                        {pausedUntil() !== undefined
+                            ? `Paused until ${pausedUntil()!.toLocaleTimeString([], { hour: "2-digit", minute: "2-digit" })}`
+                            : secureInput()
+                              ? "Paused in secure input"
+                              : preview()}
+                    </span>
+                    <span
+                        class={styles.latency}
+                        title={
+                            latency() !== undefined
+                                ? `95% of keys under ${(latency()!.p95Micros / 1000).toFixed(1)} ms`
+                                : ""
+                        }
+                    >
+                        {latency() !== undefined
+                            ? `${(latency()!.p50Micros / 1000).toFixed(1)} ms`
+                            : ""}
+                    </span>
+                </div>
+            </Panel>
+        </section>
+    );
+};
+
+export default EngineStatus;
@@ -0,0 +1,37 @@
+import { Component, For, JSX } from "solid-js";
+import styles from "./list-field.module.css";
+
+interface ListFieldProps {
+    name: string;
+    items: string[];
+    onremove: (index: number) => void;
+    // The controls adding an item
+    children: JSX.Element;
+}
+
+const ListField: Component<ListFieldProps> = (props) => {
+    return (
+        <div class={styles.container}>
+            {props.name}
+            <ul class={styles.list}>
+                <For each={props.items}>
+                    {(item, index) => (
+                        <li class={styles.item}>
+                            {item}
+                            <button
+                                class={styles.removeButton}
+                                title="Remove"
+                                onclick={() => props.onremove(index())}
+                            >
+                                ×
+                            </button>
+                        </li>
+                    )}
+                </For>
+            </ul>
+            <div class={styles.add}>{props.children}</div>
+        </div>
+    );
+};
+
+export default ListField;
@@ -4,13 +4,17 @@ import LanguagePicker from "../LanguagePicker/LanguagePicker";
 import Settings from "../Settings/Settings";
 import styles from "./main-app-layout.module.css"
 import Footer from "../Footer/Footer";
+import EngineStatus from "../EngineStatus/EngineStatus";
+import Stats from "../Stats/Stats";
 
 const MainAppLayout: Component<{}> = () => {
     return (
         <main class={styles.main}>
             <Header />
             <LanguagePicker />
+            <EngineStatus />
             <Settings />
+            <Stats />
             <Footer />
         </main>
     );
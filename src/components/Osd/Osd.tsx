@@ -0,0 +1,37 @@
+import { Component, createEffect, createSignal, onCleanup } from "solid-js";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import styles from "./osd.module.css";
+
+interface OsdMessage {
+    text: string;
+    opacity: number;
+}
+
+// The indicator shown in its own window when the language changes
+const Osd: Component<{}> = () => {
+    const [message, setMessage] = createSignal<OsdMessage | null>(null);
+
+    // The window is transparent around the indicator
+    document.documentElement.style.background = "transparent";
+
+    createEffect(async () => {
+        setMessage(await invoke<OsdMessage | null>("get_osd_command"));
+    });
+
+    const unlisten = listen<OsdMessage>("show_osd", (event) => {
+        setMessage(event.payload);
+    });
+
+    onCleanup(() => {
+        unlisten.then((f) => f());
+    });
+
+    return (
+        <div class={styles.osd} style={{ opacity: (message()?.opacity ?? 100) / 100 }}>
+            {message()?.text}
+        </div>
+    );
+};
+
+export default Osd;
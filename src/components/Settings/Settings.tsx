@@ -1,20 +1,105 @@
-import { Component, createEffect, createSignal } from "solid-js";
+import { Component, createEffect, createSignal, onCleanup } from "solid-js";
 import Panel from "../Panel/Panel";
 import ToggleSwitch from "../ToggleSwitch/ToggleSwitch";
 import styles from "./settings.module.css";
 import Selection from "../Selection/Selection";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import RoundedButton from "../RoundedButton/RoundedButton";
+import TextField from "../TextField/TextField";
+import ListField from "../ListField/ListField";
 
 enum ToggleShortcut {
     CtrlAlt = "CtrlAlt",
     AltZ = "AltZ",
 }
 
+enum ReplacementMode {
+    Retype = "Retype",
+    Swallow = "Swallow",
+}
+
+enum PhysicalLayout {
+    Qwerty = "Qwerty",
+    Azerty = "Azerty",
+    Qwertz = "Qwertz",
+}
+
+type Trigger = { char: string } | { scanCode: number };
+
+interface TriggerRule {
+    trigger: Trigger;
+    engineChar: string;
+}
+
+enum BypassKey {
+    Escape = "Escape",
+    ScrollLock = "ScrollLock",
+    Pause = "Pause",
+}
+
+enum BypassMode {
+    Off = "Off",
+    NextKey = "NextKey",
+    NextWord = "NextWord",
+    Hold = "Hold",
+}
+
+enum UndoKey {
+    Off = "Off",
+    CtrlBackspace = "CtrlBackspace",
+    Pause = "Pause",
+    ScrollLock = "ScrollLock",
+}
+
+enum OsdPosition {
+    Off = "Off",
+    Caret = "Caret",
+    TopLeft = "TopLeft",
+    TopRight = "TopRight",
+    BottomLeft = "BottomLeft",
+    BottomRight = "BottomRight",
+}
+
+enum SoundTheme {
+    Chime = "Chime",
+    Click = "Click",
+}
+
+enum LogLevel {
+    Off = "Off",
+    Error = "Error",
+    Warn = "Warn",
+    Info = "Info",
+    Debug = "Debug",
+    Trace = "Trace",
+}
+
 interface Settings {
     runOnStartup: boolean;
     hideToTray: boolean;
     toggleShortcut: ToggleShortcut;
+    wordBoundaries: string;
+    apostropheElision: boolean;
+    replacementMode: ReplacementMode;
+    physicalLayout: PhysicalLayout;
+    triggerRules: TriggerRule[];
+    bypassKey: BypassKey;
+    bypassMode: BypassMode;
+    undoKey: UndoKey;
+    excludedApps: string[];
+    accentCompletion: boolean;
+    accentSuggestions: boolean;
+    accentPreferences: string[];
+    osdPosition: OsdPosition;
+    osdDurationMs: number;
+    osdOpacity: number;
+    languageSound: boolean;
+    combinationSound: boolean;
+    soundTheme: SoundTheme;
+    soundVolume: number;
+    logLevel: LogLevel;
+    logTypedText: boolean;
 }
 
 const toggleOptions = [
@@ -22,30 +107,200 @@ const toggleOptions = [
     // ToggleShortcut.AltZ
 ];
 
+const layoutOptions = [
+    PhysicalLayout.Qwerty,
+    PhysicalLayout.Azerty,
+    PhysicalLayout.Qwertz,
+];
+
+// Set 1 scan codes of the letter keys, named after what they type on QWERTY. Each row
+// of keys has consecutive scan codes
+const letterScanCodes = new Map(
+    (
+        [
+            ["QWERTYUIOP", 0x10],
+            ["ASDFGHJKL", 0x1e],
+            ["ZXCVBNM", 0x2c],
+        ] as const
+    ).flatMap(([row, first]) => [...row].map((letter, i) => [letter, first + i] as const)),
+);
+
+const ruleKeyOptions = [...letterScanCodes.keys()];
+
+const ruleCharOptions = ruleKeyOptions.map((letter) => letter.toLowerCase());
+
+const describeRule = (rule: TriggerRule) => {
+    if ("char" in rule.trigger) {
+        return `${rule.trigger.char} → ${rule.engineChar}`;
+    }
+    const scanCode = rule.trigger.scanCode;
+    const key = ruleKeyOptions.find((letter) => letterScanCodes.get(letter) === scanCode);
+    return `QWERTY ${key ?? scanCode} → ${rule.engineChar}`;
+};
+
+const bypassKeyOptions = [BypassKey.Escape, BypassKey.ScrollLock, BypassKey.Pause];
+
+const bypassModeOptions = [
+    BypassMode.Off,
+    BypassMode.NextKey,
+    BypassMode.NextWord,
+    BypassMode.Hold,
+];
+
+const undoKeyOptions = [UndoKey.Off, UndoKey.CtrlBackspace, UndoKey.Pause, UndoKey.ScrollLock];
+
+const osdPositionOptions = [
+    OsdPosition.Off,
+    OsdPosition.Caret,
+    OsdPosition.TopLeft,
+    OsdPosition.TopRight,
+    OsdPosition.BottomLeft,
+    OsdPosition.BottomRight,
+];
+
+// Milliseconds
+const osdDurationOptions = ["600", "1200", "2000", "3000"];
+
+// Percent
+const osdOpacityOptions = ["100", "90", "75", "50"];
+
+const soundThemeOptions = [SoundTheme.Chime, SoundTheme.Click];
+
+// Percent
+const soundVolumeOptions = ["100", "80", "60", "40", "20"];
+
+const logLevelOptions = [
+    LogLevel.Off,
+    LogLevel.Error,
+    LogLevel.Warn,
+    LogLevel.Info,
+    LogLevel.Debug,
+    LogLevel.Trace,
+];
+
 const Settings: Component<{}> = () => {
     const [initialSettings, setInitialSettings] = createSignal<Settings>({
         runOnStartup: false,
         hideToTray: false,
         toggleShortcut: toggleOptions[0],
+        wordBoundaries: ".,;:!?()[]{}\"/-",
+        apostropheElision: true,
+        replacementMode: ReplacementMode.Retype,
+        physicalLayout: PhysicalLayout.Qwerty,
+        triggerRules: [],
+        bypassKey: BypassKey.Escape,
+        bypassMode: BypassMode.Off,
+        undoKey: UndoKey.Off,
+        excludedApps: [],
+        accentCompletion: false,
+        accentSuggestions: false,
+        accentPreferences: [],
+        osdPosition: OsdPosition.Off,
+        osdDurationMs: 1200,
+        osdOpacity: 90,
+        languageSound: false,
+        combinationSound: false,
+        soundTheme: SoundTheme.Chime,
+        soundVolume: 60,
+        logLevel: LogLevel.Info,
+        logTypedText: false,
     });
     const [runOnStartUp, setRunOnStartUp] = createSignal(false);
     const [hideToTray, setHideToTray] = createSignal(false);
     const [toggleOption, setToggleOption] = createSignal(toggleOptions[0]);
+    const [swallowKeys, setSwallowKeys] = createSignal(false);
+    const [wordBoundaries, setWordBoundaries] = createSignal(".,;:!?()[]{}\"/-");
+    const [apostropheElision, setApostropheElision] = createSignal(true);
+    const [layoutOption, setLayoutOption] = createSignal(layoutOptions[0]);
+    const [triggerRules, setTriggerRules] = createSignal<TriggerRule[]>([]);
+    const [ruleKey, setRuleKey] = createSignal(ruleKeyOptions[0]);
+    const [ruleChar, setRuleChar] = createSignal(ruleCharOptions[0]);
+    const [bypassKey, setBypassKey] = createSignal(bypassKeyOptions[0]);
+    const [bypassMode, setBypassMode] = createSignal(bypassModeOptions[0]);
+    const [undoKey, setUndoKey] = createSignal(undoKeyOptions[0]);
+    const [excludedApps, setExcludedApps] = createSignal<string[]>([]);
+    const [newExcludedApp, setNewExcludedApp] = createSignal("");
+    const [accentCompletion, setAccentCompletion] = createSignal(false);
+    const [accentSuggestions, setAccentSuggestions] = createSignal(false);
+    const [accentPreferences, setAccentPreferences] = createSignal<string[]>([]);
+    const [newAccentPreference, setNewAccentPreference] = createSignal("");
+    const [osdPosition, setOsdPosition] = createSignal(osdPositionOptions[0]);
+    const [osdDuration, setOsdDuration] = createSignal(osdDurationOptions[1]);
+    const [osdOpacity, setOsdOpacity] = createSignal(osdOpacityOptions[1]);
+    const [languageSound, setLanguageSound] = createSignal(false);
+    const [combinationSound, setCombinationSound] = createSignal(false);
+    const [soundTheme, setSoundTheme] = createSignal(soundThemeOptions[0]);
+    const [soundVolume, setSoundVolume] = createSignal(soundVolumeOptions[2]);
+    const [logLevel, setLogLevel] = createSignal(logLevelOptions[3]);
+    const [logTypedText, setLogTypedText] = createSignal(false);
     const [settingsChanged, setSettingsChanged] = createSignal(false);
 
-    createEffect(async () => {
+    const loadSettings = async () => {
         const settings = await invoke<Settings>("get_settings_command");
         setInitialSettings(settings);
         setRunOnStartUp(settings.runOnStartup);
         setHideToTray(settings.hideToTray);
         setToggleOption(settings.toggleShortcut);
+        setSwallowKeys(settings.replacementMode === ReplacementMode.Swallow);
+        setWordBoundaries(settings.wordBoundaries);
+        setApostropheElision(settings.apostropheElision);
+        setLayoutOption(settings.physicalLayout);
+        setTriggerRules(settings.triggerRules);
+        setBypassKey(settings.bypassKey);
+        setBypassMode(settings.bypassMode);
+        setUndoKey(settings.undoKey);
+        setExcludedApps(settings.excludedApps);
+        setAccentCompletion(settings.accentCompletion);
+        setAccentSuggestions(settings.accentSuggestions);
+        setAccentPreferences(settings.accentPreferences);
+        setOsdPosition(settings.osdPosition);
+        setOsdDuration(settings.osdDurationMs.toString());
+        setOsdOpacity(settings.osdOpacity.toString());
+        setLanguageSound(settings.languageSound);
+        setCombinationSound(settings.combinationSound);
+        setSoundTheme(settings.soundTheme);
+        setSoundVolume(settings.soundVolume.toString());
+        setLogLevel(settings.logLevel);
+        setLogTypedText(settings.logTypedText);
+        setSettingsChanged(false);
+    };
+
+    createEffect(loadSettings);
+
+    // The tray menu changes settings too
+    const unlisten = listen("settings_changed", loadSettings);
+
+    onCleanup(() => {
+        unlisten.then((f) => f());
     });
 
     const saveSettings = () => {
         const settings = {
+            ...initialSettings(),
             runOnStartup: runOnStartUp(),
             hideToTray: hideToTray(),
             toggleShortcut: toggleOption(),
+            replacementMode: swallowKeys() ? ReplacementMode.Swallow : ReplacementMode.Retype,
+            wordBoundaries: wordBoundaries(),
+            apostropheElision: apostropheElision(),
+            physicalLayout: layoutOption(),
+            triggerRules: triggerRules(),
+            bypassKey: bypassKey(),
+            bypassMode: bypassMode(),
+            undoKey: undoKey(),
+            excludedApps: excludedApps(),
+            accentCompletion: accentCompletion(),
+            accentSuggestions: accentSuggestions(),
+            accentPreferences: accentPreferences(),
+            osdPosition: osdPosition(),
+            osdDurationMs: Number(osdDuration()),
+            osdOpacity: Number(osdOpacity()),
+            languageSound: languageSound(),
+            combinationSound: combinationSound(),
+            soundTheme: soundTheme(),
+            soundVolume: Number(soundVolume()),
+            logLevel: logLevel(),
+            logTypedText: logTypedText(),
         };
         invoke("set_settings_command", { settings });
         setInitialSettings(settings);
@@ -57,6 +312,27 @@ const Settings: Component<{}> = () => {
         setRunOnStartUp(intial.runOnStartup);
         setHideToTray(intial.hideToTray);
         setToggleOption(intial.toggleShortcut);
+        setSwallowKeys(intial.replacementMode === ReplacementMode.Swallow);
+        setWordBoundaries(intial.wordBoundaries);
+        setApostropheElision(intial.apostropheElision);
+        setLayoutOption(intial.physicalLayout);
+        setTriggerRules(intial.triggerRules);
+        setBypassKey(intial.bypassKey);
+        setBypassMode(intial.bypassMode);
+        setUndoKey(intial.undoKey);
+        setExcludedApps(intial.excludedApps);
+        setAccentCompletion(intial.accentCompletion);
+        setAccentSuggestions(intial.accentSuggestions);
+        setAccentPreferences(intial.accentPreferences);
+        setOsdPosition(intial.osdPosition);
+        setOsdDuration(intial.osdDurationMs.toString());
+        setOsdOpacity(intial.osdOpacity.toString());
+        setLanguageSound(intial.languageSound);
+        setCombinationSound(intial.combinationSound);
+        setSoundTheme(intial.soundTheme);
+        setSoundVolume(intial.soundVolume.toString());
+        setLogLevel(intial.logLevel);
+        setLogTypedText(intial.logTypedText);
         setSettingsChanged(false);
     };
 
@@ -70,11 +346,151 @@ const Settings: Component<{}> = () => {
         setSettingsChanged(true);
     };
 
+    const toggleSwallowKeys = () => {
+        setSwallowKeys(!swallowKeys());
+        setSettingsChanged(true);
+    };
+
+    const toggleAccentCompletion = () => {
+        setAccentCompletion(!accentCompletion());
+        setSettingsChanged(true);
+    };
+
+    const toggleAccentSuggestions = () => {
+        setAccentSuggestions(!accentSuggestions());
+        setSettingsChanged(true);
+    };
+
+    const toggleLanguageSound = () => {
+        setLanguageSound(!languageSound());
+        setSettingsChanged(true);
+    };
+
+    const toggleCombinationSound = () => {
+        setCombinationSound(!combinationSound());
+        setSettingsChanged(true);
+    };
+
+    const onChangeWordBoundaries = (value: string) => {
+        setWordBoundaries(value);
+        setSettingsChanged(true);
+    };
+
+    const toggleApostropheElision = () => {
+        setApostropheElision(!apostropheElision());
+        setSettingsChanged(true);
+    };
+
     const onChangeToggleOption = (option: string) => {
         setToggleOption(option as ToggleShortcut);
         setSettingsChanged(true);
     };
 
+    const onChangeLayoutOption = (option: string) => {
+        setLayoutOption(option as PhysicalLayout);
+        setSettingsChanged(true);
+    };
+
+    const addTriggerRule = () => {
+        const scanCode = letterScanCodes.get(ruleKey())!;
+        // A later rule for the same key would never apply
+        const others = triggerRules().filter(
+            (rule) => !("scanCode" in rule.trigger && rule.trigger.scanCode === scanCode),
+        );
+        setTriggerRules([...others, { trigger: { scanCode }, engineChar: ruleChar() }]);
+        setSettingsChanged(true);
+    };
+
+    const removeTriggerRule = (index: number) => {
+        setTriggerRules(triggerRules().filter((_, i) => i !== index));
+        setSettingsChanged(true);
+    };
+
+    const onChangeBypassKey = (option: string) => {
+        setBypassKey(option as BypassKey);
+        setSettingsChanged(true);
+    };
+
+    const onChangeBypassMode = (option: string) => {
+        setBypassMode(option as BypassMode);
+        setSettingsChanged(true);
+    };
+
+    const onChangeUndoKey = (option: string) => {
+        setUndoKey(option as UndoKey);
+        setSettingsChanged(true);
+    };
+
+    const addExcludedApp = () => {
+        const app = newExcludedApp().trim();
+        // Executable names are matched ignoring case
+        const listed = excludedApps().some((other) => other.toLowerCase() === app.toLowerCase());
+        if (app !== "" && !listed) {
+            setExcludedApps([...excludedApps(), app]);
+            setSettingsChanged(true);
+        }
+        setNewExcludedApp("");
+    };
+
+    const removeExcludedApp = (index: number) => {
+        setExcludedApps(excludedApps().filter((_, i) => i !== index));
+        setSettingsChanged(true);
+    };
+
+    const addAccentPreference = () => {
+        // Matched lowercase by the engine
+        const word = newAccentPreference().trim().toLowerCase();
+        if (word !== "" && !accentPreferences().includes(word)) {
+            setAccentPreferences([...accentPreferences(), word]);
+            setSettingsChanged(true);
+        }
+        setNewAccentPreference("");
+    };
+
+    const removeAccentPreference = (index: number) => {
+        setAccentPreferences(accentPreferences().filter((_, i) => i !== index));
+        setSettingsChanged(true);
+    };
+
+    const onChangeOsdPosition = (option: string) => {
+        setOsdPosition(option as OsdPosition);
+        setSettingsChanged(true);
+    };
+
+    const onChangeOsdDuration = (option: string) => {
+        setOsdDuration(option);
+        setSettingsChanged(true);
+    };
+
+    const onChangeOsdOpacity = (option: string) => {
+        setOsdOpacity(option);
+        setSettingsChanged(true);
+    };
+
+    const onChangeSoundTheme = (option: string) => {
+        setSoundTheme(option as SoundTheme);
+        setSettingsChanged(true);
+    };
+
+    const onChangeSoundVolume = (option: string) => {
+        setSoundVolume(option);
+        setSettingsChanged(true);
+    };
+
+    const onChangeLogLevel = (option: string) => {
+        setLogLevel(option as LogLevel);
+        setSettingsChanged(true);
+    };
+
+    const toggleLogTypedText = () => {
+        setLogTypedText(!logTypedText());
+        setSettingsChanged(true);
+    };
+
+    const openLogsFolder = () => {
+        invoke("open_logs_folder_command");
+    };
+
     return (
         <section class={styles.section}>
             <h2 class={styles.title}>Settings</h2>
@@ -91,12 +507,166 @@ const Settings: Component<{}> = () => {
                         onToggle={toggleHideToTray}
                         checked={hideToTray()}
                     />
+                    <ToggleSwitch
+                        name="Replace keys without retyping"
+                        onToggle={toggleSwallowKeys}
+                        checked={swallowKeys()}
+                    />
+                    <TextField
+                        name="Word boundaries"
+                        value={wordBoundaries()}
+                        onchange={onChangeWordBoundaries}
+                    />
+                    <ToggleSwitch
+                        name="Apostrophe ends the word (l'été)"
+                        onToggle={toggleApostropheElision}
+                        checked={apostropheElision()}
+                    />
                     <Selection
                         name="Toggle language"
                         value={toggleOption()}
                         onchange={onChangeToggleOption}
                         options={toggleOptions}
                     />
+                    <Selection
+                        name="Keyboard"
+                        value={layoutOption()}
+                        onchange={onChangeLayoutOption}
+                        options={layoutOptions}
+                    />
+                    <ListField
+                        name="Keys typing accents (by QWERTY position)"
+                        items={triggerRules().map(describeRule)}
+                        onremove={removeTriggerRule}
+                    >
+                        <Selection
+                            name="Key"
+                            value={ruleKey()}
+                            onchange={setRuleKey}
+                            options={ruleKeyOptions}
+                        />
+                        <Selection
+                            name="Types"
+                            value={ruleChar()}
+                            onchange={setRuleChar}
+                            options={ruleCharOptions}
+                        />
+                        <RoundedButton onClick={addTriggerRule} text="Add" variant="secondary" />
+                    </ListField>
+                    <Selection
+                        name="Bypass"
+                        value={bypassMode()}
+                        onchange={onChangeBypassMode}
+                        options={bypassModeOptions}
+                    />
+                    <Selection
+                        name="Bypass key"
+                        value={bypassKey()}
+                        onchange={onChangeBypassKey}
+                        options={bypassKeyOptions}
+                    />
+                    <Selection
+                        name="Undo combination"
+                        value={undoKey()}
+                        onchange={onChangeUndoKey}
+                        options={undoKeyOptions}
+                    />
+                    <ListField
+                        name="Apps left alone"
+                        items={excludedApps()}
+                        onremove={removeExcludedApp}
+                    >
+                        <TextField
+                            name="Executable (notepad.exe)"
+                            value={newExcludedApp()}
+                            onchange={setNewExcludedApp}
+                        />
+                        <RoundedButton onClick={addExcludedApp} text="Add" variant="secondary" />
+                    </ListField>
+                    <ToggleSwitch
+                        name="Complete accents from the French word list"
+                        onToggle={toggleAccentCompletion}
+                        checked={accentCompletion()}
+                    />
+                    <ToggleSwitch
+                        name="Suggest spellings of ambiguous words"
+                        onToggle={toggleAccentSuggestions}
+                        checked={accentSuggestions()}
+                    />
+                    <ListField
+                        name="Preferred spellings (où, là)"
+                        items={accentPreferences()}
+                        onremove={removeAccentPreference}
+                    >
+                        <TextField
+                            name="Word"
+                            value={newAccentPreference()}
+                            onchange={setNewAccentPreference}
+                        />
+                        <RoundedButton
+                            onClick={addAccentPreference}
+                            text="Add"
+                            variant="secondary"
+                        />
+                    </ListField>
+                    <Selection
+                        name="Language indicator"
+                        value={osdPosition()}
+                        onchange={onChangeOsdPosition}
+                        options={osdPositionOptions}
+                    />
+                    <Selection
+                        name="Indicator duration (ms)"
+                        value={osdDuration()}
+                        onchange={onChangeOsdDuration}
+                        options={osdDurationOptions}
+                    />
+                    <Selection
+                        name="Indicator opacity (%)"
+                        value={osdOpacity()}
+                        onchange={onChangeOsdOpacity}
+                        options={osdOpacityOptions}
+                    />
+                    <ToggleSwitch
+                        name="Sound when the language changes"
+                        onToggle={toggleLanguageSound}
+                        checked={languageSound()}
+                    />
+                    <ToggleSwitch
+                        name="Sound when a combination is applied"
+                        onToggle={toggleCombinationSound}
+                        checked={combinationSound()}
+                    />
+                    <Selection
+                        name="Sound"
+                        value={soundTheme()}
+                        onchange={onChangeSoundTheme}
+                        options={soundThemeOptions}
+                    />
+                    <Selection
+                        name="Volume (%)"
+                        value={soundVolume()}
+                        onchange={onChangeSoundVolume}
+                        options={soundVolumeOptions}
+                    />
+                    <Selection
+                        name="Log level"
+                        value={logLevel()}
+                        onchange={onChangeLogLevel}
+                        options={logLevelOptions}
+                    />
+                    <ToggleSwitch
+                        name="Log typed text"
+                        onToggle={toggleLogTypedText}
+                        checked={logTypedText()}
+                    />
+                    <span>
+                        <RoundedButton
+                            onClick={openLogsFolder}
+                            text="Open logs folder"
+                            variant="secondary"
+                        />
+                    </span>
                     <span>
                         <RoundedButton
                             disable={!settingsChanged()}
@@ -0,0 +1,118 @@
+import { Component, createEffect, createSignal, For, onCleanup } from "solid-js";
+import { invoke } from "@tauri-apps/api/core";
+import Panel from "../Panel/Panel";
+import styles from "./stats.module.css";
+
+interface UsageCounts {
+    combinations: Record<string, number>;
+    reverts: number;
+    undos: number;
+    snippets: number;
+    accentCompletions: number;
+    toggles: number;
+    frenchSecs: number;
+    englishSecs: number;
+}
+
+interface Stats {
+    total: UsageCounts;
+    days: Record<string, UsageCounts>;
+}
+
+// Days shown in the daily totals
+const RECENT_DAYS = 7;
+const REFRESH_MS = 30000;
+
+const formatDuration = (secs: number) => {
+    const hours = Math.floor(secs / 3600);
+    const minutes = Math.floor((secs % 3600) / 60);
+    return hours > 0 ? `${hours} h ${minutes} min` : `${minutes} min`;
+};
+
+const combinationCount = (counts: UsageCounts) =>
+    Object.values(counts.combinations).reduce((sum, count) => sum + count, 0);
+
+// Counted on this computer only, none of the text typed is kept
+const Stats: Component<{}> = () => {
+    const [stats, setStats] = createSignal<Stats | undefined>(undefined);
+
+    const loadStats = async () => {
+        setStats(await invoke<Stats>("get_stats_command"));
+    };
+
+    createEffect(loadStats);
+
+    const timer = setInterval(loadStats, REFRESH_MS);
+
+    onCleanup(() => {
+        clearInterval(timer);
+    });
+
+    const accents = () =>
+        Object.entries(stats()?.total.combinations ?? {}).sort((a, b) => b[1] - a[1]);
+
+    const recentDays = () =>
+        Object.entries(stats()?.days ?? {})
+            .sort((a, b) => b[0].localeCompare(a[0]))
+            .slice(0, RECENT_DAYS);
+
+    return (
+        <section class={styles.section}>
+            <h2 class={styles.title}>Statistics</h2>
+            <Panel maxWidth={500}>
+                <div class={styles.container}>
+                    <div class={styles.row}>
+                        <span>Time in French</span>
+                        <span>{formatDuration(stats()?.total.frenchSecs ?? 0)}</span>
+                    </div>
+                    <div class={styles.row}>
+                        <span>Time in English</span>
+                        <span>{formatDuration(stats()?.total.englishSecs ?? 0)}</span>
+                    </div>
+                    <div class={styles.row}>
+                        <span>Language switches</span>
+                        <span>{stats()?.total.toggles ?? 0}</span>
+                    </div>
+                    <div class={styles.row}>
+                        <span>Combinations reverted or undone</span>
+                        <span>{(stats()?.total.reverts ?? 0) + (stats()?.total.undos ?? 0)}</span>
+                    </div>
+                    <div class={styles.row}>
+                        <span>Snippets expanded</span>
+                        <span>{stats()?.total.snippets ?? 0}</span>
+                    </div>
+                    <div class={styles.row}>
+                        <span>Words accented from the word list</span>
+                        <span>{stats()?.total.accentCompletions ?? 0}</span>
+                    </div>
+
+                    <h3 class={styles.subtitle}>Characters typed</h3>
+                    <div class={styles.accents}>
+                        <For each={accents()}>
+                            {([accent, count]) => (
+                                <span class={styles.accent}>
+                                    {accent} <span class={styles.count}>{count}</span>
+                                </span>
+                            )}
+                        </For>
+                    </div>
+
+                    <h3 class={styles.subtitle}>Last days</h3>
+                    <For each={recentDays()}>
+                        {([day, counts]) => (
+                            <div class={styles.row}>
+                                <span>{day}</span>
+                                <span class={styles.count}>
+                                    {combinationCount(counts)} combinations,{" "}
+                                    {formatDuration(counts.frenchSecs)} in French
+                                </span>
+                            </div>
+                        )}
+                    </For>
+                </div>
+            </Panel>
+        </section>
+    );
+};
+
+export default Stats;
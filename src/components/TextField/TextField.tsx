@@ -0,0 +1,25 @@
+import { Component } from "solid-js";
+import styles from "./text-field.module.css";
+
+interface TextFieldProps {
+    name: string;
+    value: string;
+    onchange: (value: string) => void;
+}
+
+const TextField: Component<TextFieldProps> = (props) => {
+    return (
+        <label class={styles.container}>
+            {props.name}
+            <input
+                class={styles.input}
+                type="text"
+                spellcheck={false}
+                value={props.value}
+                oninput={(e) => props.onchange(e.currentTarget.value)}
+            />
+        </label>
+    );
+};
+
+export default TextField;
@@ -1,5 +1,16 @@
 /* @refresh reload */
 import { render } from "solid-js/web";
+import { getCurrentWindow } from "@tauri-apps/api/window";
 import App from "./App";
+import Osd from "./components/Osd/Osd";
+import Candidates from "./components/Candidates/Candidates";
 
-render(() => <App />, document.getElementById("root") as HTMLElement);
+const root = document.getElementById("root") as HTMLElement;
+
+if (getCurrentWindow().label === "osd") {
+    render(() => <Osd />, root);
+} else if (getCurrentWindow().label === "candidates") {
+    render(() => <Candidates />, root);
+} else {
+    render(() => <App />, root);
+}
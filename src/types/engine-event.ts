@@ -0,0 +1,39 @@
+// Mirrors EngineEvent and EngineStatus in src-tauri/src/window_engine/engine_event.rs
+
+export enum CombinationKind {
+    Replace = "Replace",
+    Combine = "Combine",
+    Revert = "Revert",
+}
+
+export enum EngineStatus {
+    Stopped = "Stopped",
+    Running = "Running",
+    Crashed = "Crashed",
+    HookFailed = "HookFailed",
+    Recovering = "Recovering",
+}
+
+export type EngineEvent =
+    | { type: "started" }
+    | { type: "stopped" }
+    | { type: "crashed"; message: string }
+    | { type: "hookInstalled" }
+    | { type: "hookFailed"; message: string }
+    | { type: "hookLost" }
+    | { type: "restarting"; delayMs: number }
+    | { type: "buffer"; content: string }
+    | { type: "combination"; kind: CombinationKind; output: string }
+    | { type: "undo"; output: string }
+    | { type: "snippet"; output: string }
+    | { type: "accentCompletion"; output: string }
+    | { type: "candidatePicked" }
+    | { type: "candidates"; candidates: string[] }
+    | { type: "secureInput"; active: boolean }
+    | {
+          type: "latency";
+          samples: number;
+          p50Micros: number;
+          p95Micros: number;
+          maxMicros: number;
+      };